@@ -0,0 +1,69 @@
+//! Benchmarks comparing the native FOV algorithms with libtcod's diamond raycasting.
+//!
+//! Run with `make bench`, which requires a nightly toolchain.
+
+#![feature(test)]
+
+extern crate taurus;
+extern crate tcod;
+extern crate test;
+
+use taurus::coord::Coord;
+use taurus::fov::{FovAlgorithm, FovMap};
+use tcod::map::{FovAlgorithm as TcodFovAlgorithm, Map};
+use test::Bencher;
+
+const WIDTH: i32 = 80;
+const HEIGHT: i32 = 50;
+const RADIUS: i32 = 16;
+
+// Returns true if the tile at `coord` of the benchmark grid lets light through.
+// The grid is open floor scattered with pillars and bordered by walls.
+fn transparent(coord: Coord) -> bool {
+    let Coord { x, y } = coord;
+    x > 0 && y > 0 && x < WIDTH - 1 && y < HEIGHT - 1 && (x * 7 + y * 13) % 11 != 0
+}
+
+fn origin() -> Coord {
+    Coord::new(WIDTH / 2, HEIGHT / 2)
+}
+
+fn bench_native(b: &mut Bencher, algorithm: FovAlgorithm) {
+    b.iter(|| FovMap::compute(algorithm, origin(), RADIUS, transparent));
+}
+
+#[bench]
+fn shadowcasting(b: &mut Bencher) {
+    bench_native(b, FovAlgorithm::Shadowcasting);
+}
+
+#[bench]
+fn symmetric_shadowcasting(b: &mut Bencher) {
+    bench_native(b, FovAlgorithm::SymmetricShadowcasting);
+}
+
+#[bench]
+fn diamond_walls(b: &mut Bencher) {
+    bench_native(b, FovAlgorithm::DiamondWalls);
+}
+
+// The previous implementation: a tcod map covering the view around the origin, refilled and
+// recomputed on every call.
+#[bench]
+fn tcod_diamond(b: &mut Bencher) {
+    let side = RADIUS * 2 + 1;
+    let start = origin() - Coord::new(RADIUS, RADIUS);
+    let mut map = Map::new(side, side);
+
+    b.iter(|| {
+        map.clear(false, false);
+        for x in 0..side {
+            for y in 0..side {
+                let coord = start + Coord::new(x, y);
+                map.set(x, y, transparent(coord), false);
+            }
+        }
+        map.compute_fov(RADIUS, RADIUS, RADIUS, true, TcodFovAlgorithm::Diamond);
+        map.is_in_fov(RADIUS, RADIUS)
+    });
+}
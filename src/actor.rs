@@ -1,25 +1,37 @@
 //! Game actors.
 
+use crate::ai;
 use crate::console::{Color, DrawConsole};
+use crate::constants;
 use crate::coord::Coord;
 use crate::defs::*;
 use crate::dungeon::{ActResult, Dungeon};
+use crate::equipment::{EquipSlot, Equipment, Modifiers};
+use crate::error::err_convert;
+use crate::fov::FovMap;
 use crate::game_data::GameData;
-use crate::object::ObjectType;
+use crate::item::{Item, ItemCategory, ItemEffect, ItemStack, ItemStash};
+use crate::name_gen::name_gen;
+use crate::object::{ObjectType, TrapEffect};
 use crate::player;
+use crate::save::{coord_to_value, get_coord};
 use crate::ui::Draw;
 use crate::util::direction::CompassDirection;
+use crate::util::rand::{dice, rand_int};
 use crate::{GameError, GameResult, GAMEDATA};
 use failure::ResultExt;
 use over::Obj;
 use std::cell::RefCell;
 use std::cmp::Ordering;
+use std::fmt;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
 #[derive(Debug)]
 pub struct ActorInner {
     pub name: String, // Generic name.
+    /// Personal name, such as that of a townsperson.
+    pub given_name: Option<String>,
 
     pub c: char,
     pub color: Color,
@@ -27,17 +39,28 @@ pub struct ActorInner {
     pub coord: Coord, // Coordinate location in level.
     pub turn: GameRatio,
     pub speed: GameRatio,
+    /// Time taken by the current action as a multiple of `speed`. Reset after every turn.
+    pub action_cost: GameRatio,
 
     // STATS
     pub hp_cur: i32, // Current health. This value can be negative!
     pub hp_max: u32,
     pub fov_radius: u32,
+    // Base values, before equipment modifiers.
+    pub attack: u32,  // Chance to hit in melee.
+    pub defense: u32, // Chance to avoid being hit in melee.
+    pub damage: u32,  // Maximum melee damage.
 
     // COMBAT STATE
     pub visible: bool,
 
+    // ITEMS
+    pub inventory: ItemStash,
+    pub equipment: Equipment,
+
     // AI ATTRIBUTES
     pub behavior: Behavior,
+    pub target: Option<Coord>, // Where the player was last seen.
 }
 
 /// Actor object.
@@ -57,6 +80,12 @@ impl Actor {
         // Load all data from the database.
 
         let name = data.get_str("name")?;
+        // Actors with a `name_profile` get a name of their own.
+        let given_name = if data.get("name_profile").is_some() {
+            Some(name_gen(&data.get_obj("name_profile")?)?)
+        } else {
+            None
+        };
         let c = data.get_char("c")?;
         let color = Color::from_str(data.get_str("color")?.as_str())?;
 
@@ -66,16 +95,32 @@ impl Actor {
         let hp_cur = hp as i32;
         let hp_max = hp;
         let fov_radius = big_to_u32(data.get_int("fov_radius")?)?;
+        let attack = big_to_u32(data.get_int("attack")?)?;
+        let defense = big_to_u32(data.get_int("defense")?)?;
+        let damage = big_to_u32(data.get_int("damage")?)?;
 
         let visible = data.get_bool("visible")?;
 
         let behavior = Behavior::from_str(data.get_str("behavior")?.as_str())?;
 
+        // Starting equipment is optional and given as a list of item keys.
+        let mut equipment = Equipment::default();
+        if data.get("equipment").is_some() {
+            for key in data.get_arr("equipment")?.iter() {
+                let item = Item::from_key(&key.get_str()?)?;
+                if item.slot().is_none() {
+                    return err_convert(item.key(), "Starting equipment has no slot");
+                }
+                equipment.equip(item);
+            }
+        }
+
         // Create the actor instance.
 
         let mut actor = Actor {
             inner: Arc::new(Mutex::new(ActorInner {
                 name,
+                given_name,
                 c,
                 color,
 
@@ -83,14 +128,22 @@ impl Actor {
                 // We update this after creating the actor.
                 turn: GAMEDATA.read().unwrap().turn(),
                 speed,
+                action_cost: GameRatio::from_integer(1),
 
                 hp_cur,
                 hp_max,
                 fov_radius,
+                attack,
+                defense,
+                damage,
 
                 visible,
 
+                inventory: ItemStash::default(),
+                equipment,
+
                 behavior,
+                target: None,
             })),
         };
         actor.update_turn(); // Set the actor's turn.
@@ -98,6 +151,88 @@ impl Actor {
         Ok(actor)
     }
 
+    /// Restores an actor from data written by `to_save_obj`.
+    pub fn from_save_obj(data: &Obj) -> GameResult<Actor> {
+        let color = Color::from_str(&data.get_str("color")?)?;
+        let behavior = Behavior::from_str(&data.get_str("behavior")?)?;
+        let target = if data.contains("target") {
+            Some(get_coord(data, "target")?)
+        } else {
+            None
+        };
+        let given_name = if data.contains("given_name") {
+            Some(data.get_str("given_name")?)
+        } else {
+            None
+        };
+
+        Ok(Actor {
+            inner: Arc::new(Mutex::new(ActorInner {
+                name: data.get_str("name")?,
+                given_name,
+                c: data.get_char("c")?,
+                color,
+
+                coord: get_coord(data, "coord")?,
+                turn: bigr_to_gamer(data.get_frac("turn")?)?,
+                speed: bigr_to_gamer(data.get_frac("speed")?)?,
+                action_cost: GameRatio::from_integer(1),
+
+                hp_cur: big_to_i32(data.get_int("hp_cur")?)?,
+                hp_max: big_to_u32(data.get_int("hp_max")?)?,
+                fov_radius: big_to_u32(data.get_int("fov_radius")?)?,
+                attack: big_to_u32(data.get_int("attack")?)?,
+                defense: big_to_u32(data.get_int("defense")?)?,
+                damage: big_to_u32(data.get_int("damage")?)?,
+
+                visible: data.get_bool("visible")?,
+
+                inventory: ItemStash::from_save_arr(&data.get_arr("inventory")?)?,
+                equipment: Equipment::from_save_arr(&data.get_arr("equipment")?)?,
+
+                behavior,
+                target,
+            })),
+        })
+    }
+
+    /// Returns the full state of this actor for saving.
+    pub fn to_save_obj(&self) -> GameResult<Obj> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut map = map! {
+            "name".into() => inner.name.clone().into(),
+            "c".into() => inner.c.into(),
+            "color".into() => inner.color.to_code().into(),
+
+            "coord".into() => coord_to_value(inner.coord),
+            "turn".into() => gamer_to_bigr(inner.turn).into(),
+            "speed".into() => gamer_to_bigr(inner.speed).into(),
+
+            "hp_cur".into() => inner.hp_cur.into(),
+            "hp_max".into() => inner.hp_max.into(),
+            "fov_radius".into() => inner.fov_radius.into(),
+            "attack".into() => inner.attack.into(),
+            "defense".into() => inner.defense.into(),
+            "damage".into() => inner.damage.into(),
+
+            "visible".into() => inner.visible.into(),
+
+            "inventory".into() => inner.inventory.to_save_arr()?.into(),
+            "equipment".into() => inner.equipment.to_save_arr()?.into(),
+
+            "behavior".into() => inner.behavior.to_string().into(),
+        };
+        if let Some(target) = inner.target {
+            map.insert("target".into(), coord_to_value(target));
+        }
+        if let Some(ref given_name) = inner.given_name {
+            map.insert("given_name".into(), given_name.clone().into());
+        }
+
+        Ok(Obj::from_map_unchecked(map))
+    }
+
     pub fn insert_new(dungeon: &mut Dungeon, coord: Coord, actor_data: &Obj) -> GameResult<()> {
         let a = Self::new(coord, actor_data)
             .context(format!("Could not load actor:\n{}", actor_data))?;
@@ -110,6 +245,23 @@ impl Actor {
         self.inner.lock().unwrap().name.clone()
     }
 
+    /// Returns the personal name of this actor, if it has one.
+    pub fn given_name(&self) -> Option<String> {
+        self.inner.lock().unwrap().given_name.clone()
+    }
+
+    /// Returns the name of this actor as it appears in messages, e.g. "the goblin", "Brandor" or
+    /// "you".
+    pub fn the_name(&self) -> String {
+        if self.behavior() == Behavior::Player {
+            "you".to_string()
+        } else if let Some(given_name) = self.given_name() {
+            given_name
+        } else {
+            format!("the {}", self.name())
+        }
+    }
+
     /// Generates and returns the description of this actor.
     pub fn description(&self) -> String {
         // TODO
@@ -131,11 +283,22 @@ impl Actor {
         self.inner.lock().unwrap().turn
     }
 
-    /// Updates this actor's turn based on its speed.
+    /// Sets this actor's next turn value.
+    pub fn set_turn(&mut self, turn: GameRatio) {
+        self.inner.lock().unwrap().turn = turn;
+    }
+
+    /// Updates this actor's turn based on its speed and the cost of the action it just took.
     pub fn update_turn(&mut self) {
         let mut inner = self.inner.lock().unwrap();
-        let (turn, speed) = (inner.turn, inner.speed);
-        inner.turn = turn + speed;
+        let (turn, speed, cost) = (inner.turn, inner.speed, inner.action_cost);
+        inner.turn = turn + speed * cost;
+        inner.action_cost = GameRatio::from_integer(1);
+    }
+
+    /// Makes the current action take `cost` times as long as usual.
+    pub fn set_action_cost(&mut self, cost: GameRatio) {
+        self.inner.lock().unwrap().action_cost = cost;
     }
 
     /// Returns this actor's base speed.
@@ -143,6 +306,65 @@ impl Actor {
         self.inner.lock().unwrap().speed
     }
 
+    /// Returns this actor's current hit points.
+    pub fn hp(&self) -> i32 {
+        self.inner.lock().unwrap().hp_cur
+    }
+
+    /// Returns this actor's maximum hit points.
+    pub fn hp_max(&self) -> u32 {
+        self.inner.lock().unwrap().hp_max
+    }
+
+    /// Returns true if this actor has run out of hit points.
+    pub fn is_dead(&self) -> bool {
+        self.hp() <= 0
+    }
+
+    /// Reduces this actor's hit points by `amount`.
+    pub fn take_damage(&mut self, amount: u32) {
+        self.inner.lock().unwrap().hp_cur -= amount as i32;
+    }
+
+    /// Restores up to `amount` hit points, without going over the maximum.
+    pub fn heal(&mut self, amount: u32) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.hp_cur = (inner.hp_cur + amount as i32).min(inner.hp_max as i32);
+    }
+
+    /// Returns the combined modifiers of the actor's equipment.
+    pub fn modifiers(&self) -> Modifiers {
+        self.inner.lock().unwrap().equipment.modifiers()
+    }
+
+    /// Returns the actor's attack, including equipment.
+    pub fn attack(&self) -> u32 {
+        let inner = self.inner.lock().unwrap();
+        Modifiers::apply(inner.attack, inner.equipment.modifiers().attack)
+    }
+
+    /// Returns the actor's defense, including equipment.
+    pub fn defense(&self) -> u32 {
+        let inner = self.inner.lock().unwrap();
+        Modifiers::apply(inner.defense, inner.equipment.modifiers().defense)
+    }
+
+    /// Returns the actor's damage, including equipment.
+    pub fn damage(&self) -> u32 {
+        let inner = self.inner.lock().unwrap();
+        Modifiers::apply(inner.damage, inner.equipment.modifiers().damage)
+    }
+
+    /// Returns how far this actor can see.
+    pub fn fov_radius(&self) -> u32 {
+        self.inner.lock().unwrap().fov_radius
+    }
+
+    /// Computes this actor's field of view.
+    pub fn fov(&self, dungeon: &Dungeon) -> FovMap {
+        dungeon.compute_fov(self.coord(), self.fov_radius() as i32)
+    }
+
     pub fn visible(&self) -> bool {
         self.inner.lock().unwrap().visible
     }
@@ -156,12 +378,42 @@ impl Actor {
         self.inner.lock().unwrap().behavior = behavior;
     }
 
+    /// Returns where this actor last saw the player, if it is keeping track.
+    pub fn target(&self) -> Option<Coord> {
+        self.inner.lock().unwrap().target
+    }
+
+    pub fn set_target(&mut self, target: Option<Coord>) {
+        self.inner.lock().unwrap().target = target;
+    }
+
+    /// Returns true if this actor follows the player when the player takes adjacent stairs.
+    pub fn follows_player(&self) -> bool {
+        match self.behavior() {
+            Behavior::Friendly | Behavior::Hostile | Behavior::Hunting => true,
+            Behavior::Player | Behavior::Peaceful | Behavior::Wary | Behavior::Defensive => false,
+        }
+    }
+
+    /// Returns true if this actor will attack `other` when bumping into it.
+    /// Only the player and the actors it isn't friends with fight each other.
+    pub fn hostile_to(&self, other: &Actor) -> bool {
+        match (self.behavior(), other.behavior()) {
+            (Behavior::Player, Behavior::Friendly)
+            | (Behavior::Friendly, Behavior::Player)
+            | (Behavior::Player, Behavior::Peaceful)
+            | (Behavior::Peaceful, Behavior::Player) => false,
+            (Behavior::Player, _) | (_, Behavior::Player) => true,
+            _ => false,
+        }
+    }
+
     /// Acts out the actor's turn.
     /// Could change itself or the dungeon as a side effect.
     pub fn act(&mut self, dungeon: &mut Dungeon) -> ActResult {
         match self.behavior() {
             Behavior::Player => player::player_act(self, dungeon),
-            _ => ActResult::None,
+            _ => ai::ai_act(self, dungeon),
         }
     }
 
@@ -175,41 +427,368 @@ impl Actor {
         self.try_move_to(dungeon, coord)
     }
 
-    // Tries to move to the specified coordinate. Returns true if the actor uses up a turn.
-    fn try_move_to(&mut self, dungeon: &mut Dungeon, coord: Coord) -> (ActResult, bool) {
-        let passable = {
-            let tile = &mut dungeon[coord];
-            let passable = tile.passable();
-
-            if let Some(ref _actor) = tile.actor {
-                false
-            } else if let Some(ref mut object) = tile.object {
-                let mut object = object.inner.lock().unwrap();
-                match object.object_type() {
-                    ObjectType::Door => {
-                        if object.active() {
-                            // Open door.
-                            object.set_active(false);
-                            false
-                        } else {
-                            true
-                        }
-                    }
-                    _ => passable,
-                }
+    /// Tries to move to the specified coordinate, attacking any hostile actor standing there and
+    /// interacting with any object in the way, e.g. opening a closed door. Any feature and trap at
+    /// the new location is triggered. Returns true if the actor uses up a turn. Moving up or down
+    /// or through water takes longer.
+    pub fn try_move_to(&mut self, dungeon: &mut Dungeon, coord: Coord) -> (ActResult, bool) {
+        if let Some(target) = dungeon[coord].actor.clone() {
+            return if self.hostile_to(&target) {
+                (self.melee_attack(dungeon, target), true)
             } else {
-                passable
+                if self.behavior() == Behavior::Player && target.behavior() == Behavior::Peaceful {
+                    let text = format!("{} greets you.", target.the_name());
+                    dungeon.add_message(&[coord], &text, constants::MESSAGE_COLOR);
+                }
+                (ActResult::None, false)
+            };
+        }
+
+        // Objects in the way, such as closed doors, are interacted with instead.
+        if let Some(mut object) = dungeon[coord].object.clone() {
+            if !object.passable() {
+                return object.interact(self, dungeon);
             }
-        };
+        }
 
-        if passable {
+        if dungeon[coord].passable() {
+            // Climbing and wading take longer than walking.
+            self.set_action_cost(dungeon.move_cost(self.coord(), coord));
             self.move_to(dungeon, coord);
-            (ActResult::None, true)
+
+            let result = self.enter_feature(dungeon);
+            if result != ActResult::None || !dungeon.contains_actor(self) {
+                return (result, true);
+            }
+            (self.trigger_trap(dungeon), true)
         } else {
             (ActResult::None, false)
         }
     }
 
+    /// Attacks `target` in melee, killing it if it runs out of hit points.
+    /// Returns `ActResult::PlayerDead` if the target was the player and died.
+    pub fn melee_attack(&mut self, dungeon: &mut Dungeon, mut target: Actor) -> ActResult {
+        let (attack, damage) = (self.attack(), self.damage());
+        let defense = target.defense();
+
+        let coords = [self.coord(), target.coord()];
+        let is_player = self.behavior() == Behavior::Player;
+        let (subject, object) = (self.the_name(), target.the_name());
+        // Color messages by whether they are good or bad news for the player.
+        let color = if is_player {
+            constants::MESSAGE_GOOD_COLOR
+        } else if target.behavior() == Behavior::Player {
+            constants::MESSAGE_BAD_COLOR
+        } else {
+            constants::MESSAGE_COLOR
+        };
+
+        if !dice(attack + 1, attack + defense + 2) {
+            let verb = if is_player { "miss" } else { "misses" };
+            let text = format!("{} {} {}.", subject, verb, object);
+            dungeon.add_message(&coords, &text, constants::MESSAGE_COLOR);
+            return ActResult::None;
+        }
+
+        target.take_damage(rand_int(1, damage.max(1)));
+
+        if !target.is_dead() {
+            let verb = if is_player { "hit" } else { "hits" };
+            let text = format!("{} {} {}.", subject, verb, object);
+            dungeon.add_message(&coords, &text, color);
+            return ActResult::None;
+        }
+
+        let verb = if is_player { "kill" } else { "kills" };
+        let text = format!("{} {} {}!", subject, verb, object);
+        dungeon.add_message(&coords, &text, color);
+        dungeon.kill_actor(target.coord());
+
+        if target.behavior() == Behavior::Player {
+            ActResult::PlayerDead
+        } else {
+            ActResult::None
+        }
+    }
+
+    /// Suffers the effects of the feature the actor just stepped into: lava burns and chasms drop
+    /// the actor to the next depth. Returns `ActResult::PlayerDead` if the player dies and
+    /// `ActResult::FellDown` if the player falls.
+    pub fn enter_feature(&mut self, dungeon: &mut Dungeon) -> ActResult {
+        let coord = self.coord();
+        let info = match dungeon[coord].feature {
+            Some(ref feature) => Arc::clone(&feature.info),
+            None => return ActResult::None,
+        };
+
+        let is_player = self.behavior() == Behavior::Player;
+        let subject = self.the_name();
+        let color = if is_player {
+            constants::MESSAGE_BAD_COLOR
+        } else {
+            constants::MESSAGE_COLOR
+        };
+
+        if info.burn > 0 {
+            let verb = self.verb("are", "is");
+            let text = format!("{} {} burned by the {}!", subject, verb, info.name);
+            dungeon.add_message(&[coord], &text, color);
+
+            self.take_damage(info.burn);
+            if self.is_dead() {
+                let verb = self.verb("die", "dies");
+                dungeon.add_message(&[coord], &format!("{} {}!", subject, verb), color);
+                dungeon.kill_actor(coord);
+                if is_player {
+                    return ActResult::PlayerDead;
+                }
+            }
+        } else if info.fall {
+            let verb = self.verb("fall", "falls");
+            let text = format!("{} {} into the {}!", subject, verb, info.name);
+            dungeon.add_message(&[coord], &text, color);
+            if is_player {
+                return ActResult::FellDown;
+            }
+            dungeon.drop_actor(coord);
+        }
+
+        ActResult::None
+    }
+
+    /// Sets off the trap the actor is standing on, if there is one, revealing it if the player sees
+    /// it happen. Returns `ActResult::PlayerDead` if the player dies and `ActResult::FellDown` if
+    /// the player falls to the next depth.
+    pub fn trigger_trap(&mut self, dungeon: &mut Dungeon) -> ActResult {
+        let coord = self.coord();
+        let (mut trap, info) = match dungeon[coord].object {
+            Some(ref object) => match object.trap() {
+                Some(info) => (object.clone(), info),
+                None => return ActResult::None,
+            },
+            None => return ActResult::None,
+        };
+
+        let is_player = self.behavior() == Behavior::Player;
+        let subject = self.the_name();
+        let color = if is_player {
+            constants::MESSAGE_BAD_COLOR
+        } else {
+            constants::MESSAGE_COLOR
+        };
+
+        if dungeon.visible(coord) {
+            trap.reveal();
+        }
+        let verb = self.verb("trigger", "triggers");
+        let text = format!("{} {} a {}!", subject, verb, trap.name());
+        dungeon.add_message(&[coord], &text, color);
+
+        match info.effect {
+            TrapEffect::Damage => {
+                self.take_damage(info.power);
+                if !self.is_dead() {
+                    return ActResult::None;
+                }
+
+                let verb = self.verb("die", "dies");
+                dungeon.add_message(&[coord], &format!("{} {}!", subject, verb), color);
+                dungeon.kill_actor(coord);
+                if is_player {
+                    return ActResult::PlayerDead;
+                }
+            }
+            TrapEffect::Teleport => {
+                if let Some(new_coord) = dungeon.random_open_coord_actor() {
+                    self.move_to(dungeon, new_coord);
+                    let verb = self.verb("are", "is");
+                    let text = format!("{} {} whisked away!", subject, verb);
+                    dungeon.add_message(&[coord, new_coord], &text, color);
+                }
+            }
+            TrapEffect::Alarm => {
+                // The alarm can be heard anywhere on the depth.
+                GAMEDATA
+                    .read()
+                    .unwrap()
+                    .add_message("A loud alarm rings out!", constants::MESSAGE_BAD_COLOR);
+                dungeon.alert_actors(coord);
+            }
+            TrapEffect::Pit => {
+                let verb = self.verb("fall", "falls");
+                let text = format!("{} {} through!", subject, verb);
+                dungeon.add_message(&[coord], &text, color);
+                if is_player {
+                    return ActResult::FellDown;
+                }
+                dungeon.drop_actor(coord);
+            }
+        }
+
+        ActResult::None
+    }
+
+    /// Returns true if the actor carries an item with database key `key`.
+    pub fn has_item(&self, key: &str) -> bool {
+        self.inner
+            .lock()
+            .unwrap()
+            .inventory
+            .iter()
+            .any(|stack| stack.item().key() == key)
+    }
+
+    /// Returns a copy of the actor's inventory.
+    pub fn inventory(&self) -> ItemStash {
+        self.inner.lock().unwrap().inventory.clone()
+    }
+
+    /// Adds `stack` to the actor's inventory.
+    pub fn add_item(&mut self, stack: ItemStack) {
+        self.inner.lock().unwrap().inventory.add(stack);
+    }
+
+    /// Picks up `amount` items from the stack at `index` in the stash the actor is standing on.
+    pub fn pick_up(&mut self, dungeon: &mut Dungeon, index: usize, amount: usize) {
+        let coord = self.coord();
+        let stack = dungeon.take_item(coord, index, amount);
+
+        let verb = self.verb("pick up", "picks up");
+        let text = format!("{} {} the {}.", self.the_name(), verb, stack);
+        dungeon.add_message(&[coord], &text, constants::MESSAGE_COLOR);
+
+        self.inner.lock().unwrap().inventory.add(stack);
+    }
+
+    /// Drops `amount` items from the stack at `index` in the actor's inventory.
+    pub fn drop_item(&mut self, dungeon: &mut Dungeon, index: usize, amount: usize) {
+        let coord = self.coord();
+        let stack = self.inner.lock().unwrap().inventory.take(index, amount);
+
+        let verb = self.verb("drop", "drops");
+        let text = format!("{} {} the {}.", self.the_name(), verb, stack);
+        dungeon.add_message(&[coord], &text, constants::MESSAGE_COLOR);
+
+        dungeon.add_item(coord, stack);
+    }
+
+    /// Uses up one item from the stack at `index` in the actor's inventory, e.g. by drinking a
+    /// potion, and applies its effect. When the player uses an item, its kind is identified.
+    /// Returns `ActResult::PlayerDead` if the item killed the player.
+    ///
+    /// Effects that need a choice from the user, such as identifying, are left to the caller.
+    pub fn use_item(&mut self, dungeon: &mut Dungeon, index: usize) -> ActResult {
+        let coord = self.coord();
+        let stack = self.inner.lock().unwrap().inventory.take(index, 1);
+        let item = stack.item();
+        let is_player = self.behavior() == Behavior::Player;
+
+        let verb = match item.category() {
+            ItemCategory::Potion => self.verb("drink", "drinks"),
+            ItemCategory::Scroll => self.verb("read", "reads"),
+            _ => self.verb("use", "uses"),
+        };
+        let text = format!("{} {} the {}.", self.the_name(), verb, item.name());
+        dungeon.add_message(&[coord], &text, constants::MESSAGE_COLOR);
+
+        let text = match item.effect() {
+            ItemEffect::Heal => {
+                self.heal(item.power());
+                format!("{} {} better.", self.the_name(), self.verb("feel", "looks"))
+            }
+            ItemEffect::Harm => {
+                self.take_damage(item.power());
+                format!("{} {} sick.", self.the_name(), self.verb("feel", "looks"))
+            }
+            ItemEffect::None => "Nothing seems to happen.".into(),
+            ItemEffect::Identify => String::new(),
+        };
+        if !text.is_empty() {
+            dungeon.add_message(&[coord], &text, constants::MESSAGE_COLOR);
+        }
+
+        // Using an item reveals what it is.
+        if is_player && GAMEDATA.read().unwrap().identify_item(item.key()) {
+            let text = format!("That was a {}.", item.real_name());
+            dungeon.add_message(&[coord], &text, constants::MESSAGE_GOOD_COLOR);
+        }
+
+        if self.is_dead() {
+            dungeon.add_message(
+                &[coord],
+                &format!("{} {}!", self.the_name(), self.verb("die", "dies")),
+                constants::MESSAGE_BAD_COLOR,
+            );
+            dungeon.kill_actor(coord);
+            if is_player {
+                return ActResult::PlayerDead;
+            }
+        }
+
+        ActResult::None
+    }
+
+    /// Returns a copy of the actor's equipment.
+    pub fn equipment(&self) -> Equipment {
+        self.inner.lock().unwrap().equipment.clone()
+    }
+
+    /// Wields or wears one item from the stack at `index` in the actor's inventory. Anything
+    /// already equipped in the same slot is put back into the inventory.
+    pub fn equip(&mut self, dungeon: &mut Dungeon, index: usize) {
+        let coord = self.coord();
+        let stack = self.inner.lock().unwrap().inventory.take(index, 1);
+        let item = stack.item().clone();
+        let slot = item.slot().expect("Equipping an item without a slot");
+
+        if self.inner.lock().unwrap().equipment.get(slot).is_some() {
+            self.unequip(dungeon, slot);
+        }
+
+        let verb = if slot.is_wielded() {
+            self.verb("wield", "wields")
+        } else {
+            self.verb("put on", "puts on")
+        };
+        let text = format!("{} {} the {}.", self.the_name(), verb, item.name());
+        dungeon.add_message(&[coord], &text, constants::MESSAGE_COLOR);
+
+        self.inner.lock().unwrap().equipment.equip(item);
+    }
+
+    /// Puts the item equipped in `slot` back into the actor's inventory.
+    pub fn unequip(&mut self, dungeon: &mut Dungeon, slot: EquipSlot) {
+        let coord = self.coord();
+        let item = match self.inner.lock().unwrap().equipment.remove(slot) {
+            Some(item) => item,
+            None => return,
+        };
+
+        let verb = if slot.is_wielded() {
+            self.verb("put away", "puts away")
+        } else {
+            self.verb("take off", "takes off")
+        };
+        let text = format!("{} {} the {}.", self.the_name(), verb, item.name());
+        dungeon.add_message(&[coord], &text, constants::MESSAGE_COLOR);
+
+        self.inner
+            .lock()
+            .unwrap()
+            .inventory
+            .add(ItemStack::new(item, 1));
+    }
+
+    /// Returns the form of a verb agreeing with the actor, which is "you" for the player.
+    pub(crate) fn verb<'a>(&self, player_form: &'a str, other_form: &'a str) -> &'a str {
+        if self.behavior() == Behavior::Player {
+            player_form
+        } else {
+            other_form
+        }
+    }
+
     // Moves to the specified coordinate unconditionally.
     pub fn move_to(&mut self, dungeon: &mut Dungeon, new_coord: Coord) {
         assert!(
@@ -256,11 +835,13 @@ impl PartialOrd for Actor {
 }
 
 /// Enum listing possible AI states of an actor.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Behavior {
     /// Behavior corresponding to the player itself.
     Player,
     Friendly,
+    /// Goes about its own business and never attacks or follows the player.
+    Peaceful,
     Wary,
     Defensive,
     Hostile,
@@ -276,6 +857,7 @@ impl FromStr for Behavior {
         Ok(match s {
             "player" => Player,
             "friendly" => Friendly,
+            "peaceful" => Peaceful,
             "wary" => Wary,
             "defensive" => Defensive,
             "hostile" => Hostile,
@@ -289,3 +871,23 @@ impl FromStr for Behavior {
         })
     }
 }
+
+impl fmt::Display for Behavior {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Behavior::*;
+
+        write!(
+            f,
+            "{}",
+            match *self {
+                Player => "player",
+                Friendly => "friendly",
+                Peaceful => "peaceful",
+                Wary => "wary",
+                Defensive => "defensive",
+                Hostile => "hostile",
+                Hunting => "hunting",
+            }
+        )
+    }
+}
@@ -0,0 +1,163 @@
+//! Module for monster AI.
+//!
+//! Every actor other than the player is driven by its `Behavior`. Actors notice the player when it
+//! is within their FOV radius and in sight, and switch behaviors in response to what happens to
+//! them: hostile actors start hunting the player once they see it, defensive actors fight back
+//! once they are hurt, and hunting actors flee once they are badly hurt.
+
+use crate::actor::{Actor, Behavior};
+use crate::constants;
+use crate::coord::Coord;
+use crate::dungeon::{ActResult, Dungeon};
+use crate::path::{self, DijkstraMap};
+use crate::util::direction::CompassDirection;
+use crate::util::rand::{dice, Choose};
+
+/// Acts out the turn of a non-player actor.
+pub fn ai_act(actor: &mut Actor, dungeon: &mut Dungeon) -> ActResult {
+    let player = match dungeon.find_player() {
+        Some(ref player) if can_see(actor, player, dungeon) => Some(player.clone()),
+        _ => None,
+    };
+
+    // Remember where the player was last seen.
+    if let Some(ref player) = player {
+        actor.set_target(Some(player.coord()));
+    }
+
+    update_behavior(actor, player.is_some());
+
+    match actor.behavior() {
+        Behavior::Player => unreachable!(),
+        Behavior::Friendly => friendly_act(actor, dungeon, player),
+        Behavior::Peaceful => wander(actor, dungeon),
+        Behavior::Wary => wary_act(actor, dungeon, player),
+        Behavior::Defensive => defensive_act(actor, dungeon, player),
+        Behavior::Hostile => wander(actor, dungeon),
+        Behavior::Hunting => hunting_act(actor, dungeon, player),
+    }
+}
+
+/// Returns true if `actor` can see `player`.
+pub fn can_see(actor: &Actor, player: &Actor, dungeon: &Dungeon) -> bool {
+    // Skip computing the FOV when the player is obviously out of range.
+    actor.coord().distance(player.coord()) <= actor.fov_radius() as i32
+        && actor.fov(dungeon).is_visible(player.coord())
+}
+
+// Switches the actor's behavior based on whether it sees the player and how hurt it is.
+fn update_behavior(actor: &mut Actor, sees_player: bool) {
+    let behavior = match actor.behavior() {
+        Behavior::Hostile if sees_player => Behavior::Hunting,
+        Behavior::Defensive if actor.hp() < actor.hp_max() as i32 => Behavior::Hunting,
+        Behavior::Hunting if badly_hurt(actor) => Behavior::Wary,
+        Behavior::Hunting if !sees_player && actor.target().is_none() => Behavior::Hostile,
+        behavior => behavior,
+    };
+
+    actor.set_behavior(behavior);
+}
+
+// Returns true if the actor is hurt badly enough to flee.
+fn badly_hurt(actor: &Actor) -> bool {
+    actor.hp() * 100 <= actor.hp_max() as i32 * constants::AI_FLEE_HP_PERCENT
+}
+
+// Friendly actors keep close to the player but never attack it.
+fn friendly_act(actor: &mut Actor, dungeon: &mut Dungeon, player: Option<Actor>) -> ActResult {
+    match player {
+        Some(ref player) if actor.coord().distance(player.coord()) > 2 => {
+            step_towards(actor, dungeon, player.coord()).0
+        }
+        _ => wander(actor, dungeon),
+    }
+}
+
+// Wary actors keep away from the player and only attack when cornered.
+fn wary_act(actor: &mut Actor, dungeon: &mut Dungeon, player: Option<Actor>) -> ActResult {
+    match player {
+        Some(player) => {
+            let (result, moved) = step_away(actor, dungeon, player.coord());
+            if !moved && actor.coord().is_adjacent(player.coord()) {
+                return actor.melee_attack(dungeon, player);
+            }
+            result
+        }
+        None => wander(actor, dungeon),
+    }
+}
+
+// Defensive actors hold their ground and only attack the player when it comes close.
+fn defensive_act(actor: &mut Actor, dungeon: &mut Dungeon, player: Option<Actor>) -> ActResult {
+    match player {
+        Some(ref player) if actor.coord().is_adjacent(player.coord()) => {
+            actor.melee_attack(dungeon, player.clone())
+        }
+        _ => ActResult::None,
+    }
+}
+
+// Hunting actors chase the player to where it was last seen and attack it.
+fn hunting_act(actor: &mut Actor, dungeon: &mut Dungeon, player: Option<Actor>) -> ActResult {
+    if let Some(player) = player {
+        if actor.coord().is_adjacent(player.coord()) {
+            return actor.melee_attack(dungeon, player);
+        }
+    }
+
+    match actor.target() {
+        Some(target) => {
+            // Give up once the trail goes cold.
+            let (result, moved) = step_towards(actor, dungeon, target);
+            if !moved || actor.coord() == target {
+                actor.set_target(None);
+            }
+            result
+        }
+        None => wander(actor, dungeon),
+    }
+}
+
+// Moves the actor to a random adjacent open tile, or lets it stay put.
+fn wander(actor: &mut Actor, dungeon: &mut Dungeon) -> ActResult {
+    if !dice(1, 2) {
+        return ActResult::None;
+    }
+
+    let coord = actor.coord();
+    let open: Vec<Coord> = CompassDirection::all()
+        .iter()
+        .map(|dir| coord.coord_in_dir(dir, 1))
+        .filter(|&next| dungeon.open_for_actor(next))
+        .collect();
+
+    match open.choose() {
+        Some(&next) => actor.try_move_to(dungeon, next).0,
+        None => ActResult::None,
+    }
+}
+
+// Takes the first step on the way to `goal`, which may attack whoever stands in the way. Returns
+// the result of the step and false if `goal` can't be reached or the step was blocked.
+fn step_towards(actor: &mut Actor, dungeon: &mut Dungeon, goal: Coord) -> (ActResult, bool) {
+    let next = match path::find_path(dungeon, actor.coord(), goal) {
+        Some(path) => match path.first() {
+            Some(&next) => next,
+            None => return (ActResult::None, false),
+        },
+        None => return (ActResult::None, false),
+    };
+
+    actor.try_move_to(dungeon, next)
+}
+
+// Takes a step that brings the actor further from `threat`. Returns the result of the step and
+// false if there is no such step or it was blocked.
+fn step_away(actor: &mut Actor, dungeon: &mut Dungeon, threat: Coord) -> (ActResult, bool) {
+    let flee_map = DijkstraMap::flee(dungeon, &[threat]);
+
+    match flee_map.downhill(dungeon, actor.coord()) {
+        Some(next) => actor.try_move_to(dungeon, next),
+        None => (ActResult::None, false),
+    }
+}
@@ -2,7 +2,10 @@ extern crate failure;
 extern crate taurus;
 
 use taurus::handle_error;
+use taurus::options::GameOptions;
 
 fn main() {
-    handle_error(taurus::run_game());
+    let options = handle_error(GameOptions::from_args(std::env::args().skip(1)));
+
+    handle_error(taurus::run_game(&options));
 }
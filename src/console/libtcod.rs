@@ -0,0 +1,70 @@
+//! Console backend drawing to a tcod window.
+
+use super::{Color, ConsoleBackend, ConsoleSettings, Event, EventFlags, Key};
+use tcod;
+use tcod::input::check_for_event;
+use tcod::{Console, FontLayout, FontType, Renderer, RootConsole};
+
+/// Console backend drawing to a tcod window with the OpenGL renderer.
+pub struct TcodConsole {
+    root: RootConsole,
+}
+
+impl TcodConsole {
+    /// Opens the tcod window.
+    pub fn new(settings: &ConsoleSettings) -> TcodConsole {
+        tcod::system::set_fps(settings.fps);
+
+        let mut console = TcodConsole {
+            root: RootConsole::initializer()
+                .size(settings.width, settings.height)
+                .title(settings.title.clone())
+                .font(settings.font.clone(), FontLayout::Tcod)
+                .font_type(FontType::Greyscale)
+                .renderer(Renderer::OpenGL)
+                .init(),
+        };
+        console.set_default_background(settings.background_color);
+
+        console
+    }
+}
+
+impl ConsoleBackend for TcodConsole {
+    fn set_default_background(&mut self, color: Color) {
+        self.root.set_default_background(color.to_tcod());
+    }
+
+    fn window_closed(&self) -> bool {
+        self.root.window_closed()
+    }
+
+    fn draw_char(&mut self, x: i32, y: i32, c: char, color: Color) {
+        self.root.set_char(x, y, c);
+        self.root.set_char_foreground(x, y, color.to_tcod());
+    }
+
+    fn wait_for_keypress(&mut self, flush: bool) -> Key {
+        self.root.wait_for_keypress(flush)
+    }
+
+    fn check_for_event(&mut self, event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+        check_for_event(event_mask)
+    }
+
+    fn set_window_title(&mut self, title: &str) {
+        self.root.set_window_title(title);
+    }
+
+    fn flush(&mut self) {
+        self.root.flush();
+    }
+
+    fn clear(&mut self) {
+        self.root.clear();
+    }
+
+    fn get_fps(&self) -> i32 {
+        tcod::system::get_fps()
+    }
+}
@@ -0,0 +1,216 @@
+//! Console backend drawing to an in-memory grid.
+//!
+//! This lets the game run without a window, e.g. in tests. Input is scripted by queueing events
+//! ahead of time.
+
+use super::{
+    code_key, input, printable_key, Color, ConsoleBackend, ConsoleSettings, Event, EventFlags, Key,
+    KeyCode,
+};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Console backend drawing to an in-memory grid of characters and colors.
+///
+/// Drawing goes to a back buffer which is copied to the visible screen on `flush`, like a window
+/// would. Once all queued input has been consumed the window reports itself as closed, so that
+/// the game ends instead of waiting for input forever.
+pub struct MemoryConsole {
+    width: i32,
+    height: i32,
+    background: Color,
+    title: String,
+
+    buffer: Vec<(char, Color)>,
+    screen: Vec<(char, Color)>,
+
+    events: VecDeque<(EventFlags, Event)>,
+}
+
+impl MemoryConsole {
+    /// Creates a blank console with the dimensions given in `settings`.
+    pub fn new(settings: &ConsoleSettings) -> MemoryConsole {
+        let size = (settings.width * settings.height) as usize;
+        let blank = (' ', settings.background_color);
+
+        MemoryConsole {
+            width: settings.width,
+            height: settings.height,
+            background: settings.background_color,
+            title: settings.title.clone(),
+
+            buffer: vec![blank; size],
+            screen: vec![blank; size],
+
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns the character on screen at `x` and `y`.
+    pub fn char_at(&self, x: i32, y: i32) -> char {
+        self.screen[self.index(x, y)].0
+    }
+
+    /// Returns the color of the character on screen at `x` and `y`.
+    pub fn color_at(&self, x: i32, y: i32) -> Color {
+        self.screen[self.index(x, y)].1
+    }
+
+    /// Returns the characters on screen in row `y`.
+    pub fn row(&self, y: i32) -> String {
+        (0..self.width).map(|x| self.char_at(x, y)).collect()
+    }
+
+    /// Queues up a keypress to be read as input.
+    pub fn push_key(&mut self, key: Key) {
+        self.events.push_back((input::KEY_PRESS, Event::Key(key)));
+    }
+
+    /// Queues up a keypress of the printable character `c`.
+    pub fn push_char(&mut self, c: char) {
+        self.push_key(printable_key(c));
+    }
+
+    /// Queues up a keypress of the special key `code`.
+    pub fn push_code(&mut self, code: KeyCode) {
+        self.push_key(code_key(code));
+    }
+
+    /// Discards all queued input.
+    pub fn clear_input(&mut self) {
+        self.events.clear();
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        assert!(self.in_bounds(x, y), "({}, {}) is out of bounds", x, y);
+        (y * self.width + x) as usize
+    }
+}
+
+impl ConsoleBackend for MemoryConsole {
+    fn set_default_background(&mut self, color: Color) {
+        self.background = color;
+    }
+
+    fn window_closed(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    fn draw_char(&mut self, x: i32, y: i32, c: char, color: Color) {
+        // Like tcod, ignore anything drawn off screen.
+        if self.in_bounds(x, y) {
+            let index = self.index(x, y);
+            self.buffer[index] = (c, color);
+        }
+    }
+
+    fn wait_for_keypress(&mut self, flush: bool) -> Key {
+        // Queued input is never flushed since it was scripted on purpose.
+        while let Some((flags, event)) = self.events.pop_front() {
+            if let Event::Key(key) = event {
+                if flags.intersects(input::KEY_PRESS) {
+                    return key;
+                }
+            }
+        }
+
+        Key::default()
+    }
+
+    fn check_for_event(&mut self, event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+        while let Some((flags, event)) = self.events.pop_front() {
+            if flags.intersects(event_mask) {
+                return Some((flags, event));
+            }
+        }
+
+        None
+    }
+
+    fn set_window_title(&mut self, title: &str) {
+        self.title = title.into();
+    }
+
+    fn flush(&mut self) {
+        self.screen.copy_from_slice(&self.buffer);
+    }
+
+    fn clear(&mut self) {
+        let blank = (' ', self.background);
+        for cell in &mut self.buffer {
+            *cell = blank;
+        }
+    }
+
+    fn get_fps(&self) -> i32 {
+        0
+    }
+
+    fn as_memory(&mut self) -> Option<&mut MemoryConsole> {
+        Some(self)
+    }
+}
+
+impl fmt::Debug for MemoryConsole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MemoryConsole ({}x{})", self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GAMEDATA;
+
+    #[test]
+    fn draw_and_flush() {
+        let mut console = MemoryConsole::new(&GAMEDATA.read().unwrap().console_settings);
+        let color = Color { r: 1, g: 2, b: 3 };
+
+        console.draw_char(1, 2, '@', color);
+        console.draw_char(-1, 2, '#', color);
+        assert_eq!(console.char_at(1, 2), ' ');
+
+        console.flush();
+        assert_eq!(console.char_at(1, 2), '@');
+        assert_eq!(console.color_at(1, 2), color);
+        assert_eq!(console.row(2).trim(), "@");
+
+        console.clear();
+        console.flush();
+        assert_eq!(console.char_at(1, 2), ' ');
+    }
+
+    #[test]
+    fn scripted_input() {
+        let mut console = MemoryConsole::new(&GAMEDATA.read().unwrap().console_settings);
+        assert!(console.window_closed());
+
+        console.push_char('y');
+        console.push_code(KeyCode::Escape);
+        assert!(!console.window_closed());
+
+        assert_eq!(console.wait_for_keypress(true).printable, 'y');
+        match console.check_for_event(input::KEY) {
+            Some((_, Event::Key(key))) => assert_eq!(key.code, KeyCode::Escape),
+            _ => panic!("expected a key event"),
+        }
+        assert!(console.window_closed());
+    }
+}
@@ -0,0 +1,312 @@
+//! Console I/O module.
+//!
+//! This module is designed so that it can be replaced with a different console
+//! implementation if necessary. Drawing and input go through the `ConsoleBackend` trait, which is
+//! implemented by a tcod window, by a plain terminal, and by an in-memory grid used for running
+//! without a window.
+
+pub use self::libtcod::TcodConsole;
+pub use self::memory::MemoryConsole;
+#[cfg(unix)]
+pub use self::terminal::TerminalConsole;
+pub use tcod::input::{self, Event, EventFlags, Key, KeyCode};
+
+mod libtcod;
+mod memory;
+#[cfg(unix)]
+mod terminal;
+
+use crate::constants;
+use crate::defs::*;
+use crate::error::err_unexpected;
+use crate::util::convert::color_code_to_rgb;
+use crate::{GameError, GameResult};
+use over::Obj;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tcod::Color as TcodColor;
+
+/// Color struct.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    /// Converts this `Color` to a tcod Color.
+    pub fn to_tcod(self) -> TcodColor {
+        let Color { r, g, b } = self;
+        TcodColor::new(r, g, b)
+    }
+
+    /// Returns this `Color` as a color code in the format "#FFFFFF".
+    pub fn to_code(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl FromStr for Color {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match color_code_to_rgb(s) {
+            Some((r, g, b)) => Ok(Color { r, g, b }),
+            None => Err(GameError::ConversionError {
+                val: s.into(),
+                msg: "Make sure the string is in the format \"#FFFFFF\".",
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.r, self.g, self.b)
+    }
+}
+
+/// The frontend the game is displayed in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Frontend {
+    /// A tcod window.
+    Tcod,
+    /// The terminal the game was started from.
+    Terminal,
+}
+
+impl FromStr for Frontend {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "tcod" => Frontend::Tcod,
+            "terminal" => Frontend::Terminal,
+            _ => {
+                return Err(GameError::ConversionError {
+                    val: s.into(),
+                    msg: "Invalid frontend",
+                });
+            }
+        })
+    }
+}
+
+impl fmt::Display for Frontend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Frontend::Tcod => "tcod",
+                Frontend::Terminal => "terminal",
+            }
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct ConsoleSettings {
+    /// The frontend to display the console in.
+    pub frontend: Frontend,
+    title: String,
+    width: i32,
+    height: i32,
+    fps: i32,
+    font: String,
+    background_color: Color,
+}
+
+impl ConsoleSettings {
+    pub fn new(data: &Obj) -> GameResult<ConsoleSettings> {
+        // Load all data from the database.
+
+        let frontend = Frontend::from_str(&data.get_str("frontend")?)?;
+        let title = data.get_str("title")?;
+        let width = big_to_i32(data.get_int("console_width")?)?;
+        let height = big_to_i32(data.get_int("console_height")?)?;
+        let fps = big_to_i32(data.get_int("fps")?)?;
+        let font = data.get_str("default_font")?;
+        let background_color = Color::from_str(&data.get_str("background_color")?)?;
+
+        Ok(ConsoleSettings {
+            frontend,
+            title,
+            width,
+            height,
+            fps,
+            font,
+            background_color,
+        })
+    }
+
+    /// Returns the width of the console in characters.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Returns the height of the console in characters.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn background_color(&self) -> Color {
+        self.background_color
+    }
+}
+
+/// A surface that the game can draw to and read input from.
+pub trait ConsoleBackend: Send {
+    fn set_default_background(&mut self, color: Color);
+
+    /// Returns true if the window has been closed.
+    fn window_closed(&self) -> bool;
+
+    /// Puts given char `c` to tile at `x` and `y` with `color`.
+    fn draw_char(&mut self, x: i32, y: i32, c: char, color: Color);
+
+    /// Waits for a keypress event from the user.
+    /// If `flush` is true, all pending keypresses are flushed from the keyboard buffer first.
+    fn wait_for_keypress(&mut self, flush: bool) -> Key;
+
+    /// Returns the next pending event matching `event_mask`, if any, without blocking.
+    fn check_for_event(&mut self, event_mask: EventFlags) -> Option<(EventFlags, Event)>;
+
+    fn set_window_title(&mut self, title: &str);
+
+    fn flush(&mut self);
+
+    fn clear(&mut self);
+
+    fn get_fps(&self) -> i32;
+
+    /// Returns this backend as a `MemoryConsole`, if it is one.
+    fn as_memory(&mut self) -> Option<&mut MemoryConsole> {
+        None
+    }
+}
+
+/// Console object responsible for display and input.
+pub struct DrawConsole {
+    backend: Box<dyn ConsoleBackend>,
+}
+
+impl DrawConsole {
+    /// Initialize a console in the frontend given in `settings`.
+    pub fn new(settings: &ConsoleSettings) -> GameResult<DrawConsole> {
+        let backend: Box<dyn ConsoleBackend> = match settings.frontend {
+            Frontend::Tcod => Box::new(TcodConsole::new(settings)),
+            #[cfg(unix)]
+            Frontend::Terminal => Box::new(TerminalConsole::new(settings)?),
+            #[cfg(not(unix))]
+            Frontend::Terminal => {
+                return err_unexpected("The terminal frontend is only supported on Unix")
+            }
+        };
+
+        Ok(DrawConsole::with_backend(backend))
+    }
+
+    /// Initialize a console that draws to memory instead of a window.
+    pub fn new_memory(settings: &ConsoleSettings) -> DrawConsole {
+        DrawConsole::with_backend(Box::new(MemoryConsole::new(settings)))
+    }
+
+    /// Initialize a console with the given backend.
+    pub fn with_backend(backend: Box<dyn ConsoleBackend>) -> DrawConsole {
+        DrawConsole { backend }
+    }
+
+    pub fn set_default_background(&mut self, color: Color) {
+        self.backend.set_default_background(color);
+    }
+
+    /// Returns true if the window has been closed.
+    pub fn window_closed(&self) -> bool {
+        self.backend.window_closed()
+    }
+
+    /// Puts given char `c` to tile at `x` and `y` with `color`.
+    pub fn draw_char(&mut self, x: i32, y: i32, c: char, color: Color) {
+        self.backend.draw_char(x, y, c, color);
+    }
+
+    // TODO: Stop printing if edge of console is hit
+    // TODO: Add version of this with max x and y and with wrapping
+    pub fn put_str(&mut self, x: i32, y: i32, s: &str, color: Color) {
+        for (j, c) in s.chars().enumerate() {
+            self.draw_char(x + j as i32, y, c, color);
+        }
+    }
+
+    /// This function will wait for a keypress event from the user, returning the `KeyState` that
+    /// represents the event.
+    /// If `flush` is true, all pending keypresses are flushed from the keyboard buffer.
+    /// If `flush` is false, it returns the first element from it.
+    pub fn wait_for_keypress(&mut self, flush: bool) -> Key {
+        self.backend.wait_for_keypress(flush)
+    }
+
+    pub fn check_for_event(&mut self, event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+        self.backend.check_for_event(event_mask)
+    }
+
+    /// Sets the main window's title to `title`.
+    pub fn set_window_title<T>(&mut self, title: T)
+    where
+        T: AsRef<str>,
+    {
+        self.backend.set_window_title(title.as_ref());
+    }
+
+    pub fn flush(&mut self) {
+        self.backend.flush();
+    }
+
+    pub fn clear(&mut self) {
+        self.backend.clear();
+    }
+
+    pub fn get_fps(&self) -> i32 {
+        self.backend.get_fps()
+    }
+
+    /// Returns the in-memory backend, if this console draws to memory.
+    pub fn as_memory(&mut self) -> Option<&mut MemoryConsole> {
+        self.backend.as_memory()
+    }
+}
+
+/// Restores the terminal if the terminal frontend was in use. This should be called before printing
+/// to stdout once the game is over.
+pub fn restore_terminal() {
+    #[cfg(unix)]
+    terminal::restore_terminal();
+}
+
+/// Returns a keypress of the printable character `c`.
+fn printable_key(c: char) -> Key {
+    let mut key = code_key(KeyCode::Char);
+    key.printable = c;
+
+    key
+}
+
+/// Returns a keypress of the special key `code`.
+fn code_key(code: KeyCode) -> Key {
+    let mut key = Key::default();
+    key.code = code;
+    key.pressed = true;
+
+    key
+}
+
+impl fmt::Debug for DrawConsole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Console")
+    }
+}
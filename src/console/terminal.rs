@@ -0,0 +1,391 @@
+//! Console backend drawing to a plain terminal with ANSI escape sequences.
+//!
+//! This works anywhere a text terminal does, such as over SSH. Colors are sent as 24-bit
+//! truecolor sequences and input is read from stdin in raw mode.
+
+use super::{
+    code_key, input, printable_key, Color, ConsoleBackend, ConsoleSettings, Event, EventFlags, Key,
+    KeyCode,
+};
+use crate::error::err_unexpected;
+use crate::GameResult;
+use failure::ResultExt;
+use libc;
+use std::collections::VecDeque;
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Write};
+use std::mem;
+use std::panic;
+use std::sync::Mutex;
+
+const STDIN: libc::c_int = libc::STDIN_FILENO;
+const STDOUT: libc::c_int = libc::STDOUT_FILENO;
+
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l\x1b[2J";
+const LEAVE_SCREEN: &str = "\x1b[0m\x1b[?25h\x1b[?1049l";
+
+lazy_static! {
+    /// Terminal attributes from before raw mode was entered, if it is active.
+    static ref SAVED_TERMIOS: Mutex<Option<libc::termios>> = Mutex::new(None);
+}
+
+/// Restores the terminal to the state it was in before the terminal console was created.
+/// Does nothing if the terminal console isn't active.
+pub fn restore_terminal() {
+    let mut saved = SAVED_TERMIOS.lock().unwrap_or_else(|err| err.into_inner());
+
+    if let Some(termios) = saved.take() {
+        unsafe {
+            libc::tcsetattr(STDIN, libc::TCSAFLUSH, &termios);
+        }
+
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(LEAVE_SCREEN.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+/// Console backend drawing to the terminal the game was started from.
+pub struct TerminalConsole {
+    width: i32,
+    height: i32,
+    background: Color,
+
+    buffer: Vec<(char, Color)>,
+    // What is currently on the terminal, or `None` if it needs to be redrawn completely.
+    screen: Option<Vec<(char, Color)>>,
+
+    keys: VecDeque<Key>,
+    closed: bool,
+}
+
+impl TerminalConsole {
+    /// Switches the terminal to raw mode and an alternate screen.
+    pub fn new(settings: &ConsoleSettings) -> GameResult<TerminalConsole> {
+        if unsafe { libc::isatty(STDIN) == 0 || libc::isatty(STDOUT) == 0 } {
+            return err_unexpected("The terminal frontend requires an interactive terminal");
+        }
+
+        let (columns, rows) = terminal_size()?;
+        if columns < settings.width || rows < settings.height {
+            return err_unexpected("The terminal is smaller than the console size in settings")
+                .context(format!(
+                    "Terminal is {}x{}, console is {}x{}",
+                    columns, rows, settings.width, settings.height
+                ))
+                .map_err(Into::into);
+        }
+
+        enter_raw_mode()?;
+
+        // Make sure a panic message isn't swallowed by the alternate screen.
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            hook(info);
+        }));
+
+        let size = (settings.width * settings.height) as usize;
+        let mut console = TerminalConsole {
+            width: settings.width,
+            height: settings.height,
+            background: settings.background_color,
+
+            buffer: vec![(' ', settings.background_color); size],
+            screen: None,
+
+            keys: VecDeque::new(),
+            closed: false,
+        };
+        console.write(ENTER_SCREEN);
+        console.set_window_title(&settings.title);
+
+        Ok(console)
+    }
+
+    fn write(&self, s: &str) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(s.as_bytes());
+        let _ = stdout.flush();
+    }
+
+    // Reads all pending input into the key queue, waiting up to `timeout` milliseconds for some to
+    // arrive. A negative timeout waits indefinitely.
+    fn read_input(&mut self, timeout: libc::c_int) {
+        let mut poll_fd = libc::pollfd {
+            fd: STDIN,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if unsafe { libc::poll(&mut poll_fd, 1, timeout) } <= 0 {
+            return;
+        }
+
+        let mut bytes = [0u8; 64];
+        let count = unsafe { libc::read(STDIN, bytes.as_mut_ptr() as *mut libc::c_void, 64) };
+        if count <= 0 {
+            // Stdin was closed.
+            self.closed = true;
+            return;
+        }
+
+        let bytes = &bytes[..count as usize];
+        // Raw mode disables signals, so treat ctrl-c like closing a window.
+        if bytes.contains(&CTRL_C) {
+            self.closed = true;
+        }
+        self.keys.extend(parse_keys(bytes));
+    }
+}
+
+impl ConsoleBackend for TerminalConsole {
+    fn set_default_background(&mut self, color: Color) {
+        self.background = color;
+        self.screen = None;
+    }
+
+    fn window_closed(&self) -> bool {
+        self.closed
+    }
+
+    fn draw_char(&mut self, x: i32, y: i32, c: char, color: Color) {
+        if x >= 0 && y >= 0 && x < self.width && y < self.height {
+            self.buffer[(y * self.width + x) as usize] = (c, color);
+        }
+    }
+
+    fn wait_for_keypress(&mut self, flush: bool) -> Key {
+        if flush {
+            self.keys.clear();
+            unsafe {
+                libc::tcflush(STDIN, libc::TCIFLUSH);
+            }
+        }
+
+        while self.keys.is_empty() && !self.closed {
+            self.read_input(-1);
+        }
+
+        self.keys.pop_front().unwrap_or_default()
+    }
+
+    fn check_for_event(&mut self, event_mask: EventFlags) -> Option<(EventFlags, Event)> {
+        if !event_mask.intersects(input::KEY_PRESS) {
+            return None;
+        }
+
+        if self.keys.is_empty() {
+            self.read_input(0);
+        }
+
+        self.keys
+            .pop_front()
+            .map(|key| (input::KEY_PRESS, Event::Key(key)))
+    }
+
+    fn set_window_title(&mut self, title: &str) {
+        self.write(&format!("\x1b]0;{}\x07", title));
+    }
+
+    fn flush(&mut self) {
+        let mut out = String::new();
+        let mut cursor = None;
+        let mut current_color = None;
+
+        if self.screen.is_none() {
+            let Color { r, g, b } = self.background;
+            let _ = write!(out, "\x1b[48;2;{};{};{}m\x1b[2J", r, g, b);
+        }
+
+        for (index, &(c, color)) in self.buffer.iter().enumerate() {
+            if let Some(ref screen) = self.screen {
+                if screen[index] == (c, color) {
+                    continue;
+                }
+            }
+
+            // Only move the cursor and change colors when we have to.
+            if cursor != Some(index) {
+                let (x, y) = (index as i32 % self.width, index as i32 / self.width);
+                let _ = write!(out, "\x1b[{};{}H", y + 1, x + 1);
+            }
+            if current_color != Some(color) {
+                let _ = write!(out, "\x1b[38;2;{};{};{}m", color.r, color.g, color.b);
+                current_color = Some(color);
+            }
+
+            out.push(c);
+            // Terminals wider than the console don't wrap to the next row of the console.
+            cursor = if (index as i32 + 1) % self.width == 0 {
+                None
+            } else {
+                Some(index + 1)
+            };
+        }
+
+        self.write(&out);
+        self.screen = Some(self.buffer.clone());
+    }
+
+    fn clear(&mut self) {
+        let blank = (' ', self.background);
+        for cell in &mut self.buffer {
+            *cell = blank;
+        }
+    }
+
+    fn get_fps(&self) -> i32 {
+        0
+    }
+}
+
+impl Drop for TerminalConsole {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+const CTRL_C: u8 = 0x03;
+const ESC: u8 = 0x1b;
+
+/// Translates raw terminal input into keys. Unrecognized input is skipped.
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+        i += 1;
+
+        let key = match byte {
+            ESC => {
+                // An escape sequence is read all at once, so a lone escape is the escape key.
+                match (bytes.get(i), bytes.get(i + 1)) {
+                    (Some(b'['), Some(&c)) | (Some(b'O'), Some(&c)) => {
+                        i += 2;
+                        match c {
+                            b'A' => Some(code_key(KeyCode::Up)),
+                            b'B' => Some(code_key(KeyCode::Down)),
+                            b'C' => Some(code_key(KeyCode::Right)),
+                            b'D' => Some(code_key(KeyCode::Left)),
+                            b'P' => Some(code_key(KeyCode::F1)),
+                            b'H' => Some(code_key(KeyCode::Home)),
+                            b'F' => Some(code_key(KeyCode::End)),
+                            _ => {
+                                // Read the rest of a longer sequence, which ends with a letter or
+                                // '~'. Keys such as page up are sent as a number followed by '~'.
+                                let start = i - 1;
+                                let mut last = c;
+                                while !(last.is_ascii_alphabetic() || last == b'~')
+                                    && i < bytes.len()
+                                {
+                                    last = bytes[i];
+                                    i += 1;
+                                }
+
+                                match &bytes[start..i] {
+                                    b"1~" | b"7~" => Some(code_key(KeyCode::Home)),
+                                    b"4~" | b"8~" => Some(code_key(KeyCode::End)),
+                                    b"5~" => Some(code_key(KeyCode::PageUp)),
+                                    b"6~" => Some(code_key(KeyCode::PageDown)),
+                                    _ => None,
+                                }
+                            }
+                        }
+                    }
+                    _ => Some(code_key(KeyCode::Escape)),
+                }
+            }
+            b'\r' | b'\n' => Some(code_key(KeyCode::Enter)),
+            b'\t' => Some(code_key(KeyCode::Tab)),
+            0x7f | 0x08 => Some(code_key(KeyCode::Backspace)),
+            b' ' => {
+                let mut key = code_key(KeyCode::Spacebar);
+                key.printable = ' ';
+                Some(key)
+            }
+            byte if byte.is_ascii_graphic() => Some(printable_key(byte as char)),
+            _ => None,
+        };
+
+        if let Some(key) = key {
+            keys.push(key);
+        }
+    }
+
+    keys
+}
+
+// Returns the number of columns and rows of the terminal.
+fn terminal_size() -> GameResult<(i32, i32)> {
+    unsafe {
+        let mut size: libc::winsize = mem::zeroed();
+        if libc::ioctl(STDOUT, libc::TIOCGWINSZ, &mut size) != 0 {
+            Err(io::Error::last_os_error()).context("Getting the terminal size")?;
+        }
+
+        Ok((i32::from(size.ws_col), i32::from(size.ws_row)))
+    }
+}
+
+// Puts the terminal in raw mode, saving the previous attributes so they can be restored.
+fn enter_raw_mode() -> GameResult<()> {
+    unsafe {
+        let mut termios: libc::termios = mem::zeroed();
+        if libc::tcgetattr(STDIN, &mut termios) != 0 {
+            Err(io::Error::last_os_error()).context("Getting the terminal attributes")?;
+        }
+
+        let mut raw = termios;
+        libc::cfmakeraw(&mut raw);
+        if libc::tcsetattr(STDIN, libc::TCSAFLUSH, &raw) != 0 {
+            Err(io::Error::last_os_error()).context("Switching the terminal to raw mode")?;
+        }
+
+        *SAVED_TERMIOS.lock().unwrap() = Some(termios);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keys() {
+        let keys = parse_keys(b"a>\x1b[A\x1bOP\x1b\r");
+        let codes: Vec<KeyCode> = keys.iter().map(|key| key.code).collect();
+
+        assert_eq!(
+            codes,
+            vec![
+                KeyCode::Char,
+                KeyCode::Char,
+                KeyCode::Up,
+                KeyCode::F1,
+                KeyCode::Escape,
+                KeyCode::Enter
+            ]
+        );
+        assert_eq!(keys[0].printable, 'a');
+        assert_eq!(keys[1].printable, '>');
+
+        let keys = parse_keys(b"\x1b[5~\x1b[6~\x1b[H\x1b[4~");
+        let codes: Vec<KeyCode> = keys.iter().map(|key| key.code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                KeyCode::PageUp,
+                KeyCode::PageDown,
+                KeyCode::Home,
+                KeyCode::End
+            ]
+        );
+
+        // Unknown sequences are skipped.
+        let keys = parse_keys(b"\x1b[15~x");
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].printable, 'x');
+    }
+}
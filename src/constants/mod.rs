@@ -1,12 +1,83 @@
 //! Game constants.
 
+use crate::console::Color;
+
 pub const MESSAGE_DEQUE_SIZE: usize = 1000;
 
+// UI
+
+pub const STATUS_COLOR: Color = Color {
+    r: 0xbd,
+    g: 0xbd,
+    b: 0xb3,
+};
+
+/// Color of ordinary messages.
+pub const MESSAGE_COLOR: Color = STATUS_COLOR;
+/// Color of messages about things going well for the player.
+pub const MESSAGE_GOOD_COLOR: Color = Color {
+    r: 0x7f,
+    g: 0xc9,
+    b: 0x6b,
+};
+/// Color of messages about the player being harmed.
+pub const MESSAGE_BAD_COLOR: Color = Color {
+    r: 0xe0,
+    g: 0x5a,
+    b: 0x4f,
+};
+/// Rows of the screen taken up by the status above the message panel.
+pub const STATUS_HEIGHT: i32 = 3;
+
+// AI
+
+/// Actors flee once their health drops to this percentage of their maximum.
+pub const AI_FLEE_HP_PERCENT: i32 = 25;
+
+// PATHFINDING
+
+/// Cost of stepping onto an open tile.
+pub const PATH_STEP_COST: u32 = 2;
+/// Cost of stepping onto a closed door, which has to be opened first.
+pub const PATH_DOOR_COST: u32 = 5;
+/// Cost of stepping onto a trap, which actors go around unless there is no other way.
+pub const PATH_TRAP_COST: u32 = 40;
+/// Flee maps scale the distance from threats by this percentage.
+pub const PATH_FLEE_PERCENT: i32 = 120;
+
+// TERRAIN
+
+/// Generated tiles at or below this height open up into chasms, if there is a depth below.
+pub const CHASM_HEIGHT: i32 = -4;
+/// Height of an actor's eyes above the tile it stands on. Tiles higher than this block its view.
+pub const EYE_HEIGHT: i32 = 1;
+/// Height differences of at least this much are ledges, which take longer to climb.
+pub const LEDGE_HEIGHT: i32 = 2;
+/// Water at least this deep has to be swum through.
+pub const DEEP_WATER_DEPTH: u32 = 2;
+
+// GENERATION
+
+/// Number of times to generate a depth before giving up, if its levels keep failing validation.
+pub const DUNGEON_TRIES: usize = 10;
+/// Number of spots to try for each new room before the room method stops adding rooms.
+pub const ROOM_TRIES: usize = 100;
+/// Groups and packs of monsters are spawned no further than this many steps from their leader.
+pub const SPAWN_GROUP_RADIUS: usize = 4;
+
+// ITEMS
+
+pub const CORPSE_CHAR: char = '%';
+
 // GAME DATA LOCATIONS
 
 pub const ORG_DIRECTORY: &str = "data/manuals";
 pub const ORG_MANUAL: &str = "game.org";
 
+// SAVE FILES
+
+pub const SAVE_PATH: &str = "save.over";
+
 // MISC
 
 pub const FLAME_PATH: &str = "flame.html";
@@ -22,6 +22,11 @@ impl Coord {
         in_one(self.x, other.x) && in_one(self.y, other.y) && self != other
     }
 
+    /// Returns the number of steps between two `Coord`s when moving in all eight directions.
+    pub fn distance(self, other: Self) -> i32 {
+        max!((self.x - other.x).abs(), (self.y - other.y).abs())
+    }
+
     /// Gets the `Coord` `n` steps in direction `dir`.
     pub fn coord_in_dir<D>(self, dir: &D, n: i32) -> Coord
     where
@@ -88,6 +93,16 @@ mod tests {
         assert!(!coord3.is_adjacent(coord4));
     }
 
+    #[test]
+    fn distance() {
+        let coord = Coord::new(1, 1);
+
+        assert_eq!(coord.distance(coord), 0);
+        assert_eq!(coord.distance(Coord::new(2, 2)), 1);
+        assert_eq!(coord.distance(Coord::new(4, 0)), 3);
+        assert_eq!(coord.distance(Coord::new(-1, 6)), 5);
+    }
+
     #[test]
     fn coord_in_dir() {
         let coord = Coord::new(0, 0);
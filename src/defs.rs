@@ -27,6 +27,12 @@ pub fn bigr_to_f32(value: BigRational) -> GameResult<f32> {
     Ok(numer / denom)
 }
 
+/// Converts an `f32` into a `BigRational`, keeping three decimal places.
+pub fn f32_to_bigr(value: f32) -> BigRational {
+    let thousandths = (value * 1000.0).round() as i64;
+    BigRational::new(BigInt::from(thousandths), BigInt::from(1000))
+}
+
 /// Converts a `BigRational` into a `GameRatio`.
 pub fn bigr_to_gamer(value: BigRational) -> GameResult<GameRatio> {
     let numer = match value.numer().to_u32() {
@@ -41,6 +47,11 @@ pub fn bigr_to_gamer(value: BigRational) -> GameResult<GameRatio> {
     Ok(Ratio::new(numer, denom))
 }
 
+/// Converts a `GameRatio` into a `BigRational`.
+pub fn gamer_to_bigr(value: GameRatio) -> BigRational {
+    BigRational::new(BigInt::from(*value.numer()), BigInt::from(*value.denom()))
+}
+
 pub fn big_to_u32(value: BigInt) -> GameResult<u32> {
     match value.to_u32() {
         Some(n) => Ok(n),
@@ -48,6 +59,13 @@ pub fn big_to_u32(value: BigInt) -> GameResult<u32> {
     }
 }
 
+pub fn big_to_u64(value: BigInt) -> GameResult<u64> {
+    match value.to_u64() {
+        Some(n) => Ok(n),
+        None => err_convert(value, "Value is too large"),
+    }
+}
+
 pub fn big_to_i32(value: BigInt) -> GameResult<i32> {
     match value.to_i32() {
         Some(n) => Ok(n),
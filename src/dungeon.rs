@@ -1,28 +1,35 @@
 //! Dungeon object.
 
 use crate::actor::*;
-use crate::console::DrawConsole;
+use crate::console::{Color, DrawConsole};
+use crate::constants;
 use crate::coord::Coord;
-use crate::defs::{gameratio_max, GameRatio};
-use crate::error::GameError;
+use crate::defs::{big_to_u32, big_to_usize, gameratio_max, GameRatio};
+use crate::error::{err_convert, err_unexpected, GameError};
+use crate::fov::FovMap;
 use crate::game_data::GameData;
 use crate::generate::{gen_dungeon, gen_dungeon_list};
-use crate::item::{Item, ItemStack};
+use crate::item::{Item, ItemStack, ItemStash};
 use crate::object::Object;
-use crate::tile::Tile;
-use crate::util::rand::rand_int;
-use crate::{GameLoopOutcome, GameResult, DATABASE, GAMEDATA};
+use crate::player;
+use crate::save::{arr_from_vec, coord_to_value, decode_rle, encode_rle, get_coord};
+use crate::tile::{Feature, Staircase, Tile, TileInfo};
+use crate::util::direction::CardinalDirection;
+use crate::util::rand::{chance, rand_int, shuffle, Choose};
+use crate::util::string::capitalize;
+use crate::{handle_error, GameLoopOutcome, GameResult, DATABASE, GAMEDATA};
 use failure::ResultExt;
+use over::tup::Tup;
 use over::Obj;
 use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 use std::fmt;
+use std::mem;
 use std::ops::{Deref, DerefMut, Index, IndexMut};
 use std::rc::Rc;
 use std::str::FromStr;
-use tcod::map::FovAlgorithm;
-use tcod::map::Map;
+use std::sync::Arc;
 
 /// Struct containing a single depth of the dungeon.
 /// This struct is also responsible for running the actor priority queue.
@@ -34,13 +41,15 @@ pub struct Dungeon {
     width: usize,
     height: usize,
 
-    pub fov_grid: Map,
-    pub fov_start: Coord,
-    pub fov_end: Coord,
+    fov: FovMap,
+    /// Actors that fell out of this depth, to land on the next depth down.
+    fallen: Vec<Actor>,
 
     // Not serialized.
     actor_queue: BinaryHeap<Actor>,
     object_queue: BinaryHeap<Object>,
+    /// Turn at which liquids next flow.
+    liquid_turn: Option<GameRatio>,
 }
 
 // impl fmt::Debug for Dungeon {
@@ -52,30 +61,259 @@ pub struct Dungeon {
 impl Dungeon {
     pub fn new(danger_level: u32, profile_data: &Obj) -> GameResult<Dungeon> {
         let dungeon_type = DungeonType::from_str(&profile_data.get_str("type")?)?;
-        let ui_settings = GAMEDATA.read().unwrap().ui_settings;
-        let fov_width = (ui_settings.game_width / 2) as i32;
-        let fov_height = (ui_settings.game_height / 2) as i32;
+        let mut dungeon = Dungeon::with_type(danger_level, dungeon_type);
 
-        let mut dungeon = Dungeon {
+        gen_dungeon(&mut dungeon, profile_data)?;
+
+        Ok(dungeon)
+    }
+
+    /// Returns a new `Dungeon` with an empty tile grid.
+    fn with_type(danger_level: u32, dungeon_type: DungeonType) -> Dungeon {
+        Dungeon {
             danger_level,
             dungeon_type,
 
             tile_grid: Vec::with_capacity(0),
-            fov_grid: Map::new(fov_width * 2 + 1, fov_height * 2 + 1),
-            fov_start: Default::default(),
-            fov_end: Default::default(),
+            fov: Default::default(),
             width: 0,
             height: 0,
+            fallen: Vec::new(),
 
             actor_queue: BinaryHeap::new(),
             object_queue: BinaryHeap::new(),
-        };
+            liquid_turn: None,
+        }
+    }
 
-        gen_dungeon(&mut dungeon, profile_data)?;
+    /// Restores a dungeon from data written by `to_save_obj`.
+    pub fn from_save_obj(data: &Obj) -> GameResult<Dungeon> {
+        let danger_level = big_to_u32(data.get_int("danger_level")?)?;
+        let dungeon_type = DungeonType::from_str(&data.get_str("type")?)?;
+        let mut dungeon = Dungeon::with_type(danger_level, dungeon_type);
+
+        let width = big_to_usize(data.get_int("width")?)?;
+        let height = big_to_usize(data.get_int("height")?)?;
+
+        // Restore the tile grid.
+
+        let mut tile_palette: Vec<Arc<TileInfo>> = Vec::new();
+        for key in data.get_arr("tile_palette")?.iter() {
+            let key = key.get_str()?;
+            match GAMEDATA.read().unwrap().tile_info_by_key(&key) {
+                Some(info) => tile_palette.push(info),
+                None => return err_convert(key, "Unknown tile"),
+            }
+        }
+
+        let mut seen_palette: Vec<(char, Color)> = Vec::new();
+        for seen in data.get_arr("seen_palette")?.iter() {
+            let seen = seen.get_tup()?;
+            seen_palette.push((
+                seen.get(0)?.get_char()?,
+                Color::from_str(&seen.get(1)?.get_str()?)?,
+            ));
+        }
+
+        let tiles: Vec<usize> = decode_rle(&data.get_str("tiles")?)?;
+        let seen: Vec<usize> = decode_rle(&data.get_str("seen")?)?;
+        let heights: Vec<i32> = decode_rle(&data.get_str("heights")?)?;
+
+        let mut feature_palette: Vec<Arc<TileInfo>> = Vec::new();
+        for key in data.get_arr("feature_palette")?.iter() {
+            let key = key.get_str()?;
+            match GAMEDATA.read().unwrap().tile_info_by_key(&key) {
+                Some(info) => feature_palette.push(info),
+                None => return err_convert(key, "Unknown feature"),
+            }
+        }
+        let features: Vec<usize> = decode_rle(&data.get_str("features")?)?;
+        let amounts: Vec<u32> = decode_rle(&data.get_str("amounts")?)?;
+
+        let size = width * height;
+        if tiles.len() != size
+            || seen.len() != size
+            || heights.len() != size
+            || features.len() != size
+            || amounts.len() != size
+        {
+            return err_convert(size, "Saved tile grid does not match dungeon dimensions");
+        }
+
+        let mut tile_grid = Vec::with_capacity(size);
+        for i in 0..size {
+            let mut tile = match tile_palette.get(tiles[i]) {
+                Some(info) => Tile::from_info(Arc::clone(info)),
+                None => return err_convert(tiles[i], "Invalid tile index"),
+            };
+            if seen[i] > 0 {
+                match seen_palette.get(seen[i] - 1) {
+                    Some(last_seen) => tile.last_seen.set(Some(*last_seen)),
+                    None => return err_convert(seen[i], "Invalid last seen index"),
+                }
+            }
+            tile.height = heights[i];
+            if features[i] > 0 {
+                match feature_palette.get(features[i] - 1) {
+                    Some(info) => {
+                        tile.feature = Some(Feature {
+                            info: Arc::clone(info),
+                            amount: amounts[i],
+                        })
+                    }
+                    None => return err_convert(features[i], "Invalid feature index"),
+                }
+            }
+
+            tile_grid.push(tile);
+        }
+
+        dungeon.tile_grid = tile_grid;
+        dungeon.width = width;
+        dungeon.height = height;
+
+        // Restore actors, objects and items.
+
+        for actor in data.get_arr("actors")?.iter() {
+            dungeon.add_actor(Actor::from_save_obj(&actor.get_obj()?)?);
+        }
+        for object in data.get_arr("objects")?.iter() {
+            dungeon.add_object(Object::from_save_obj(&object.get_obj()?)?);
+        }
+        for actor in data.get_arr("fallen")?.iter() {
+            dungeon
+                .fallen
+                .push(Actor::from_save_obj(&actor.get_obj()?)?);
+        }
+        for stash in data.get_arr("item_stashes")?.iter() {
+            let stash = stash.get_obj()?;
+            let coord = get_coord(&stash, "coord")?;
+            dungeon[coord].item_stash = Some(Box::new(ItemStash::from_save_arr(
+                &stash.get_arr("items")?,
+            )?));
+        }
 
         Ok(dungeon)
     }
 
+    /// Returns the full state of this dungeon for saving.
+    ///
+    /// The tile grid is stored as run-length encoded indices into palettes of tile types and last
+    /// seen glyphs, which keeps save files small.
+    pub fn to_save_obj(&self) -> GameResult<Obj> {
+        let size = self.tile_grid.len();
+
+        let mut tile_palette: Vec<String> = Vec::new();
+        let mut seen_palette: Vec<(char, Color)> = Vec::new();
+        let mut tiles = Vec::with_capacity(size);
+        let mut seen = Vec::with_capacity(size);
+        let mut heights = Vec::with_capacity(size);
+        let mut feature_palette: Vec<String> = Vec::new();
+        let mut features = Vec::with_capacity(size);
+        let mut amounts = Vec::with_capacity(size);
+        let mut item_stashes = Vec::new();
+
+        for (i, tile) in self.tile_grid.iter().enumerate() {
+            let index = match tile_palette.iter().position(|key| *key == tile.info.key) {
+                Some(index) => index,
+                None => {
+                    tile_palette.push(tile.info.key.clone());
+                    tile_palette.len() - 1
+                }
+            };
+            tiles.push(index);
+
+            // Index 0 means the tile has never been seen.
+            let index = match tile.last_seen.get() {
+                Some(last_seen) => match seen_palette.iter().position(|s| *s == last_seen) {
+                    Some(index) => index + 1,
+                    None => {
+                        seen_palette.push(last_seen);
+                        seen_palette.len()
+                    }
+                },
+                None => 0,
+            };
+            seen.push(index);
+
+            heights.push(tile.height);
+
+            // Index 0 means there is no feature.
+            let index = match tile.feature {
+                Some(ref feature) => {
+                    match feature_palette
+                        .iter()
+                        .position(|key| *key == feature.info.key)
+                    {
+                        Some(index) => index + 1,
+                        None => {
+                            feature_palette.push(feature.info.key.clone());
+                            feature_palette.len()
+                        }
+                    }
+                }
+                None => 0,
+            };
+            features.push(index);
+            amounts.push(tile.feature.as_ref().map_or(0, |feature| feature.amount));
+
+            if let Some(ref stash) = tile.item_stash {
+                let coord = Coord::new((i / self.height) as i32, (i % self.height) as i32);
+                item_stashes.push(
+                    Obj::from_map_unchecked(map! {
+                        "coord".into() => coord_to_value(coord),
+                        "items".into() => stash.to_save_arr()?.into(),
+                    })
+                    .into(),
+                );
+            }
+        }
+
+        let tile_palette = tile_palette.into_iter().map(|key| key.into()).collect();
+        let feature_palette = feature_palette.into_iter().map(|key| key.into()).collect();
+        let seen_palette = seen_palette
+            .into_iter()
+            .map(|(c, color)| Tup::from_vec(vec![c.into(), color.to_code().into()]).into())
+            .collect();
+        let actors = self
+            .actor_queue
+            .iter()
+            .map(|actor| Ok(actor.to_save_obj()?.into()))
+            .collect::<GameResult<_>>()?;
+        let objects = self
+            .object_queue
+            .iter()
+            .map(|object| object.to_save_obj().into())
+            .collect();
+        let fallen = self
+            .fallen
+            .iter()
+            .map(|actor| Ok(actor.to_save_obj()?.into()))
+            .collect::<GameResult<_>>()?;
+
+        Ok(Obj::from_map_unchecked(map! {
+            "danger_level".into() => self.danger_level.into(),
+            "type".into() => self.dungeon_type.to_string().into(),
+
+            "width".into() => self.width.into(),
+            "height".into() => self.height.into(),
+
+            "tile_palette".into() => arr_from_vec(tile_palette)?.into(),
+            "seen_palette".into() => arr_from_vec(seen_palette)?.into(),
+            "tiles".into() => encode_rle(&tiles).into(),
+            "seen".into() => encode_rle(&seen).into(),
+            "heights".into() => encode_rle(&heights).into(),
+            "feature_palette".into() => arr_from_vec(feature_palette)?.into(),
+            "features".into() => encode_rle(&features).into(),
+            "amounts".into() => encode_rle(&amounts).into(),
+
+            "actors".into() => arr_from_vec(actors)?.into(),
+            "objects".into() => arr_from_vec(objects)?.into(),
+            "fallen".into() => arr_from_vec(fallen)?.into(),
+            "item_stashes".into() => arr_from_vec(item_stashes)?.into(),
+        }))
+    }
+
     pub fn init_grid(&mut self, width: usize, height: usize, tile_data: &Obj) -> GameResult<()> {
         self.width = width;
         self.height = height;
@@ -98,27 +336,33 @@ impl Dungeon {
         self.height
     }
 
+    /// Returns true if `coord` is in the player's field of view.
     pub fn visible(&self, coord: Coord) -> bool {
-        let Coord { x, y } = coord;
-        if x < max!(self.fov_start.x, 0)
-            || y < max!(self.fov_start.y, 0)
-            || x > min!(self.fov_end.x + 1, self.width as i32)
-            || y > min!(self.fov_end.y + 1, self.height as i32)
-        {
-            return false;
-        }
+        self.in_bounds(coord) && self.fov.is_visible(coord)
+    }
 
-        self.fov_grid
-            .is_in_fov(x - self.fov_start.x, y - self.fov_start.y)
+    /// Logs a message about an event taking place at `coords`, capitalizing its first letter.
+    /// The message is only logged if the player can see at least one of `coords`.
+    pub fn add_message(&self, coords: &[Coord], text: &str, color: Color) {
+        if coords.iter().any(|&coord| self.visible(coord)) {
+            GAMEDATA
+                .read()
+                .unwrap()
+                .add_message(&capitalize(text), color);
+        }
     }
 
-    pub fn set_visible(&mut self, coord: Coord, transparent: bool) {
-        self.fov_grid.set(
-            coord.x - self.fov_start.x,
-            coord.y - self.fov_start.y,
-            transparent,
-            false,
-        );
+    /// Returns true if light passes through `coord`.
+    pub fn transparent(&self, coord: Coord) -> bool {
+        if !self.in_bounds(coord) {
+            return false;
+        }
+
+        let tile = &self[coord];
+        match tile.object {
+            Some(ref object) => tile.transparent() && object.transparent(),
+            None => tile.transparent(),
+        }
     }
 
     pub fn in_bounds(&self, coord: Coord) -> bool {
@@ -126,40 +370,60 @@ impl Dungeon {
         x >= 0 && y >= 0 && x < self.width() as i32 && y < self.height() as i32
     }
 
-    pub fn calc_fov(&mut self, origin: Coord, fov_radius: i32) {
-        let radius = Coord::new(fov_radius, fov_radius);
-        let start = origin - radius;
-        let end = origin + radius;
+    /// Returns the depth of the water at `coord`, or 0 if there is none to swim or wade through.
+    pub fn water_depth(&self, coord: Coord) -> u32 {
+        match self[coord].feature {
+            Some(ref feature) if feature.info.swim => feature.amount,
+            _ => 0,
+        }
+    }
 
-        self.fov_start = start;
-        self.fov_end = end;
+    /// Returns the height of the ground at `coord`, or of the surface of any liquid covering it.
+    pub fn surface(&self, coord: Coord) -> i32 {
+        let tile = &self[coord];
+        match tile.feature {
+            Some(ref feature) if feature.liquid() => tile.height + feature.amount as i32,
+            _ => tile.height,
+        }
+    }
 
-        self.fov_grid.clear(false, false);
+    /// Returns how many times as long as usual it takes to step from `from` onto `to`. Climbing up
+    /// or down takes longer, more so for ledges, as does wading or swimming through water.
+    pub fn move_cost(&self, from: Coord, to: Coord) -> GameRatio {
+        let climb = (self.surface(to) - self.surface(from)).abs();
+        let climb_cost = if climb >= constants::LEDGE_HEIGHT {
+            GameRatio::from_integer(2)
+        } else if climb > 0 {
+            GameRatio::new(3, 2)
+        } else {
+            GameRatio::from_integer(1)
+        };
 
-        for x in max!(start.x, 0)..min!(end.x + 1, self.width as i32) {
-            for y in max!(start.y, 0)..min!(end.y + 1, self.height as i32) {
-                let coord = Coord::new(x, y);
+        let depth = self.water_depth(to);
+        let water_cost = if depth >= constants::DEEP_WATER_DEPTH {
+            GameRatio::from_integer(2)
+        } else if depth > 0 {
+            GameRatio::new(3, 2)
+        } else {
+            GameRatio::from_integer(1)
+        };
 
-                // Set tile transparency.
-                let mut transparent = self[coord].transparent();
+        climb_cost * water_cost
+    }
 
-                // If object is not transparent, override.
-                if let Some(ref object) = self[coord].object {
-                    if !object.transparent() {
-                        transparent = false
-                    }
-                }
-                self.set_visible(coord, transparent);
-            }
-        }
+    /// Returns the field of view from `origin` within `fov_radius`. Tiles rising above the eyes of
+    /// someone standing at `origin` block the view.
+    pub fn compute_fov(&self, origin: Coord, fov_radius: i32) -> FovMap {
+        let algorithm = GAMEDATA.read().unwrap().fov_algorithm;
+        let eye_height = self[origin].height + constants::EYE_HEIGHT;
+        FovMap::compute(algorithm, origin, fov_radius, |coord| {
+            self.transparent(coord) && self[coord].height <= eye_height
+        })
+    }
 
-        self.fov_grid.compute_fov(
-            origin.x - start.x,
-            origin.y - start.y,
-            fov_radius,
-            true,
-            FovAlgorithm::Diamond,
-        );
+    /// Calculates the player's field of view, which determines what is drawn.
+    pub fn calc_fov(&mut self, origin: Coord, fov_radius: i32) {
+        self.fov = self.compute_fov(origin, fov_radius);
     }
 
     /// Returns the number of actors in the dungeon.
@@ -167,6 +431,14 @@ impl Dungeon {
         self.actor_queue.len()
     }
 
+    /// Returns the player if it is in this dungeon.
+    pub fn find_player(&self) -> Option<Actor> {
+        self.actor_queue
+            .iter()
+            .find(|actor| actor.behavior() == Behavior::Player)
+            .cloned()
+    }
+
     /// Adds actor to both the tile grid and the priority queue.
     pub fn add_actor(&mut self, actor: Actor) {
         let coord = actor.coord();
@@ -201,6 +473,62 @@ impl Dungeon {
         self[coord].actor.take().unwrap()
     }
 
+    /// Removes the actor at `coord`, which fell through the floor. It lands on the next depth down
+    /// once the player gets there.
+    pub fn drop_actor(&mut self, coord: Coord) {
+        let actor = self.remove_actor(coord);
+        self.fallen.push(actor);
+    }
+
+    /// Takes the actors that fell out of this depth.
+    pub fn take_fallen(&mut self) -> Vec<Actor> {
+        mem::take(&mut self.fallen)
+    }
+
+    /// Returns true if `actor` is still in the tile grid, i.e. it hasn't died or left the depth.
+    pub fn contains_actor(&self, actor: &Actor) -> bool {
+        if !self.in_bounds(actor.coord()) {
+            return false;
+        }
+
+        match self[actor.coord()].actor {
+            Some(ref other) => Arc::ptr_eq(&actor.inner, &other.inner),
+            None => false,
+        }
+    }
+
+    /// Sends every hostile actor hunting towards `coord`.
+    pub fn alert_actors(&mut self, coord: Coord) {
+        for actor in self.actor_queue.iter() {
+            let mut inner = actor.inner.lock().unwrap();
+            if inner.behavior == Behavior::Hostile || inner.behavior == Behavior::Hunting {
+                inner.behavior = Behavior::Hunting;
+                inner.target = Some(coord);
+            }
+        }
+    }
+
+    /// Removes a dead actor from the dungeon, leaving its corpse and belongings behind.
+    pub fn kill_actor(&mut self, coord: Coord) {
+        let actor = self.remove_actor(coord);
+
+        let (inventory, equipment) = {
+            let mut inner = actor.inner.lock().unwrap();
+            (
+                mem::take(&mut inner.inventory),
+                mem::take(&mut inner.equipment),
+            )
+        };
+        for stack in inventory.iter() {
+            self.add_item(coord, stack.clone());
+        }
+        for (_, item) in equipment.iter() {
+            self.add_item(coord, ItemStack::new(item.clone(), 1));
+        }
+
+        self.add_item(coord, ItemStack::new(Item::corpse(&actor), 1));
+    }
+
     /// Inserts an object into the tile grid.
     pub fn add_object(&mut self, object: Object) {
         let coord = object.coord();
@@ -218,11 +546,45 @@ impl Dungeon {
         self[new_coord].object = Some(object);
     }
 
+    /// Returns every object sharing `link`, such as the doors controlled by a lever.
+    pub fn linked_objects(&self, link: &str) -> Vec<Object> {
+        self.object_queue
+            .iter()
+            .filter(|object| object.inner.lock().unwrap().link() == Some(link))
+            .cloned()
+            .collect()
+    }
+
     /// Removes an object from the tile grid.
     pub fn remove_object(&mut self, coord: Coord) -> Object {
         self[coord].object.take().unwrap()
     }
 
+    /// Adds an item stack to the stash at `coord`, creating the stash if necessary.
+    pub fn add_item(&mut self, coord: Coord, stack: ItemStack) {
+        self[coord]
+            .item_stash
+            .get_or_insert_with(Default::default)
+            .add(stack);
+    }
+
+    /// Removes `amount` items from the stack at `index` in the stash at `coord` and returns them.
+    /// The stash is removed once it is empty.
+    pub fn take_item(&mut self, coord: Coord, index: usize, amount: usize) -> ItemStack {
+        let tile = &mut self[coord];
+        let (stack, empty) = {
+            let stash = tile.item_stash.as_mut().unwrap();
+            let stack = stash.take(index, amount);
+            (stack, stash.is_empty())
+        };
+
+        if empty {
+            tile.item_stash = None;
+        }
+
+        stack
+    }
+
     /// Returns the amount of stacks in a stash.
     pub fn stash_size(&self, coord: Coord) -> usize {
         match self[coord].item_stash {
@@ -239,22 +601,111 @@ impl Dungeon {
         )
     }
 
-    /// Returns a random available coordinate, not currently occupied by any actors.
-    // TODO: Avoid picking staircases.
-    // TODO: Fail after some number of tries.
+    /// Returns a random available coordinate, not currently occupied by any actors, or `None` if
+    /// there is no such coordinate. Staircases are never picked.
     pub fn random_open_coord_actor(&self) -> Option<Coord> {
-        let mut available = false;
-        let mut coord: Coord = Default::default();
+        let open: Vec<Coord> = (0..self.width as i32)
+            .flat_map(|x| (0..self.height as i32).map(move |y| Coord::new(x, y)))
+            .filter(|coord| self.open_for_spawn(*coord))
+            .collect();
+
+        open.choose().cloned()
+    }
+
+    /// Returns true if a new actor may be placed at `coord`. Unlike `open_for_actor`, tiles with
+    /// any object, feature or a staircase don't count as open.
+    pub fn open_for_spawn(&self, coord: Coord) -> bool {
+        if !self.in_bounds(coord) {
+            return false;
+        }
+
+        let tile = &self[coord];
+        tile.actor.is_none()
+            && tile.object.is_none()
+            && tile.feature.is_none()
+            && tile.passable()
+            && tile.staircase() == Staircase::None
+    }
+
+    /// Returns true if an actor could step onto `coord` right now. Traps and hazards don't count as
+    /// open.
+    pub fn open_for_actor(&self, coord: Coord) -> bool {
+        if !self.in_bounds(coord) {
+            return false;
+        }
+
+        let tile = &self[coord];
+        let object_passable = match tile.object {
+            Some(ref object) => object.passable() && object.trap().is_none(),
+            None => true,
+        };
+
+        tile.passable() && !tile.hazard() && tile.actor.is_none() && object_passable
+    }
+
+    /// Returns the coordinate closest to `coord` that an actor could step onto, or `None` if there
+    /// isn't one.
+    pub fn nearest_open_coord_actor(&self, coord: Coord) -> Option<Coord> {
+        let max_radius = max!(self.width, self.height) as i32;
+
+        for radius in 0..=max_radius {
+            for x in coord.x - radius..=coord.x + radius {
+                for y in coord.y - radius..=coord.y + radius {
+                    // Only check the ring at the current radius.
+                    if (x - coord.x).abs() != radius && (y - coord.y).abs() != radius {
+                        continue;
+                    }
+
+                    let candidate = Coord::new(x, y);
+                    if self.open_for_actor(candidate) {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+
+        None
+    }
 
-        while !available {
-            coord = self.random_coord();
-            let tile = &self[coord];
-            // Don't put any actors on top of objects.
-            // Should use some other function for that.
-            available = tile.actor.is_none() && tile.object.is_none() && tile.passable();
+    /// Returns the location of a staircase leading in `direction`, if there is one.
+    pub fn find_staircase(&self, direction: DepthDirection) -> Option<Coord> {
+        for x in 0..self.width as i32 {
+            for y in 0..self.height as i32 {
+                let coord = Coord::new(x, y);
+                if self[coord].staircase().leads(direction) {
+                    return Some(coord);
+                }
+            }
         }
 
-        Some(coord)
+        None
+    }
+
+    /// Delays every actor and object scheduled before `turn` so that it acts after `turn`, and lets
+    /// liquids next flow on `turn`.
+    ///
+    /// Time does not pass on depths the player is not on, so this is called when the player
+    /// arrives to prevent a backlog of turns from being played out all at once.
+    pub fn catch_up(&mut self, turn: GameRatio) {
+        let actors: Vec<Actor> = self.actor_queue.drain().collect();
+        for mut actor in actors {
+            if actor.turn() < turn {
+                let speed = actor.speed();
+                actor.set_turn(turn + speed);
+            }
+            self.actor_queue.push(actor);
+        }
+
+        let objects: Vec<Object> = self.object_queue.drain().collect();
+        for mut object in objects {
+            if object.turn() < turn {
+                let speed = object.speed();
+                object.set_turn(turn + speed);
+            }
+            self.object_queue.push(object);
+        }
+
+        self.liquid_turn = Some(turn.ceil());
     }
 
     /// Runs the main game loop by iterating over the actor/object priority queues.
@@ -301,18 +752,41 @@ impl Dungeon {
 
             // Update the global game turn.
             GAMEDATA.write().unwrap().set_turn(actor_turn.unwrap());
-
-            match actor.act(self) {
-                ActResult::WindowClosed => return GameLoopOutcome::WindowClosed,
-                ActResult::QuitGame => return GameLoopOutcome::QuitGame,
-                ActResult::None => (),
+            self.update_liquids(actor_turn.unwrap());
+
+            let outcome = match actor.act(self) {
+                ActResult::WindowClosed => GameLoopOutcome::WindowClosed,
+                ActResult::QuitGame => GameLoopOutcome::QuitGame,
+                ActResult::SaveGame => GameLoopOutcome::SaveGame,
+                ActResult::PlayerDead => {
+                    actor.update_turn();
+                    GameLoopOutcome::PlayerDead
+                }
+                ActResult::DepthChanged(direction) => {
+                    actor.update_turn();
+                    GameLoopOutcome::DepthChanged(direction)
+                }
+                ActResult::FellDown => {
+                    actor.update_turn();
+                    GameLoopOutcome::FellDown
+                }
+                ActResult::None => {
+                    actor.update_turn();
+                    GameLoopOutcome::None
+                }
             };
-
-            actor.update_turn();
             *actor_turn = None;
 
-            // Push the actor back on the queue.
-            self.actor_queue.push(actor);
+            // Push the actor back on the queue. If the actor did not finish its turn, it keeps its
+            // place at the front so that the game can resume where it left off. Actors that died
+            // or left the depth during their turn are dropped.
+            if self.contains_actor(&actor) {
+                self.actor_queue.push(actor);
+            }
+
+            if outcome != GameLoopOutcome::None {
+                return outcome;
+            }
         } else {
             // Object acting.
 
@@ -324,9 +798,7 @@ impl Dungeon {
             // Update the global game turn.
             GAMEDATA.write().unwrap().set_turn(object_turn.unwrap());
 
-            match object.act(self) {
-                _ => (),
-            };
+            object.act(self);
 
             object.update_turn();
             *object_turn = None;
@@ -338,6 +810,74 @@ impl Dungeon {
         GameLoopOutcome::None
     }
 
+    /// Lets liquids flow once for every whole turn that has passed up to `turn`.
+    pub fn update_liquids(&mut self, turn: GameRatio) {
+        let mut liquid_turn = self.liquid_turn.unwrap_or_else(|| turn.ceil());
+        while liquid_turn <= turn {
+            self.flow_liquids();
+            liquid_turn += 1;
+        }
+        self.liquid_turn = Some(liquid_turn);
+    }
+
+    /// Spreads liquids to lower ground. Each liquid tile has a chance of its material's `flow` to
+    /// pass one unit of liquid to the neighbor with the lowest surface, if that is at least two
+    /// lower than its own. Liquids pour away into chasms and don't mix with other features.
+    pub fn flow_liquids(&mut self) {
+        let mut liquids: Vec<Coord> = (0..self.width as i32)
+            .flat_map(|x| (0..self.height as i32).map(move |y| Coord::new(x, y)))
+            .filter(|coord| match self[*coord].feature {
+                Some(ref feature) => feature.liquid(),
+                None => false,
+            })
+            .collect();
+        shuffle(&mut liquids);
+
+        for coord in liquids {
+            let feature = match self[coord].feature {
+                Some(ref feature) if feature.liquid() => feature.clone(),
+                _ => continue,
+            };
+            if !chance(feature.info.material.flow) {
+                continue;
+            }
+
+            let level = self.surface(coord);
+            let target = CardinalDirection::all()
+                .iter()
+                .map(|dir| coord.coord_in_dir(dir, 1))
+                .filter(|next| self.in_bounds(*next) && self[*next].passable())
+                .filter(|next| match self[*next].feature {
+                    Some(ref other) => other.info.fall || Arc::ptr_eq(&other.info, &feature.info),
+                    None => true,
+                })
+                .filter(|next| self.surface(*next) < level - 1)
+                .min_by_key(|next| self.surface(*next));
+            let target = match target {
+                Some(target) => target,
+                None => continue,
+            };
+
+            match self[target].feature {
+                Some(ref mut other) if !other.info.fall => other.amount += 1,
+                Some(_) => (), // Poured into a chasm.
+                None => {
+                    self[target].feature = Some(Feature {
+                        info: Arc::clone(&feature.info),
+                        amount: 1,
+                    })
+                }
+            }
+            if feature.amount > 1 {
+                if let Some(ref mut source) = self[coord].feature {
+                    source.amount -= 1;
+                }
+            } else {
+                self[coord].feature = None;
+            }
+        }
+    }
+
     pub fn peek_actor(&self) -> Actor {
         self.actor_queue.peek().unwrap().clone()
     }
@@ -393,6 +933,22 @@ impl<'a> IndexMut<&'a Coord> for Dungeon {
 pub struct DungeonList {
     dungeon_list: Vec<Dungeon>,
     pub current_depth: usize,
+
+    /// Landscape above the first depth, if the game has one.
+    pub overworld: Option<Dungeon>,
+    /// Whether the player is on the overworld instead of at `current_depth`.
+    pub on_overworld: bool,
+    // Dungeon entrance the player last went down, where they come back out.
+    entrance: Option<Coord>,
+    // Actors that fell out of a depth the player left, with the depth they land on.
+    fallen: Vec<(usize, Actor)>,
+}
+
+// A level the player can be on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Level {
+    Overworld,
+    Depth(usize),
 }
 
 impl DungeonList {
@@ -407,20 +963,282 @@ impl DungeonList {
             .context("Parsing main.dungeons.dungeons")?;
         let num_dungeons = dungeons.len();
 
-        let dungeon_list = DungeonList {
-            dungeon_list: Vec::with_capacity(num_dungeons),
+        let dungeon_list = DungeonList::from_dungeons(Vec::with_capacity(num_dungeons));
+        let dungeon_list = gen_dungeon_list(dungeon_list, &dungeons, num_dungeons)?;
+
+        Ok(dungeon_list)
+    }
+
+    /// Creates a `DungeonList` from already generated dungeons, starting at the first depth.
+    pub fn from_dungeons(dungeon_list: Vec<Dungeon>) -> DungeonList {
+        DungeonList {
+            dungeon_list,
             current_depth: 0,
-        };
 
-        let dungeon_list = gen_dungeon_list(dungeon_list, &dungeons, num_dungeons)?;
+            overworld: None,
+            on_overworld: false,
+            entrance: None,
+            fallen: Vec::new(),
+        }
+    }
+
+    /// Restores a list of dungeons from data written by `to_save_obj`.
+    pub fn from_save_obj(data: &Obj) -> GameResult<DungeonList> {
+        let mut dungeon_list = Vec::new();
+
+        for (depth, dungeon) in data.get_arr("dungeons")?.iter().enumerate() {
+            dungeon_list.push(
+                Dungeon::from_save_obj(&dungeon.get_obj()?)
+                    .context(format!("Loading dungeon at depth {}", depth))?,
+            );
+        }
+
+        let mut dungeon_list = DungeonList::from_dungeons(dungeon_list);
+        dungeon_list.current_depth = big_to_usize(data.get_int("current_depth")?)?;
+
+        if data.contains("overworld") {
+            dungeon_list.overworld = Some(
+                Dungeon::from_save_obj(&data.get_obj("overworld")?).context("Loading overworld")?,
+            );
+            dungeon_list.on_overworld = data.get_bool("on_overworld")?;
+        }
+        if data.contains("entrance") {
+            dungeon_list.entrance = Some(get_coord(data, "entrance")?);
+        }
+        for fallen in data.get_arr("fallen")?.iter() {
+            let fallen = fallen.get_obj()?;
+            dungeon_list.fallen.push((
+                big_to_usize(fallen.get_int("depth")?)?,
+                Actor::from_save_obj(&fallen.get_obj("actor")?)?,
+            ));
+        }
 
         Ok(dungeon_list)
     }
 
+    /// Returns the full state of all dungeons for saving.
+    pub fn to_save_obj(&self) -> GameResult<Obj> {
+        let mut dungeons = Vec::with_capacity(self.dungeon_list.len());
+        for dungeon in &self.dungeon_list {
+            dungeons.push(dungeon.to_save_obj()?.into());
+        }
+
+        let mut fallen = Vec::with_capacity(self.fallen.len());
+        for (depth, actor) in &self.fallen {
+            fallen.push(
+                Obj::from_map_unchecked(map! {
+                    "depth".into() => (*depth).into(),
+                    "actor".into() => actor.to_save_obj()?.into(),
+                })
+                .into(),
+            );
+        }
+
+        let mut map = map! {
+            "current_depth".into() => self.current_depth.into(),
+            "dungeons".into() => arr_from_vec(dungeons)?.into(),
+            "fallen".into() => arr_from_vec(fallen)?.into(),
+        };
+        if let Some(ref overworld) = self.overworld {
+            map.insert("overworld".into(), overworld.to_save_obj()?.into());
+            map.insert("on_overworld".into(), self.on_overworld.into());
+        }
+        if let Some(entrance) = self.entrance {
+            map.insert("entrance".into(), coord_to_value(entrance));
+        }
+
+        Ok(Obj::from_map_unchecked(map))
+    }
+
     /// Returns a mutable reference to the current dungeon.
     pub fn current_dungeon(&mut self) -> &mut Dungeon {
-        let index = self.current_depth;
-        &mut self.dungeon_list[index]
+        match (self.on_overworld, self.overworld.as_mut()) {
+            (true, Some(overworld)) => overworld,
+            _ => &mut self.dungeon_list[self.current_depth],
+        }
+    }
+
+    /// Moves the player from the current depth to the adjacent depth in `direction`.
+    ///
+    /// The player arrives on the connecting staircase, and any adjacent actors that follow the
+    /// player are brought along and placed nearby. Going up from the first depth leads out to the
+    /// dungeon entrance on the overworld, and going down an entrance leads to the first depth.
+    pub fn change_depth(&mut self, direction: DepthDirection) -> GameResult<()> {
+        let new_level = self.adjacent_level(direction)?;
+        let arrival_direction = match direction {
+            DepthDirection::Up => DepthDirection::Down,
+            DepthDirection::Down => DepthDirection::Up,
+        };
+
+        self.move_player(new_level, Some(arrival_direction))
+    }
+
+    /// Drops the player to a random location on the next depth down, leaving any followers behind.
+    pub fn fall_down(&mut self) -> GameResult<()> {
+        let new_level = self.adjacent_level(DepthDirection::Down)?;
+        self.move_player(new_level, None)
+    }
+
+    /// Returns true if there is a depth below the current one.
+    pub fn has_depth_below(&self) -> bool {
+        if self.on_overworld {
+            !self.dungeon_list.is_empty()
+        } else {
+            self.current_depth + 1 < self.dungeon_list.len()
+        }
+    }
+
+    // Returns the level the player is on.
+    fn level(&self) -> Level {
+        if self.on_overworld {
+            Level::Overworld
+        } else {
+            Level::Depth(self.current_depth)
+        }
+    }
+
+    // Returns the level next to the current one in `direction`.
+    fn adjacent_level(&self, direction: DepthDirection) -> GameResult<Level> {
+        match (self.level(), direction) {
+            (Level::Overworld, DepthDirection::Down) if self.has_depth_below() => {
+                Ok(Level::Depth(0))
+            }
+            (Level::Depth(0), DepthDirection::Up) if self.overworld.is_some() => {
+                Ok(Level::Overworld)
+            }
+            (Level::Depth(depth), DepthDirection::Up) if depth > 0 => Ok(Level::Depth(depth - 1)),
+            (Level::Depth(depth), DepthDirection::Down) if self.has_depth_below() => {
+                Ok(Level::Depth(depth + 1))
+            }
+            _ => err_unexpected("No depth in that direction"),
+        }
+    }
+
+    // Returns the dungeon at `level`.
+    fn level_dungeon(&mut self, level: Level) -> GameResult<&mut Dungeon> {
+        match level {
+            Level::Overworld => match self.overworld {
+                Some(ref mut overworld) => Ok(overworld),
+                None => err_unexpected("No overworld"),
+            },
+            Level::Depth(depth) => Ok(&mut self.dungeon_list[depth]),
+        }
+    }
+
+    // Moves the player to `new_level`. Players arriving by stairs come out at the staircase leading
+    // in `arrival_direction`, bringing along their followers, while players arriving any other way
+    // land at a random location. Actors that fell out of the old level are queued for the level
+    // below it, and any queued for the new level land at random locations.
+    fn move_player(
+        &mut self,
+        new_level: Level,
+        arrival_direction: Option<DepthDirection>,
+    ) -> GameResult<()> {
+        let old_level = self.level();
+
+        // Take the player and any followers out of the old level.
+
+        let old_dungeon = self.level_dungeon(old_level)?;
+        let player = match old_dungeon.find_player() {
+            Some(player) => player,
+            None => return err_unexpected("No player found in current dungeon"),
+        };
+        let player_coord = player.coord();
+
+        let mut followers = Vec::new();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let coord = player_coord + Coord::new(dx, dy);
+                if arrival_direction.is_none()
+                    || coord == player_coord
+                    || !old_dungeon.in_bounds(coord)
+                {
+                    continue;
+                }
+
+                let follows = match old_dungeon[coord].actor {
+                    Some(ref actor) => actor.follows_player(),
+                    None => false,
+                };
+                if follows {
+                    followers.push(old_dungeon.remove_actor(coord));
+                }
+            }
+        }
+        let mut player = old_dungeon.remove_actor(player_coord);
+        let fallen = old_dungeon.take_fallen();
+
+        let below = match old_level {
+            Level::Overworld => 0,
+            Level::Depth(depth) => depth + 1,
+        };
+        if below < self.dungeon_list.len() {
+            self.fallen
+                .extend(fallen.into_iter().map(|actor| (below, actor)));
+        }
+        let (landing, queued): (Vec<_>, Vec<_>) = self
+            .fallen
+            .drain(..)
+            .partition(|(depth, _)| Level::Depth(*depth) == new_level);
+        self.fallen = queued;
+
+        if old_level == Level::Overworld {
+            self.entrance = Some(player_coord);
+        }
+        let entrance = self.entrance;
+
+        // Put them into the new level.
+
+        let new_dungeon = self.level_dungeon(new_level)?;
+        for (_, mut actor) in landing {
+            // Actors with nowhere to land are lost.
+            if let Some(coord) = new_dungeon.random_open_coord_actor() {
+                actor.set_coord(coord);
+                new_dungeon.add_actor(actor);
+            }
+        }
+        new_dungeon.catch_up(GAMEDATA.read().unwrap().turn());
+
+        let arrival = match arrival_direction {
+            Some(direction) => {
+                let staircase = match (new_level, entrance) {
+                    (Level::Overworld, Some(entrance)) => Some(entrance),
+                    _ => new_dungeon.find_staircase(direction),
+                };
+                let staircase = match staircase {
+                    Some(coord) => coord,
+                    None => return err_unexpected("No connecting staircase found"),
+                };
+                new_dungeon.nearest_open_coord_actor(staircase)
+            }
+            None => new_dungeon.random_open_coord_actor(),
+        };
+        let arrival = match arrival {
+            Some(coord) => coord,
+            None => return err_unexpected("No room for the player on the new depth"),
+        };
+        player.set_coord(arrival);
+        new_dungeon.add_actor(player.clone());
+
+        for mut follower in followers {
+            // Followers that don't fit are left behind.
+            if let Some(coord) = new_dungeon.nearest_open_coord_actor(arrival) {
+                follower.set_coord(coord);
+                new_dungeon.add_actor(follower);
+            }
+        }
+
+        player::calc_fov(&player, new_dungeon);
+
+        match new_level {
+            Level::Overworld => self.on_overworld = true,
+            Level::Depth(depth) => {
+                self.on_overworld = false;
+                self.current_depth = depth;
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -438,9 +1256,12 @@ impl DerefMut for DungeonList {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DungeonType {
     Room,
+    Bsp,
+    Cave,
+    Overworld,
     /// Used in tests.
     Empty,
 }
@@ -453,6 +1274,9 @@ impl FromStr for DungeonType {
 
         Ok(match s {
             "room" => Room,
+            "bsp" => Bsp,
+            "cave" => Cave,
+            "overworld" => Overworld,
             "empty" => Empty,
             _ => {
                 return Err(GameError::ConversionError {
@@ -464,10 +1288,40 @@ impl FromStr for DungeonType {
     }
 }
 
+impl fmt::Display for DungeonType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::DungeonType::*;
+
+        write!(
+            f,
+            "{}",
+            match *self {
+                Room => "room",
+                Bsp => "bsp",
+                Cave => "cave",
+                Overworld => "overworld",
+                Empty => "empty",
+            }
+        )
+    }
+}
+
+/// Direction of travel between depths.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthDirection {
+    Up,
+    Down,
+}
+
 /// Enum of possible results of an action.
 #[derive(Debug, PartialEq)]
 pub enum ActResult {
     WindowClosed,
     QuitGame,
+    SaveGame,
+    DepthChanged(DepthDirection),
+    /// The player fell through a trap door to the next depth.
+    FellDown,
+    PlayerDead,
     None,
 }
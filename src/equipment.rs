@@ -0,0 +1,208 @@
+//! Module for equipment worn or wielded by actors.
+
+use crate::defs::big_to_i32;
+use crate::item::Item;
+use crate::save::arr_from_vec;
+use crate::{GameError, GameResult};
+use over::arr::Arr;
+use over::Obj;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Add, AddAssign};
+use std::str::FromStr;
+
+/// Places on an actor where an item can be equipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EquipSlot {
+    Weapon,
+    Shield,
+    Head,
+    Body,
+}
+
+impl EquipSlot {
+    /// Returns all slots, in order.
+    pub fn all() -> [EquipSlot; 4] {
+        use self::EquipSlot::*;
+
+        [Weapon, Shield, Head, Body]
+    }
+
+    /// Returns true if items in this slot are wielded rather than worn.
+    pub fn is_wielded(self) -> bool {
+        self == EquipSlot::Weapon
+    }
+}
+
+impl FromStr for EquipSlot {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use self::EquipSlot::*;
+
+        Ok(match s {
+            "weapon" => Weapon,
+            "shield" => Shield,
+            "head" => Head,
+            "body" => Body,
+            _ => {
+                return Err(GameError::ConversionError {
+                    val: s.into(),
+                    msg: "Invalid equipment slot",
+                });
+            }
+        })
+    }
+}
+
+impl fmt::Display for EquipSlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::EquipSlot::*;
+
+        write!(
+            f,
+            "{}",
+            match *self {
+                Weapon => "weapon",
+                Shield => "shield",
+                Head => "head",
+                Body => "body",
+            }
+        )
+    }
+}
+
+/// Changes to an actor's combat stats granted by equipment. Values can be negative.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Modifiers {
+    pub attack: i32,
+    pub defense: i32,
+    pub damage: i32,
+}
+
+impl Modifiers {
+    /// Loads modifiers from the `attack`, `defense` and `damage` fields of `data`.
+    pub fn new(data: &Obj) -> GameResult<Modifiers> {
+        Ok(Modifiers {
+            attack: big_to_i32(data.get_int("attack")?)?,
+            defense: big_to_i32(data.get_int("defense")?)?,
+            damage: big_to_i32(data.get_int("damage")?)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Modifiers::default()
+    }
+
+    /// Applies a modifier to a base stat, which can't drop below zero.
+    pub fn apply(base: u32, modifier: i32) -> u32 {
+        (base as i32 + modifier).max(0) as u32
+    }
+}
+
+impl Add for Modifiers {
+    type Output = Modifiers;
+
+    fn add(self, other: Modifiers) -> Modifiers {
+        Modifiers {
+            attack: self.attack + other.attack,
+            defense: self.defense + other.defense,
+            damage: self.damage + other.damage,
+        }
+    }
+}
+
+impl AddAssign for Modifiers {
+    fn add_assign(&mut self, other: Modifiers) {
+        *self = *self + other;
+    }
+}
+
+/// Displays the non-zero modifiers, e.g. "+2 attack, -1 defense".
+impl fmt::Display for Modifiers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stats = [
+            (self.attack, "attack"),
+            (self.defense, "defense"),
+            (self.damage, "damage"),
+        ];
+        let parts: Vec<String> = stats
+            .iter()
+            .filter(|(value, _)| *value != 0)
+            .map(|(value, name)| format!("{:+} {}", value, name))
+            .collect();
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// The items an actor has equipped, at most one per slot.
+#[derive(Clone, Debug, Default)]
+pub struct Equipment {
+    slots: BTreeMap<EquipSlot, Item>,
+}
+
+impl Equipment {
+    /// Restores equipment from data written by `to_save_arr`.
+    pub fn from_save_arr(data: &Arr) -> GameResult<Equipment> {
+        let mut equipment = Equipment::default();
+
+        for item in data.iter() {
+            equipment.equip(Item::from_save_obj(&item.get_obj()?)?);
+        }
+
+        Ok(equipment)
+    }
+
+    /// Returns the full state of this equipment for saving.
+    pub fn to_save_arr(&self) -> GameResult<Arr> {
+        arr_from_vec(
+            self.slots
+                .values()
+                .map(|item| item.to_save_obj().into())
+                .collect(),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Returns the item equipped in `slot`.
+    pub fn get(&self, slot: EquipSlot) -> Option<&Item> {
+        self.slots.get(&slot)
+    }
+
+    /// Equips `item` in its slot, returning the item that was there before.
+    ///
+    /// # Panics
+    ///
+    /// If the item can't be equipped.
+    pub fn equip(&mut self, item: Item) -> Option<Item> {
+        let slot = item.slot().expect("Equipping an item without a slot");
+        self.slots.insert(slot, item)
+    }
+
+    /// Removes and returns the item equipped in `slot`.
+    pub fn remove(&mut self, slot: EquipSlot) -> Option<Item> {
+        self.slots.remove(&slot)
+    }
+
+    /// Returns an iterator over the equipped items and their slots, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (EquipSlot, &Item)> {
+        self.slots.iter().map(|(slot, item)| (*slot, item))
+    }
+
+    /// Returns the combined modifiers of all equipped items.
+    pub fn modifiers(&self) -> Modifiers {
+        let mut total = Modifiers::default();
+        for item in self.slots.values() {
+            total += item.modifiers();
+        }
+        total
+    }
+}
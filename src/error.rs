@@ -6,12 +6,28 @@ use std::fmt::Display;
 /// Error type used throughout the game.
 #[derive(Debug, Fail)]
 pub enum GameError {
+    #[fail(display = "invalid argument: {}. {}", arg, msg)]
+    ArgumentError { arg: String, msg: &'static str },
     #[fail(display = "conversion error: {}. {}", val, msg)]
     ConversionError { val: String, msg: &'static str },
+    #[fail(
+        display = "failed to generate a valid dungeon at depth {} after {} tries",
+        depth, tries
+    )]
+    GenerationError { depth: u32, tries: usize },
+    #[fail(
+        display = "incompatible save file version: found {}, expected {}",
+        found, expected
+    )]
+    SaveVersionError { found: u32, expected: u32 },
     #[fail(display = "unexpected error: {}", msg)]
     UnexpectedError { msg: &'static str },
 }
 
+pub fn err_argument<R>(arg: String, msg: &'static str) -> GameResult<R> {
+    Err(GameError::ArgumentError { arg, msg }.into())
+}
+
 pub fn err_convert<T, R>(val: T, msg: &'static str) -> GameResult<R>
 where
     T: Display,
@@ -23,6 +39,10 @@ where
     .into())
 }
 
+pub fn err_generation<R>(depth: u32, tries: usize) -> GameResult<R> {
+    Err(GameError::GenerationError { depth, tries }.into())
+}
+
 pub fn err_unexpected<R>(msg: &'static str) -> GameResult<R> {
     Err(GameError::UnexpectedError { msg }.into())
 }
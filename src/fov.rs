@@ -0,0 +1,405 @@
+//! Field of view calculation.
+//!
+//! FOV is computed over any grid through a closure reporting which coordinates are transparent, so
+//! that it can be used for the player, for monsters and for anything else that needs a view.
+
+use crate::coord::Coord;
+use crate::error::GameError;
+use num::rational::Ratio;
+use std::fmt;
+use std::str::FromStr;
+
+/// Algorithms available for computing FOV.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FovAlgorithm {
+    /// Recursive shadowcasting treating every tile as a square.
+    Shadowcasting,
+    /// Shadowcasting in which a tile is visible from another exactly when the reverse is true.
+    SymmetricShadowcasting,
+    /// Recursive shadowcasting treating walls as diamonds, which lets light through diagonal gaps
+    /// and around pillar corners more generously.
+    DiamondWalls,
+}
+
+impl FromStr for FovAlgorithm {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use self::FovAlgorithm::*;
+
+        Ok(match s {
+            "shadowcasting" => Shadowcasting,
+            "symmetric_shadowcasting" => SymmetricShadowcasting,
+            "diamond_walls" => DiamondWalls,
+            _ => {
+                return Err(GameError::ConversionError {
+                    val: s.into(),
+                    msg: "Invalid FOV algorithm",
+                });
+            }
+        })
+    }
+}
+
+impl fmt::Display for FovAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::FovAlgorithm::*;
+
+        write!(
+            f,
+            "{}",
+            match *self {
+                Shadowcasting => "shadowcasting",
+                SymmetricShadowcasting => "symmetric_shadowcasting",
+                DiamondWalls => "diamond_walls",
+            }
+        )
+    }
+}
+
+/// The set of tiles visible from an origin within a radius.
+#[derive(Clone, Debug, Default)]
+pub struct FovMap {
+    start: Coord,
+    side: i32,
+    visible: Vec<bool>,
+}
+
+impl FovMap {
+    /// Computes the tiles visible from `origin` within `radius`.
+    /// `transparent` must return false for coordinates outside of the grid.
+    pub fn compute<F>(algorithm: FovAlgorithm, origin: Coord, radius: i32, transparent: F) -> FovMap
+    where
+        F: Fn(Coord) -> bool,
+    {
+        let radius = radius.max(0);
+        let side = radius * 2 + 1;
+        let mut fov = FovMap {
+            start: origin - Coord::new(radius, radius),
+            side,
+            visible: vec![false; (side * side) as usize],
+        };
+
+        fov.set_visible(origin);
+
+        match algorithm {
+            FovAlgorithm::Shadowcasting => {
+                shadowcast(&mut fov, origin, radius, &transparent, TileShape::Square)
+            }
+            FovAlgorithm::SymmetricShadowcasting => {
+                symmetric_shadowcast(&mut fov, origin, radius, &transparent)
+            }
+            FovAlgorithm::DiamondWalls => {
+                shadowcast(&mut fov, origin, radius, &transparent, TileShape::Diamond)
+            }
+        }
+
+        fov
+    }
+
+    /// Returns true if `coord` is visible.
+    pub fn is_visible(&self, coord: Coord) -> bool {
+        match self.index(coord) {
+            Some(index) => self.visible[index],
+            None => false,
+        }
+    }
+
+    fn set_visible(&mut self, coord: Coord) {
+        if let Some(index) = self.index(coord) {
+            self.visible[index] = true;
+        }
+    }
+
+    fn index(&self, coord: Coord) -> Option<usize> {
+        let Coord { x, y } = coord - self.start;
+        if x < 0 || y < 0 || x >= self.side || y >= self.side {
+            None
+        } else {
+            Some((x * self.side + y) as usize)
+        }
+    }
+}
+
+// Returns true if the offset `(dx, dy)` lies within `radius`.
+fn in_radius(dx: i32, dy: i32, radius: i32) -> bool {
+    dx * dx + dy * dy <= radius * radius + radius
+}
+
+// Shape of tiles as seen by the recursive shadowcaster.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TileShape {
+    Square,
+    Diamond,
+}
+
+impl TileShape {
+    // Returns the (low, high) slopes covered by the tile at column `col` of row `row` of an octant.
+    fn slopes(self, row: i32, col: i32) -> (f64, f64) {
+        let (row, col) = (f64::from(row), f64::from(col));
+
+        let vertices = match self {
+            TileShape::Square => [
+                (col - 0.5, row - 0.5),
+                (col + 0.5, row - 0.5),
+                (col - 0.5, row + 0.5),
+                (col + 0.5, row + 0.5),
+            ],
+            TileShape::Diamond => [
+                (col - 0.5, row),
+                (col + 0.5, row),
+                (col, row - 0.5),
+                (col, row + 0.5),
+            ],
+        };
+
+        vertices
+            .iter()
+            .map(|&(col, row)| col / row)
+            .fold((f64::MAX, f64::MIN), |(low, high), slope| {
+                (low.min(slope), high.max(slope))
+            })
+    }
+}
+
+// Multipliers transforming (row, col) octant coordinates into the eight octants around the origin.
+const OCTANTS: [(i32, i32, i32, i32); 8] = [
+    (1, 0, 0, 1),
+    (0, 1, 1, 0),
+    (0, -1, 1, 0),
+    (-1, 0, 0, 1),
+    (-1, 0, 0, -1),
+    (0, -1, -1, 0),
+    (0, 1, -1, 0),
+    (1, 0, 0, -1),
+];
+
+// Recursive shadowcasting, scanning each octant row by row and recursing past obstacles.
+fn shadowcast<F>(fov: &mut FovMap, origin: Coord, radius: i32, transparent: &F, shape: TileShape)
+where
+    F: Fn(Coord) -> bool,
+{
+    for &octant in OCTANTS.iter() {
+        cast_light(fov, origin, radius, transparent, shape, octant, 1, 0.0, 1.0);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cast_light<F>(
+    fov: &mut FovMap,
+    origin: Coord,
+    radius: i32,
+    transparent: &F,
+    shape: TileShape,
+    octant: (i32, i32, i32, i32),
+    first_row: i32,
+    mut low: f64,
+    high: f64,
+) where
+    F: Fn(Coord) -> bool,
+{
+    if low >= high {
+        return;
+    }
+
+    let (xx, xy, yx, yy) = octant;
+
+    for row in first_row..=radius {
+        let mut blocked = false;
+        let mut next_low = low;
+
+        for col in 0..=row {
+            let (tile_low, tile_high) = shape.slopes(row, col);
+            if tile_high <= low {
+                continue;
+            }
+            if tile_low >= high {
+                break;
+            }
+
+            let coord = Coord::new(
+                origin.x + col * xx + row * xy,
+                origin.y + col * yx + row * yy,
+            );
+            if in_radius(col, row, radius) {
+                fov.set_visible(coord);
+            }
+
+            let opaque = !transparent(coord);
+            if blocked {
+                if opaque {
+                    next_low = tile_high;
+                } else {
+                    blocked = false;
+                    low = next_low;
+                }
+            } else if opaque {
+                // Light continues past the obstacle on its low side.
+                blocked = true;
+                cast_light(
+                    fov,
+                    origin,
+                    radius,
+                    transparent,
+                    shape,
+                    octant,
+                    row + 1,
+                    low,
+                    tile_low,
+                );
+                next_low = tile_high;
+            }
+        }
+
+        if blocked {
+            break;
+        }
+    }
+}
+
+// Symmetric shadowcasting, scanning each quadrant row by row with exact slopes.
+fn symmetric_shadowcast<F>(fov: &mut FovMap, origin: Coord, radius: i32, transparent: &F)
+where
+    F: Fn(Coord) -> bool,
+{
+    // Transforms (depth, col) quadrant coordinates into the four quadrants around the origin.
+    let quadrants: [fn(Coord, i32, i32) -> Coord; 4] = [
+        |o, depth, col| Coord::new(o.x + col, o.y - depth),
+        |o, depth, col| Coord::new(o.x + depth, o.y + col),
+        |o, depth, col| Coord::new(o.x + col, o.y + depth),
+        |o, depth, col| Coord::new(o.x - depth, o.y + col),
+    ];
+
+    for transform in quadrants.iter() {
+        let mut rows = vec![(1, Ratio::new(-1, 1), Ratio::new(1, 1))];
+
+        while let Some((depth, mut start, end)) = rows.pop() {
+            if depth > radius {
+                continue;
+            }
+
+            let min_col = (Ratio::from_integer(depth) * start + Ratio::new(1, 2))
+                .floor()
+                .to_integer();
+            let max_col = (Ratio::from_integer(depth) * end - Ratio::new(1, 2))
+                .ceil()
+                .to_integer();
+
+            let mut prev_opaque = None;
+            for col in min_col..=max_col {
+                let coord = transform(origin, depth, col);
+                let opaque = !transparent(coord);
+
+                // Floor tiles are only visible if their centers are, which keeps the view
+                // symmetric.
+                let symmetric = Ratio::from_integer(col) >= Ratio::from_integer(depth) * start
+                    && Ratio::from_integer(col) <= Ratio::from_integer(depth) * end;
+                if (opaque || symmetric) && in_radius(col, depth, radius) {
+                    fov.set_visible(coord);
+                }
+
+                let slope = Ratio::new(2 * col - 1, 2 * depth);
+                match prev_opaque {
+                    Some(true) if !opaque => start = slope,
+                    Some(false) if opaque => rows.push((depth + 1, start, slope)),
+                    _ => (),
+                }
+                prev_opaque = Some(opaque);
+            }
+
+            if prev_opaque == Some(false) {
+                rows.push((depth + 1, start, end));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [FovAlgorithm; 3] = [
+        FovAlgorithm::Shadowcasting,
+        FovAlgorithm::SymmetricShadowcasting,
+        FovAlgorithm::DiamondWalls,
+    ];
+
+    // Returns a closure for a 30x30 grid with walls at `walls` and around the border.
+    fn grid(walls: Vec<Coord>) -> impl Fn(Coord) -> bool {
+        move |coord: Coord| {
+            coord.x > 0 && coord.y > 0 && coord.x < 29 && coord.y < 29 && !walls.contains(&coord)
+        }
+    }
+
+    #[test]
+    fn test_open_room() {
+        let transparent = grid(vec![]);
+        let origin = Coord::new(15, 15);
+
+        for &algorithm in ALGORITHMS.iter() {
+            let fov = FovMap::compute(algorithm, origin, 5, &transparent);
+
+            assert!(fov.is_visible(origin));
+            assert!(fov.is_visible(Coord::new(20, 15)));
+            assert!(fov.is_visible(Coord::new(15, 10)));
+            assert!(fov.is_visible(Coord::new(18, 19)));
+            assert!(!fov.is_visible(Coord::new(21, 15)));
+            assert!(!fov.is_visible(Coord::new(20, 20)));
+        }
+    }
+
+    #[test]
+    fn test_walls_block() {
+        let walls = (10..=20).map(|y| Coord::new(17, y)).collect();
+        let transparent = grid(walls);
+        let origin = Coord::new(15, 15);
+
+        for &algorithm in ALGORITHMS.iter() {
+            let fov = FovMap::compute(algorithm, origin, 10, &transparent);
+
+            // Walls are visible but what's behind them isn't.
+            assert!(fov.is_visible(Coord::new(17, 15)));
+            assert!(!fov.is_visible(Coord::new(18, 15)));
+            assert!(!fov.is_visible(Coord::new(22, 17)));
+            assert!(fov.is_visible(Coord::new(10, 15)));
+        }
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let walls: Vec<Coord> = (0..30)
+            .flat_map(|x| (0..30).map(move |y| Coord::new(x, y)))
+            .filter(|c| (c.x * 7 + c.y * 13) % 11 == 0)
+            .collect();
+        let transparent = grid(walls);
+
+        let floors: Vec<Coord> = (0..30)
+            .flat_map(|x| (0..30).map(move |y| Coord::new(x, y)))
+            .filter(|&c| transparent(c))
+            .collect();
+
+        let fovs: Vec<FovMap> = floors
+            .iter()
+            .map(|&c| FovMap::compute(FovAlgorithm::SymmetricShadowcasting, c, 8, &transparent))
+            .collect();
+
+        for (i, &a) in floors.iter().enumerate() {
+            for (j, &b) in floors.iter().enumerate() {
+                if fovs[i].is_visible(b) {
+                    assert!(fovs[j].is_visible(a), "{} sees {} but not vice versa", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fov_algorithm_from_str() {
+        for &algorithm in ALGORITHMS.iter() {
+            assert_eq!(
+                FovAlgorithm::from_str(&algorithm.to_string()).unwrap(),
+                algorithm
+            );
+        }
+        assert!(FovAlgorithm::from_str("diamond").is_err());
+    }
+}
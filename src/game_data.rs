@@ -1,29 +1,42 @@
 //! Module for game-wide data.
 
 use crate::actor::Actor;
-use crate::console::{ConsoleSettings, DrawConsole};
+use crate::console::{Color, ConsoleSettings, DrawConsole};
 use crate::constants;
 use crate::coord::Coord;
-use crate::defs::GameRatio;
+use crate::defs::*;
+use crate::dungeon::DepthDirection;
+use crate::fov::FovAlgorithm;
+use crate::identify::{Appearance, ItemKnowledge};
+use crate::item::Item;
 use crate::material::MaterialInfo;
+use crate::message::Message;
+use crate::save::arr_from_vec;
 use crate::tile::TileInfo;
 use crate::ui::UiSettings;
+use crate::util::rand::GameRng;
 use crate::{handle_error, GameResult, DATABASE};
 use failure::ResultExt;
 use num_traits::identities::Zero;
 use over::{self, value::Value, Obj};
 use std::cell::RefCell;
 use std::collections::{HashMap, VecDeque};
-use std::sync::Arc;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
 /// Result of the main game loop.
+#[derive(Debug, PartialEq)]
 pub enum GameLoopOutcome {
-    /// The player has changed depth.
-    DepthChanged,
+    /// The player has taken the stairs in the given direction.
+    DepthChanged(DepthDirection),
+    /// The player has fallen through a trap door to the next depth.
+    FellDown,
     /// Game window was closed by player.
     WindowClosed,
     /// Game has been quit.
     QuitGame,
+    /// Player requested to save the game.
+    SaveGame,
     /// Player died and we need to return.
     PlayerDead,
     /// No actors remaining in queue.
@@ -37,15 +50,23 @@ pub struct GameData {
     pub console_settings: ConsoleSettings,
     /// A struct containing UI parameters.
     pub ui_settings: UiSettings,
+    /// Algorithm used to compute fields of view.
+    pub fov_algorithm: FovAlgorithm,
 
     /// Message deque storing a fixed number of messages.
-    message_list: VecDeque<String>,
+    message_list: Mutex<VecDeque<Message>>,
 
     /// Reference to the player.
     player: Option<Actor>,
     /// Current global game turn.
     turn: GameRatio,
 
+    /// Game-wide random number generator.
+    rng: Mutex<GameRng>,
+
+    /// Which kinds of items the player has identified.
+    item_knowledge: Mutex<ItemKnowledge>,
+
     /// Vector of tile info structs, indexed by id.
     tile_info_list: Vec<Arc<TileInfo>>,
     tile_start_id: Option<usize>,
@@ -53,6 +74,9 @@ pub struct GameData {
     /// Vector of material structs, indexed by id.
     material_info_list: Vec<Arc<MaterialInfo>>,
     material_start_id: Option<usize>,
+
+    /// Item that corpses are made from, loaded up front so that actors can always leave one.
+    corpse: Option<Item>,
 }
 
 impl GameData {
@@ -61,31 +85,119 @@ impl GameData {
         let settings = DATABASE.read().unwrap().get_obj("settings")?;
         let console_settings = ConsoleSettings::new(&settings)?;
         let ui_settings = UiSettings::new(&settings)?;
+        let fov_algorithm = FovAlgorithm::from_str(&settings.get_str("fov_algorithm")?)?;
 
         let mut game_data = GameData {
             console_settings,
             ui_settings,
+            fov_algorithm,
 
-            message_list: VecDeque::with_capacity(constants::MESSAGE_DEQUE_SIZE),
+            message_list: Mutex::new(VecDeque::with_capacity(constants::MESSAGE_DEQUE_SIZE)),
 
             player: None,
             turn: GameRatio::zero(),
 
+            rng: Mutex::new(GameRng::from_entropy()),
+
+            item_knowledge: Mutex::new(ItemKnowledge::default()),
+
             tile_info_list: Vec::new(),
             tile_start_id: None,
             material_info_list: Vec::new(),
             material_start_id: None,
+
+            corpse: None,
         };
 
-        // As tiles contain materials, initialize materials first.
+        // As tiles and items contain materials, initialize materials first.
         game_data.init_materials()?;
         game_data.init_tiles()?;
 
+        let corpse = DATABASE
+            .read()
+            .unwrap()
+            .get_obj("items")?
+            .get_obj("corpse")?;
+        game_data.corpse = Some(Item::with_game_data(&game_data, "corpse", &corpse).context(
+            format!(
+                "Could not load item:
+{}",
+                corpse
+            ),
+        )?);
+
         Ok(game_data)
     }
 
-    /// Adds a string to the message deque.
-    pub fn add_message(&self, message: &str) {} // TODO. Should pop_front when queue gets too big
+    /// Returns the saveable state of the game data. Settings and database info are not included as
+    /// they are reloaded on startup.
+    pub fn to_save_obj(&self) -> GameResult<Obj> {
+        let messages = self
+            .message_list
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|message| message.to_save_obj().into())
+            .collect();
+
+        Ok(Obj::from_map_unchecked(map! {
+            "turn".into() => gamer_to_bigr(self.turn).into(),
+            "seed".into() => self.seed().into(),
+            "rng_steps".into() => self.rng.lock().unwrap().steps().into(),
+            "messages".into() => arr_from_vec(messages)?.into(),
+            "item_knowledge".into() => self.item_knowledge.lock().unwrap().to_save_arr()?.into(),
+        }))
+    }
+
+    /// Restores the game data state from data written by `to_save_obj`.
+    /// The player must be set separately once the dungeons are loaded.
+    pub fn load_save_obj(&mut self, data: &Obj) -> GameResult<()> {
+        self.turn = bigr_to_gamer(data.get_frac("turn")?)?;
+        // Pick up the random sequence where it left off instead of replaying it from the start.
+        self.rng = Mutex::new(GameRng::with_steps(
+            big_to_u64(data.get_int("seed")?)?,
+            big_to_u64(data.get_int("rng_steps")?)?,
+        ));
+
+        let mut message_list = self.message_list.lock().unwrap();
+        message_list.clear();
+        for message in data.get_arr("messages")?.iter() {
+            message_list.push_back(Message::from_save_obj(&message.get_obj()?)?);
+        }
+
+        self.set_item_knowledge(ItemKnowledge::from_save_arr(
+            &data.get_arr("item_knowledge")?,
+        )?);
+
+        Ok(())
+    }
+
+    /// Adds a message to the message deque, stamped with the current turn.
+    /// The oldest message is dropped once the deque is full.
+    ///
+    /// Messages about events in the dungeon should go through `Dungeon::add_message` instead, so
+    /// that only events the player sees are logged.
+    pub fn add_message(&self, text: &str, color: Color) {
+        let mut message_list = self.message_list.lock().unwrap();
+
+        if message_list.len() >= constants::MESSAGE_DEQUE_SIZE {
+            message_list.pop_front();
+        }
+        message_list.push_back(Message::new(text, color, self.turn));
+    }
+
+    /// Returns all logged messages, oldest first.
+    pub fn messages(&self) -> Vec<Message> {
+        self.message_list.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Returns up to `n` of the most recent messages, oldest first.
+    pub fn recent_messages(&self, n: usize) -> Vec<Message> {
+        let message_list = self.message_list.lock().unwrap();
+        let start = message_list.len().saturating_sub(n);
+
+        message_list.iter().skip(start).cloned().collect()
+    }
 
     /// Gets a reference to the player.
     ///
@@ -109,25 +221,87 @@ impl GameData {
         self.turn = value;
     }
 
+    /// Returns a reference to the game-wide random number generator.
+    ///
+    /// Prefer `util::rand::with_rng` and the helpers in `util::rand` over using this directly.
+    pub fn rng(&self) -> &Mutex<GameRng> {
+        &self.rng
+    }
+
+    /// Returns the seed of the random number generator.
+    pub fn seed(&self) -> u64 {
+        self.rng.lock().unwrap().seed()
+    }
+
+    /// Reseeds the random number generator with `seed`.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Mutex::new(GameRng::new(seed));
+    }
+
+    /// Replaces the player's knowledge of items, e.g. with newly generated appearances.
+    pub fn set_item_knowledge(&self, knowledge: ItemKnowledge) {
+        *self.item_knowledge.lock().unwrap() = knowledge;
+    }
+
+    /// Returns how the item with database key `key` looks, or `None` if it is identified.
+    pub fn item_appearance(&self, key: &str) -> Option<Appearance> {
+        self.item_knowledge.lock().unwrap().appearance(key).cloned()
+    }
+
+    pub fn is_item_identified(&self, key: &str) -> bool {
+        self.item_knowledge.lock().unwrap().is_identified(key)
+    }
+
+    /// Identifies the item with database key `key`. Returns false if it was already identified.
+    pub fn identify_item(&self, key: &str) -> bool {
+        self.item_knowledge.lock().unwrap().identify(key)
+    }
+
     /// Returns a reference to the `TileInfo` object with `id`.
     pub fn tile_info(&self, id: usize) -> Arc<TileInfo> {
         Arc::clone(&self.tile_info_list[id - self.tile_start_id.unwrap()])
     }
 
+    /// Returns a reference to the `TileInfo` object with database key `key`, if it exists.
+    pub fn tile_info_by_key(&self, key: &str) -> Option<Arc<TileInfo>> {
+        self.tile_info_list
+            .iter()
+            .find(|info| info.key == key)
+            .map(Arc::clone)
+    }
+
     /// Returns a reference to the `MaterialInfo` object with `id`.
     pub fn material_info(&self, id: usize) -> Arc<MaterialInfo> {
         Arc::clone(&self.material_info_list[id - self.material_start_id.unwrap()])
     }
 
+    /// Returns the item that corpses are made from.
+    pub fn corpse(&self) -> &Item {
+        self.corpse.as_ref().unwrap()
+    }
+
+    /// Returns all `MaterialInfo` objects.
+    pub fn material_info_list(&self) -> &[Arc<MaterialInfo>] {
+        &self.material_info_list
+    }
+
+    /// Returns a reference to the `MaterialInfo` object with database key `key`, if it exists.
+    pub fn material_info_by_key(&self, key: &str) -> Option<Arc<MaterialInfo>> {
+        self.material_info_list
+            .iter()
+            .find(|info| info.key == key)
+            .map(Arc::clone)
+    }
+
     fn init_tiles(&mut self) -> GameResult<()> {
         let tiles = DATABASE.read().unwrap().get_obj("tiles")?;
         let mut vec_temp: Vec<(Arc<TileInfo>, usize)> = Vec::new();
         let mut min = usize::max_value();
 
-        for tile_val in tiles.values() {
+        for (key, tile_val) in tiles.iter() {
             if let Value::Obj(ref tile_data) = *tile_val {
                 let tile = Arc::new(
-                    TileInfo::new(self, tile_data)
+                    TileInfo::new(self, key, tile_data)
                         .context(format!("Could not load tile:\n{}", tile_data))?,
                 );
                 let id = tile_data.id();
@@ -154,10 +328,10 @@ impl GameData {
         let mut vec_temp: Vec<(Arc<MaterialInfo>, usize)> = Vec::new();
         let mut min = usize::max_value();
 
-        for material_val in materials.values() {
+        for (key, material_val) in materials.iter() {
             if let Value::Obj(ref material_data) = *material_val {
                 let material = Arc::new(
-                    MaterialInfo::new(self, material_data)
+                    MaterialInfo::new(self, key, material_data)
                         .context(format!("Could not load material:\n{}", material_data))?,
                 );
                 let id = material_data.id();
@@ -0,0 +1,326 @@
+//! Dungeon generation using binary space partitioning.
+//!
+//! The level is split in two over and over again until the pieces are no bigger than twice
+//! `min_leaf_size`. Each leaf gets a room, and at every split a corridor joins a room on one side
+//! to a room on the other, so every room can be reached. Corridors are `corridor_width` tiles wide
+//! and take a random turn with a chance of `winding` at every step. Wandering corridors can double
+//! back on themselves and leave short dead ends behind; these are filled in if `remove_dead_ends`
+//! is set.
+
+use crate::coord::Coord;
+use crate::defs::*;
+use crate::dungeon::Dungeon;
+use crate::error::err_argument;
+use crate::generate::util::pick_obj_from_tup_arr;
+use crate::object::Object;
+use crate::util::direction::{CardinalDirection, Direction};
+use crate::util::rand::{chance, dice, rand_int, Choose};
+use crate::util::rectangle::Rectangle;
+use crate::{GameResult, GAMEDATA};
+use failure::ResultExt;
+use over::Obj;
+use std::sync::Arc;
+
+#[derive(Debug)]
+struct DungeonBspParams {
+    width: usize,
+    height: usize,
+
+    min_leaf_size: usize,
+    min_room_size: usize,
+
+    corridor_width: usize,
+    winding: GameRatio,
+    remove_dead_ends: bool,
+    door_chance: GameRatio,
+}
+
+/// Generates a dungeon level of rooms joined by corridors, using binary space partitioning.
+pub fn gen_dungeon_bsp(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()> {
+    let params = DungeonBspParams {
+        width: big_to_usize(profile.get_int("width")?)?,
+        height: big_to_usize(profile.get_int("height")?)?,
+
+        min_leaf_size: big_to_usize(profile.get_int("min_leaf_size")?)?,
+        min_room_size: big_to_usize(profile.get_int("min_room_size")?)?,
+
+        corridor_width: big_to_usize(profile.get_int("corridor_width")?)?,
+        winding: bigr_to_gamer(profile.get_frac("winding")?)?,
+        remove_dead_ends: profile.get_bool("remove_dead_ends")?,
+        door_chance: bigr_to_gamer(profile.get_frac("door_chance")?)?,
+    };
+
+    if params.width < params.min_leaf_size + 2 || params.height < params.min_leaf_size + 2 {
+        return err_argument(
+            format!("{}x{}", params.width, params.height),
+            "Dungeon is too small to fit \"min_leaf_size\"",
+        );
+    }
+    if params.min_room_size == 0 || params.min_leaf_size < params.min_room_size + 2 {
+        return err_argument(
+            format!("{}", params.min_leaf_size),
+            "\"min_leaf_size\" must leave room for walls around \"min_room_size\"",
+        );
+    }
+    if params.corridor_width == 0 {
+        return err_argument(
+            format!("{}", params.corridor_width),
+            "\"corridor_width\" must be positive",
+        );
+    }
+
+    let mut grid = BspGrid::new(params.width, params.height);
+    let root = Rectangle::from_dimensions(1, 1, params.width - 2, params.height - 2);
+    grid.split(&root, &params);
+
+    if params.remove_dead_ends {
+        grid.remove_dead_ends();
+    }
+    grid.widen_corridors(params.corridor_width);
+
+    // Write the grid to the dungeon.
+
+    dungeon.init_grid(params.width, params.height, &profile.get_obj("wall_tile")?)?;
+
+    let floor = GAMEDATA
+        .read()
+        .unwrap()
+        .tile_info(profile.get_obj("floor_tile")?.id());
+    for (index, open) in grid.open.iter().enumerate() {
+        if *open {
+            dungeon[grid.coord(index)].info = Arc::clone(&floor);
+        }
+    }
+
+    // Doors only fit in corridors one tile wide.
+    if params.corridor_width == 1 && profile.get("doors").is_some() {
+        for coord in grid.doorways() {
+            if chance(params.door_chance) {
+                let door = pick_obj_from_tup_arr(&profile.get_arr("doors")?)
+                    .context("Parsing \"doors\" Arr in \"dungeon_profiles.over\"")?;
+                let object = Object::new(coord, &door, dice(8, 10))
+                    .context(format!("Could not load object:\n{}", door))?;
+                dungeon.add_object(object);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Grid of room and corridor tiles, stored row by row. Corridors are dug one tile wide and widened
+// at the end.
+struct BspGrid {
+    width: usize,
+    height: usize,
+    room: Vec<bool>,
+    corridor: Vec<bool>,
+    open: Vec<bool>,
+}
+
+impl BspGrid {
+    fn new(width: usize, height: usize) -> BspGrid {
+        BspGrid {
+            width,
+            height,
+            room: vec![false; width * height],
+            corridor: vec![false; width * height],
+            open: vec![false; width * height],
+        }
+    }
+
+    // Recursively splits `leaf`, digging rooms and corridors. Returns the rooms inside `leaf`.
+    fn split(&mut self, leaf: &Rectangle, params: &DungeonBspParams) -> Vec<Rectangle> {
+        let width = (leaf.right - leaf.left + 1) as usize;
+        let height = (leaf.bottom - leaf.top + 1) as usize;
+        let min = params.min_leaf_size;
+
+        let can_split_x = width >= min * 2;
+        let can_split_y = height >= min * 2;
+        let split_x = match (can_split_x, can_split_y) {
+            (false, false) => return vec![self.dig_room(leaf, params)],
+            (true, false) => true,
+            (false, true) => false,
+            // Prefer to split the longer side.
+            (true, true) => width > height || (width == height && dice(1, 2)),
+        };
+
+        let (first, second) = if split_x {
+            let x = leaf.left + rand_int(min, width - min) as i32;
+            (
+                Rectangle::new(leaf.left, leaf.top, x - 1, leaf.bottom),
+                Rectangle::new(x, leaf.top, leaf.right, leaf.bottom),
+            )
+        } else {
+            let y = leaf.top + rand_int(min, height - min) as i32;
+            (
+                Rectangle::new(leaf.left, leaf.top, leaf.right, y - 1),
+                Rectangle::new(leaf.left, y, leaf.right, leaf.bottom),
+            )
+        };
+
+        let mut rooms = self.split(&first, params);
+        let second_rooms = self.split(&second, params);
+
+        let from = center(rooms.choose().unwrap());
+        let to = center(second_rooms.choose().unwrap());
+        self.dig_corridor(from, to, params.winding);
+
+        rooms.extend(second_rooms);
+        rooms
+    }
+
+    // Digs a room of random size inside `leaf`, leaving at least one wall on each side.
+    fn dig_room(&mut self, leaf: &Rectangle, params: &DungeonBspParams) -> Rectangle {
+        let max_width = (leaf.right - leaf.left - 1) as usize;
+        let max_height = (leaf.bottom - leaf.top - 1) as usize;
+        let width = rand_int(params.min_room_size, max_width);
+        let height = rand_int(params.min_room_size, max_height);
+
+        let left = rand_int(leaf.left + 1, leaf.right - width as i32);
+        let top = rand_int(leaf.top + 1, leaf.bottom - height as i32);
+        let room = Rectangle::from_dimensions(left, top, width, height);
+
+        for x in room.left..=room.right {
+            for y in room.top..=room.bottom {
+                let index = self.index(Coord::new(x, y));
+                self.room[index] = true;
+                self.open[index] = true;
+            }
+        }
+
+        room
+    }
+
+    // Digs a corridor from `from` to `to`. At each step the corridor heads towards `to`, or with a
+    // chance of `winding` goes off in a random direction instead.
+    fn dig_corridor(&mut self, from: Coord, to: Coord, winding: GameRatio) {
+        // Give up on winding after this many steps, so the corridor always arrives.
+        let max_steps = (self.width + self.height) * 4;
+        let dirs = CardinalDirection::all();
+        let mut coord = from;
+        let mut steps = 0;
+
+        while coord != to {
+            let step = if steps < max_steps && chance(winding) {
+                dirs[rand_int(0, dirs.len() - 1)].unit_vec()
+            } else if coord.x != to.x && (coord.y == to.y || dice(1, 2)) {
+                ((to.x - coord.x).signum(), 0)
+            } else {
+                (0, (to.y - coord.y).signum())
+            };
+
+            let next = coord + Coord::new(step.0, step.1);
+            if self.in_interior(next) {
+                coord = next;
+                let index = self.index(coord);
+                self.corridor[index] = true;
+                self.open[index] = true;
+            }
+            steps += 1;
+        }
+    }
+
+    // Fills in corridor tiles that lead nowhere, until none are left.
+    fn remove_dead_ends(&mut self) {
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for index in 0..self.open.len() {
+                if self.corridor[index] && !self.room[index] && self.open_neighbors(index) <= 1 {
+                    self.corridor[index] = false;
+                    self.open[index] = false;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    // Widens every corridor to `width` tiles by digging to the right of and below each tile.
+    fn widen_corridors(&mut self, width: usize) {
+        if width <= 1 {
+            return;
+        }
+
+        let corridor = self.corridor.clone();
+        for (index, _) in corridor.iter().enumerate().filter(|(_, dug)| **dug) {
+            let coord = self.coord(index);
+            for dx in 0..width as i32 {
+                for dy in 0..width as i32 {
+                    let wide = coord + Coord::new(dx, dy);
+                    if self.in_interior(wide) {
+                        let wide = self.index(wide);
+                        self.corridor[wide] = true;
+                        self.open[wide] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // Returns corridor tiles that lead into a room and have walls on either side.
+    fn doorways(&self) -> Vec<Coord> {
+        let mut doorways = Vec::new();
+
+        for index in 0..self.open.len() {
+            if !self.corridor[index] || self.room[index] {
+                continue;
+            }
+
+            let coord = self.coord(index);
+            let is_open = |dx, dy| self.is_open(coord + Coord::new(dx, dy));
+            let is_room = |dx, dy| self.is_room(coord + Coord::new(dx, dy));
+
+            let horizontal = !is_open(0, -1) && !is_open(0, 1) && (is_room(-1, 0) || is_room(1, 0));
+            let vertical = !is_open(-1, 0) && !is_open(1, 0) && (is_room(0, -1) || is_room(0, 1));
+            if horizontal || vertical {
+                doorways.push(coord);
+            }
+        }
+
+        doorways
+    }
+
+    fn open_neighbors(&self, index: usize) -> usize {
+        let coord = self.coord(index);
+
+        CardinalDirection::all()
+            .iter()
+            .filter(|dir| self.is_open(coord.coord_in_dir(*dir, 1)))
+            .count()
+    }
+
+    fn is_open(&self, coord: Coord) -> bool {
+        self.in_bounds(coord) && self.open[self.index(coord)]
+    }
+
+    fn is_room(&self, coord: Coord) -> bool {
+        self.in_bounds(coord) && self.room[self.index(coord)]
+    }
+
+    fn in_bounds(&self, coord: Coord) -> bool {
+        coord.x >= 0 && coord.y >= 0 && coord.x < self.width as i32 && coord.y < self.height as i32
+    }
+
+    // Returns true if `coord` is inside the grid and not on its border.
+    fn in_interior(&self, coord: Coord) -> bool {
+        coord.x > 0
+            && coord.y > 0
+            && coord.x < self.width as i32 - 1
+            && coord.y < self.height as i32 - 1
+    }
+
+    fn index(&self, coord: Coord) -> usize {
+        coord.y as usize * self.width + coord.x as usize
+    }
+
+    fn coord(&self, index: usize) -> Coord {
+        Coord::new((index % self.width) as i32, (index / self.width) as i32)
+    }
+}
+
+fn center(room: &Rectangle) -> Coord {
+    Coord::new((room.left + room.right) / 2, (room.top + room.bottom) / 2)
+}
@@ -0,0 +1,272 @@
+//! Cave generation using cellular automata.
+//!
+//! The grid starts out as random noise, with each tile a wall with a chance of `fill_ratio`. Every
+//! iteration then smooths it out: floor tiles surrounded by more than `birth_limit` walls become
+//! walls, and walls surrounded by fewer than `death_limit` walls become floor. The result is a set
+//! of open regions. Regions smaller than `min_region_size` are filled in and the rest are joined to
+//! the largest region by tunnels, so that every open tile can be reached.
+
+use crate::coord::Coord;
+use crate::defs::*;
+use crate::dungeon::Dungeon;
+use crate::error::err_unexpected;
+use crate::util::direction::CardinalDirection;
+use crate::util::rand::chance;
+use crate::{GameResult, GAMEDATA};
+use over::Obj;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Number of times to generate a new cave if one ends up with no open space at all.
+const CAVE_TRIES: usize = 10;
+
+#[derive(Debug)]
+struct DungeonCaveParams {
+    width: usize,
+    height: usize,
+
+    fill_ratio: GameRatio,
+    iterations: usize,
+    birth_limit: usize,
+    death_limit: usize,
+    min_region_size: usize,
+}
+
+/// Generates a dungeon level using cellular automata.
+pub fn gen_dungeon_cave(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()> {
+    let params = DungeonCaveParams {
+        width: big_to_usize(profile.get_int("width")?)?,
+        height: big_to_usize(profile.get_int("height")?)?,
+
+        fill_ratio: bigr_to_gamer(profile.get_frac("fill_ratio")?)?,
+        iterations: big_to_usize(profile.get_int("iterations")?)?,
+        birth_limit: big_to_usize(profile.get_int("birth_limit")?)?,
+        death_limit: big_to_usize(profile.get_int("death_limit")?)?,
+        min_region_size: big_to_usize(profile.get_int("min_region_size")?)?,
+    };
+
+    let grid = match try_some!(gen_cave_grid(&params), CAVE_TRIES) {
+        Some(grid) => grid,
+        None => return err_unexpected("Failed to generate a cave with any open space"),
+    };
+
+    dungeon.init_grid(params.width, params.height, &profile.get_obj("wall_tile")?)?;
+
+    let floor = GAMEDATA
+        .read()
+        .unwrap()
+        .tile_info(profile.get_obj("floor_tile")?.id());
+    for (index, wall) in grid.walls.iter().enumerate() {
+        if !wall {
+            dungeon[grid.coord(index)].info = Arc::clone(&floor);
+        }
+    }
+
+    Ok(())
+}
+
+// Generates a connected cave, or returns `None` if no open space is left.
+fn gen_cave_grid(params: &DungeonCaveParams) -> Option<CaveGrid> {
+    let mut grid = CaveGrid::random(params.width, params.height, params.fill_ratio);
+
+    for _ in 0..params.iterations {
+        grid = grid.smooth(params.birth_limit, params.death_limit);
+    }
+
+    let mut regions = grid.regions();
+    regions.retain(|region| {
+        let keep = region.len() >= params.min_region_size;
+        if !keep {
+            grid.fill(region);
+        }
+        keep
+    });
+
+    // Join every region to the largest one.
+    regions.sort_by_key(|region| region.len());
+    let main = regions.pop()?;
+    for region in &regions {
+        grid.join(region, &main);
+    }
+
+    Some(grid)
+}
+
+// A grid of wall and floor tiles, stored row by row.
+struct CaveGrid {
+    width: usize,
+    height: usize,
+    walls: Vec<bool>,
+}
+
+impl CaveGrid {
+    // Returns a grid of random noise. The border is always made of walls.
+    fn random(width: usize, height: usize, fill_ratio: GameRatio) -> CaveGrid {
+        let mut grid = CaveGrid {
+            width,
+            height,
+            walls: vec![true; width * height],
+        };
+
+        for index in 0..grid.walls.len() {
+            if !grid.on_border(grid.coord(index)) {
+                grid.walls[index] = chance(fill_ratio);
+            }
+        }
+
+        grid
+    }
+
+    // Runs one iteration of the cellular automaton.
+    fn smooth(&self, birth_limit: usize, death_limit: usize) -> CaveGrid {
+        let mut walls = self.walls.clone();
+
+        for (index, wall) in walls.iter_mut().enumerate() {
+            let coord = self.coord(index);
+            if self.on_border(coord) {
+                continue;
+            }
+
+            let neighbors = self.wall_neighbors(coord);
+            *wall = if self.walls[index] {
+                neighbors >= death_limit
+            } else {
+                neighbors > birth_limit
+            };
+        }
+
+        CaveGrid {
+            width: self.width,
+            height: self.height,
+            walls,
+        }
+    }
+
+    // Returns the number of walls among the eight tiles around `coord`.
+    fn wall_neighbors(&self, coord: Coord) -> usize {
+        let mut count = 0;
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let neighbor = coord + Coord::new(dx, dy);
+                if neighbor != coord && self.is_wall(neighbor) {
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    // Returns the indices of the tiles in each open region. Regions are connected orthogonally, so
+    // that actors never have to squeeze between two walls diagonally.
+    fn regions(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.walls.len()];
+        let mut regions = Vec::new();
+
+        for start in 0..self.walls.len() {
+            if self.walls[start] || visited[start] {
+                continue;
+            }
+
+            let mut region = Vec::new();
+            let mut queue = VecDeque::new();
+            visited[start] = true;
+            queue.push_back(start);
+
+            while let Some(index) = queue.pop_front() {
+                region.push(index);
+
+                let coord = self.coord(index);
+                for dir in CardinalDirection::all().iter() {
+                    let neighbor = coord.coord_in_dir(dir, 1);
+                    if self.is_wall(neighbor) {
+                        continue;
+                    }
+
+                    let neighbor = self.index(neighbor);
+                    if !visited[neighbor] {
+                        visited[neighbor] = true;
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+
+            regions.push(region);
+        }
+
+        regions
+    }
+
+    // Turns every tile in `region` into a wall.
+    fn fill(&mut self, region: &[usize]) {
+        for &index in region {
+            self.walls[index] = true;
+        }
+    }
+
+    // Digs a tunnel between the closest pair of tiles in `region` and `main`.
+    fn join(&mut self, region: &[usize], main: &[usize]) {
+        let mut best = None;
+
+        for &from in region {
+            let from = self.coord(from);
+            for &to in main {
+                let to = self.coord(to);
+                let distance = (from.x - to.x).abs() + (from.y - to.y).abs();
+                match best {
+                    Some((best_distance, _, _)) if best_distance <= distance => (),
+                    _ => best = Some((distance, from, to)),
+                }
+            }
+        }
+
+        if let Some((_, from, to)) = best {
+            self.dig(from, to);
+        }
+    }
+
+    // Digs an L-shaped tunnel from `from` to `to`, first horizontally and then vertically.
+    fn dig(&mut self, from: Coord, to: Coord) {
+        let mut coord = from;
+
+        while coord != to {
+            if coord.x != to.x {
+                coord.x += (to.x - coord.x).signum();
+            } else {
+                coord.y += (to.y - coord.y).signum();
+            }
+
+            let index = self.index(coord);
+            self.walls[index] = false;
+        }
+    }
+
+    // Returns true if `coord` is a wall. Tiles outside the grid count as walls.
+    fn is_wall(&self, coord: Coord) -> bool {
+        if coord.x < 0
+            || coord.y < 0
+            || coord.x >= self.width as i32
+            || coord.y >= self.height as i32
+        {
+            return true;
+        }
+
+        self.walls[self.index(coord)]
+    }
+
+    fn on_border(&self, coord: Coord) -> bool {
+        coord.x == 0
+            || coord.y == 0
+            || coord.x == self.width as i32 - 1
+            || coord.y == self.height as i32 - 1
+    }
+
+    fn index(&self, coord: Coord) -> usize {
+        coord.y as usize * self.width + coord.x as usize
+    }
+
+    fn coord(&self, index: usize) -> Coord {
+        Coord::new((index % self.width) as i32, (index / self.width) as i32)
+    }
+}
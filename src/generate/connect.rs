@@ -0,0 +1,142 @@
+//! Connectivity checks for generated levels.
+//!
+//! Every open tile of a level should be reachable from every other one. Doors don't block the way,
+//! since they can be opened, kicked down or unlocked with a lever, but hazards such as lava and
+//! chasms do. Levels that fall apart into several regions are repaired by digging tunnels between
+//! them, which bridge any hazards on the way.
+
+use crate::coord::Coord;
+use crate::dungeon::Dungeon;
+use crate::util::direction::CardinalDirection;
+use crate::GameResult;
+use over::Obj;
+use std::collections::{HashMap, VecDeque};
+
+/// Returns true if `dungeon` has open space and all of it is connected.
+pub fn is_connected(dungeon: &Dungeon) -> bool {
+    regions(dungeon).len() == 1
+}
+
+/// Joins every region of open space in `dungeon` by digging tunnels of the profile's `floor_tile`.
+/// Returns false if the dungeon has no open space or a region can't be reached, in which case the
+/// dungeon should be generated again.
+pub fn connect_dungeon(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<bool> {
+    let floor = profile.get_obj("floor_tile")?;
+
+    loop {
+        let mut regions = regions(dungeon);
+        match regions.len() {
+            0 => return Ok(false),
+            1 => return Ok(true),
+            _ => (),
+        }
+
+        // Join the smallest region to its closest neighbor. Each tunnel merges at least two
+        // regions, so this runs at most once per region.
+        regions.sort_by_key(Vec::len);
+        let tunnel = match find_tunnel(dungeon, &regions[0]) {
+            Some(tunnel) => tunnel,
+            None => return Ok(false),
+        };
+        // Tunnels are level with the ground.
+        for coord in tunnel {
+            dungeon[coord].set_tile_info(&floor)?;
+            dungeon[coord].height = 0;
+            dungeon[coord].feature = None;
+        }
+    }
+}
+
+// Returns the coordinates of the tiles in each region of open space, connected orthogonally.
+fn regions(dungeon: &Dungeon) -> Vec<Vec<Coord>> {
+    let width = dungeon.width() as i32;
+    let height = dungeon.height() as i32;
+    let mut visited = vec![false; (width * height) as usize];
+    let index = |coord: Coord| (coord.y * width + coord.x) as usize;
+    let mut regions = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            let start = Coord::new(x, y);
+            if visited[index(start)] || !open(dungeon, start) {
+                continue;
+            }
+
+            let mut region = Vec::new();
+            let mut queue = VecDeque::new();
+            visited[index(start)] = true;
+            queue.push_back(start);
+
+            while let Some(coord) = queue.pop_front() {
+                region.push(coord);
+
+                for dir in CardinalDirection::all().iter() {
+                    let neighbor = coord.coord_in_dir(dir, 1);
+                    if in_bounds(dungeon, neighbor)
+                        && !visited[index(neighbor)]
+                        && open(dungeon, neighbor)
+                    {
+                        visited[index(neighbor)] = true;
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+
+            regions.push(region);
+        }
+    }
+
+    regions
+}
+
+// Finds the shortest tunnel through solid tiles and hazards from `region` to any other open tile,
+// staying off the dungeon's border. Returns the tiles to dig out.
+fn find_tunnel(dungeon: &Dungeon, region: &[Coord]) -> Option<Vec<Coord>> {
+    let width = dungeon.width() as i32;
+    let height = dungeon.height() as i32;
+    let interior =
+        |coord: Coord| coord.x > 0 && coord.y > 0 && coord.x < width - 1 && coord.y < height - 1;
+
+    let mut parents: HashMap<Coord, Option<Coord>> = region.iter().map(|c| (*c, None)).collect();
+    let mut queue: VecDeque<Coord> = region.iter().cloned().collect();
+
+    while let Some(coord) = queue.pop_front() {
+        for dir in CardinalDirection::all().iter() {
+            let neighbor = coord.coord_in_dir(dir, 1);
+            if !interior(neighbor) || parents.contains_key(&neighbor) {
+                continue;
+            }
+
+            if open(dungeon, neighbor) {
+                // Reached another region. Walk back to the starting region.
+                let mut tunnel = Vec::new();
+                let mut next = Some(coord);
+                while let Some(coord) = next {
+                    if open(dungeon, coord) {
+                        break;
+                    }
+                    tunnel.push(coord);
+                    next = parents[&coord];
+                }
+                return Some(tunnel);
+            }
+
+            parents.insert(neighbor, Some(coord));
+            queue.push_back(neighbor);
+        }
+    }
+
+    None
+}
+
+// Returns true if the tile at `coord` can be walked on safely.
+fn open(dungeon: &Dungeon, coord: Coord) -> bool {
+    dungeon[coord].passable() && !dungeon[coord].hazard()
+}
+
+fn in_bounds(dungeon: &Dungeon, coord: Coord) -> bool {
+    coord.x >= 0
+        && coord.y >= 0
+        && coord.x < dungeon.width() as i32
+        && coord.y < dungeon.height() as i32
+}
@@ -0,0 +1,66 @@
+//! Item placement.
+//!
+//! Loot tables are arrays of (item key, ratio) tuples. Profiles with `loot` scatter between
+//! `min_num_items` and `max_num_items` items from the loot table for the dungeon's danger level
+//! over the floor. Vaults pick their treasure from `vault_loot` in the same way, falling back to
+//! `loot`. Actors with a `drops` table carry one item from it with a chance of `drop_chance`.
+
+use crate::actor::Actor;
+use crate::defs::*;
+use crate::dungeon::Dungeon;
+use crate::error::err_unexpected;
+use crate::generate::util::{danger_table, pick_str_from_tup_arr};
+use crate::item::{Item, ItemStack};
+use crate::util::rand::{chance, rand_int};
+use crate::GameResult;
+use failure::ResultExt;
+use over::arr::Arr;
+use over::Obj;
+
+/// Scatters items from the profile's loot tables over open tiles in `dungeon`. Profiles without
+/// `loot` leave the dungeon untouched.
+pub fn gen_floor_items(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()> {
+    if profile.get("loot").is_none() {
+        return Ok(());
+    }
+
+    let table = match danger_table(&profile.get_arr("loot")?, dungeon.danger_level, "items")? {
+        Some(table) => table,
+        None => return Ok(()),
+    };
+    let num_items = rand_int(
+        big_to_usize(profile.get_int("min_num_items")?)?,
+        big_to_usize(profile.get_int("max_num_items")?)?,
+    );
+
+    for _ in 0..num_items {
+        let coord = match dungeon.random_open_coord_actor() {
+            Some(coord) => coord,
+            None => return err_unexpected("Ran out of tiles for items"),
+        };
+        dungeon.add_item(coord, gen_loot(&table)?);
+    }
+
+    Ok(())
+}
+
+/// Creates an item picked from the loot table `table`.
+pub fn gen_loot(table: &Arr) -> GameResult<ItemStack> {
+    let key = pick_str_from_tup_arr(table).context("Parsing loot table")?;
+    let item = Item::from_key(&key).context(format!("Could not load item \"{}\"", key))?;
+
+    Ok(ItemStack::new(item, 1))
+}
+
+/// Gives `actor` an item from the `drops` table in `actor_data`, with a chance of `drop_chance`.
+pub fn gen_drops(actor: &mut Actor, actor_data: &Obj) -> GameResult<()> {
+    if actor_data.get("drops").is_none() {
+        return Ok(());
+    }
+
+    if chance(bigr_to_gamer(actor_data.get_frac("drop_chance")?)?) {
+        actor.add_item(gen_loot(&actor_data.get_arr("drops")?)?);
+    }
+
+    Ok(())
+}
@@ -1,15 +1,31 @@
 //! Module containing dungeon generation algorithms.
 
+mod bsp;
+mod cave;
+mod connect;
+mod loot;
+mod overworld;
+mod spawn;
+mod terrain;
 mod util;
+mod vault;
 
 use crate::actor::Actor;
+use crate::constants::{DUNGEON_TRIES, ROOM_TRIES};
 use crate::coord::Coord;
 use crate::defs::*;
 use crate::dungeon::{Dungeon, DungeonList, DungeonType};
-use crate::error::{err_unexpected, GameError};
+use crate::error::{err_generation, err_unexpected, GameError};
 use crate::game_data::GameData;
+use crate::generate::bsp::gen_dungeon_bsp;
+use crate::generate::cave::gen_dungeon_cave;
+use crate::generate::loot::gen_floor_items;
+use crate::generate::overworld::gen_dungeon_overworld;
+use crate::generate::spawn::gen_monsters;
+use crate::generate::terrain::gen_terrain;
 use crate::generate::util::*;
-use crate::object::Object;
+use crate::generate::vault::gen_vaults;
+use crate::object::{Object, TrapEffect};
 use crate::player;
 use crate::tile::{Tile, TileInfo};
 use crate::ui::draw_game;
@@ -26,12 +42,34 @@ use std::rc::Rc;
 use std::sync::{mpsc, Arc, Mutex};
 use std::{fmt, thread, time};
 
-/// Generates a connected series of dungeons.
+pub use crate::generate::connect::{connect_dungeon, is_connected};
+pub use crate::generate::loot::gen_loot;
+pub use crate::generate::overworld::gen_overworld;
+pub use crate::generate::spawn::{gen_group, gen_monster};
+pub use crate::generate::util::danger_table;
+pub use crate::generate::vault::{place_vault, Vault};
+
+/// Generates a connected series of dungeons, below the overworld if the game has one.
 pub fn gen_dungeon_list(
     mut dungeon_list: DungeonList,
     dungeons_arr: &Arr,
     num_dungeons: usize,
 ) -> GameResult<DungeonList> {
+    // Generate the overworld.
+
+    let overworld_profile = {
+        let database = DATABASE.read().unwrap();
+        if database.get("overworld").is_some() {
+            Some(database.get_obj("overworld")?)
+        } else {
+            None
+        }
+    };
+    if let Some(profile) = overworld_profile {
+        dungeon_list.overworld =
+            Some(gen_overworld(&profile).context("Parsing \"overworld.over\"")?);
+    }
+
     // Generate each depth.
 
     for n in 0..num_dungeons {
@@ -59,19 +97,13 @@ pub fn gen_dungeon_list(
 
     // let mut dungeon_list = Arc::try_unwrap(dungeon_list).unwrap().into_inner()?;
 
-    // Generate stairs and pits, skipping the last depth.
-
-    /*
-        for n in 0..dungeon_list.len() - 1 {
-        gen_pits(dungeon_list, n);
-    }
-         */
-
     // Add player.
 
-    let player = gen_player(&mut dungeon_list, 0)?;
+    dungeon_list.current_depth = 0;
+    dungeon_list.on_overworld = dungeon_list.overworld.is_some();
     {
-        let dungeon = &mut dungeon_list[0];
+        let dungeon = dungeon_list.current_dungeon();
+        let player = gen_player(dungeon)?;
         dungeon.add_actor(player);
     }
 
@@ -84,28 +116,142 @@ fn create_dungeon(
     index: usize,
 ) -> GameResult<()> {
     let profile = get_dungeon_profile(dungeons_arr, index)?;
-    let dungeon = Dungeon::new(index as u32, &profile)
-        .context(format!("Failed to create dungeon at depth {}", index))?;
+
+    // Generate stairs connecting this depth to its neighbors. The first depth only has an up
+    // staircase if it leads out to the overworld, and the last depth has no down staircase.
+    let num_dungeons = dungeons_arr.len();
+    let up = index > 0 || dungeon_list.overworld.is_some();
+    let dungeon = gen_valid_dungeon(index as u32, &profile, up, index + 1 < num_dungeons)?;
+
     dungeon_list.push(dungeon);
 
     Ok(())
 }
 
+/// Generates a depth of the dungeon with vaults, stairs, traps, items and monsters, and checks that
+/// all of it can be reached. Disconnected levels are repaired if possible and generated again
+/// otherwise, up to `DUNGEON_TRIES` times.
+pub fn gen_valid_dungeon(
+    danger_level: u32,
+    profile: &Obj,
+    up: bool,
+    down: bool,
+) -> GameResult<Dungeon> {
+    for _ in 0..DUNGEON_TRIES {
+        let mut dungeon = Dungeon::new(danger_level, profile).context(format!(
+            "Failed to create dungeon at depth {}",
+            danger_level
+        ))?;
+
+        gen_terrain(&mut dungeon, profile, down).context(format!(
+            "Failed to generate terrain at depth {}",
+            danger_level
+        ))?;
+        gen_vaults(&mut dungeon, profile).context(format!(
+            "Failed to generate vaults at depth {}",
+            danger_level
+        ))?;
+
+        if !connect_dungeon(&mut dungeon, profile)? {
+            continue;
+        }
+
+        gen_stairs(&mut dungeon, profile, up, down).context(format!(
+            "Failed to generate stairs at depth {}",
+            danger_level
+        ))?;
+        gen_traps(&mut dungeon, profile, down).context(format!(
+            "Failed to generate traps at depth {}",
+            danger_level
+        ))?;
+        gen_floor_items(&mut dungeon, profile).context(format!(
+            "Failed to generate items at depth {}",
+            danger_level
+        ))?;
+        gen_monsters(&mut dungeon, profile).context(format!(
+            "Failed to generate monsters at depth {}",
+            danger_level
+        ))?;
+
+        return Ok(dungeon);
+    }
+
+    err_generation(danger_level, DUNGEON_TRIES)
+}
+
 /// Generates a single depth of the dungeon.
 pub fn gen_dungeon(mut dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()> {
     match dungeon.dungeon_type {
         DungeonType::Room => gen_dungeon_room(&mut dungeon, profile)?,
+        DungeonType::Bsp => gen_dungeon_bsp(dungeon, profile)?,
+        DungeonType::Cave => gen_dungeon_cave(dungeon, profile)?,
+        DungeonType::Overworld => gen_dungeon_overworld(dungeon, profile)?,
         DungeonType::Empty => gen_dungeon_empty(&mut dungeon, profile)?,
     }
 
     Ok(())
 }
 
-/// Generates pits for a depth of the dungeon.
-fn gen_pits(dungeon_list: &mut DungeonList, index: usize) {
-    debug_assert!(index < dungeon_list.len() - 1);
+/// Generates the up and/or down staircases for a depth of the dungeon.
+///
+/// Each depth has at most one staircase in each direction. Taking the down staircase on one depth
+/// leads to the up staircase on the next, and vice versa.
+fn gen_stairs(dungeon: &mut Dungeon, profile: &Obj, up: bool, down: bool) -> GameResult<()> {
+    if up {
+        let tile_data = profile.get_obj("stairs_up_tile")?;
+        gen_staircase(dungeon, &tile_data)?;
+    }
+    if down {
+        let tile_data = profile.get_obj("stairs_down_tile")?;
+        gen_staircase(dungeon, &tile_data)?;
+    }
 
-    unimplemented!();
+    Ok(())
+}
+
+/// Places a single staircase of type `tile_data` in a random open location in `dungeon`.
+fn gen_staircase(dungeon: &mut Dungeon, tile_data: &Obj) -> GameResult<()> {
+    let coord = match dungeon.random_open_coord_actor() {
+        Some(coord) => coord,
+        None => return err_unexpected("Ran out of tiles for stairs"),
+    };
+
+    dungeon[coord].set_tile_info(tile_data)
+}
+
+/// Scatters traps picked from the profile's `traps` array over open tiles in `dungeon`. Profiles
+/// without traps leave the dungeon untouched. Trap doors are only placed if there is a depth below.
+fn gen_traps(dungeon: &mut Dungeon, profile: &Obj, down: bool) -> GameResult<()> {
+    if profile.get("traps").is_none() {
+        return Ok(());
+    }
+
+    let traps = profile.get_arr("traps")?;
+    let num_traps = rand_int(
+        big_to_usize(profile.get_int("min_num_traps")?)?,
+        big_to_usize(profile.get_int("max_num_traps")?)?,
+    );
+
+    for _ in 0..num_traps {
+        let trap_data = pick_obj_from_tup_arr(&traps)
+            .context("Parsing \"traps\" Arr in \"dungeon_profiles.over\"")?;
+        let mut trap = Object::new(Default::default(), &trap_data, true)
+            .context(format!("Could not load object:\n{}", trap_data))?;
+        match trap.trap() {
+            Some(info) if info.effect == TrapEffect::Pit && !down => continue,
+            Some(_) => (),
+            None => return err_unexpected("Non-trap object in \"traps\""),
+        }
+
+        let coord = match dungeon.random_open_coord_actor() {
+            Some(coord) => coord,
+            None => return err_unexpected("Ran out of tiles for traps"),
+        };
+        trap.set_coord(coord);
+        dungeon.add_object(trap);
+    }
+
+    Ok(())
 }
 
 /// Creates an actor of type `name` and places it in a random open location in `dungeon`.
@@ -127,10 +273,7 @@ fn gen_actor_random_coord(dungeon: &Dungeon, actor_data: &Obj) -> GameResult<Act
 }
 
 /// Creates the player and places him in a random location of the dungeon.
-fn gen_player(dungeon_list: &mut DungeonList, depth: usize) -> GameResult<Actor> {
-    dungeon_list.current_depth = depth;
-
-    let mut dungeon = &mut dungeon_list[depth];
+fn gen_player(mut dungeon: &mut Dungeon) -> GameResult<Actor> {
     let player_data = DATABASE.read().unwrap().get_obj("player")?;
 
     let player = gen_actor_random_coord(dungeon, &player_data)?;
@@ -197,8 +340,8 @@ pub fn gen_dungeon_room(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()>
     ));
 
     // Generate rooms by looking for free space next to existing rooms.
-    for _ in 0..goal_num_rooms - 1 {
-        loop {
+    'rooms: for _ in 0..goal_num_rooms - 1 {
+        for _ in 0..ROOM_TRIES {
             let room = room_list.choose().unwrap().clone();
             let direction = direction_list.choose().unwrap();
 
@@ -216,9 +359,12 @@ pub fn gen_dungeon_room(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()>
                 3
             ) {
                 room_list.push(new_room);
-                break;
+                continue 'rooms;
             };
         }
+
+        // No free space turned up, so settle for the rooms we have.
+        break;
     }
 
     // Initialize the dungeon tile grid and convert the list of rooms into a tile grid
@@ -0,0 +1,285 @@
+//! Overworld generation.
+//!
+//! The overworld is the wide landscape above the dungeon. Two layers of noise give every tile an
+//! elevation and a moisture, and the first of the profile's `biomes` whose `max_elevation` and
+//! `max_moisture` are not exceeded decides its `tile`. A biome may scatter its `scatter` tile, such
+//! as trees, over a `scatter_chance` of its area, and biomes with a `liquid` are flooded with it up
+//! to `sea_level`. Land rises by `height_scale` from sea level to the highest elevation, so hills
+//! block the view and take longer to climb.
+//!
+//! Between `min_num_towns` and `max_num_towns` towns are cleared out of the landscape, each with up
+//! to `max_num_houses` houses and between `min_num_townsfolk` and `max_num_townsfolk` townsfolk
+//! from the `townsfolk` table. `num_entrances` dungeon entrances lead down to the first depth.
+
+use crate::constants::DUNGEON_TRIES;
+use crate::coord::Coord;
+use crate::defs::*;
+use crate::dungeon::Dungeon;
+use crate::error::{err_convert, err_generation, err_unexpected};
+use crate::generate::connect::connect_dungeon;
+use crate::generate::spawn::{gen_monster, gen_monsters};
+use crate::generate::util::pick_obj_from_tup_arr;
+use crate::object::Object;
+use crate::util::noise::Noise;
+use crate::util::rand::{chance, rand_int, Choose};
+use crate::util::rectangle::Rectangle;
+use crate::GameResult;
+use failure::ResultExt;
+use over::Obj;
+
+/// Number of spots to try for each town before giving up on it.
+const TOWN_TRIES: usize = 50;
+/// Number of spots to try for each house before giving up on it.
+const HOUSE_TRIES: usize = 20;
+
+/// Generates the overworld with its towns, dungeon entrances and wildlife, making sure that all of
+/// it can be reached.
+pub fn gen_overworld(profile: &Obj) -> GameResult<Dungeon> {
+    for _ in 0..DUNGEON_TRIES {
+        let mut dungeon = Dungeon::new(0, profile).context("Failed to create overworld")?;
+
+        gen_towns(&mut dungeon, profile).context("Failed to generate towns")?;
+        if !connect_dungeon(&mut dungeon, profile)? {
+            continue;
+        }
+
+        gen_entrances(&mut dungeon, profile).context("Failed to generate dungeon entrances")?;
+        gen_monsters(&mut dungeon, profile).context("Failed to generate overworld monsters")?;
+
+        return Ok(dungeon);
+    }
+
+    err_generation(0, DUNGEON_TRIES)
+}
+
+struct Biome {
+    max_elevation: f64,
+    max_moisture: f64,
+
+    tile: Obj,
+    scatter: Option<(Obj, GameRatio)>,
+    liquid: Option<Obj>,
+}
+
+impl Biome {
+    fn new(data: &Obj) -> GameResult<Biome> {
+        Ok(Biome {
+            max_elevation: f64::from(bigr_to_f32(data.get_frac("max_elevation")?)?),
+            max_moisture: f64::from(bigr_to_f32(data.get_frac("max_moisture")?)?),
+
+            tile: data.get_obj("tile")?,
+            scatter: if data.get("scatter").is_some() {
+                Some((
+                    data.get_obj("scatter")?,
+                    bigr_to_gamer(data.get_frac("scatter_chance")?)?,
+                ))
+            } else {
+                None
+            },
+            liquid: if data.get("liquid").is_some() {
+                Some(data.get_obj("liquid")?)
+            } else {
+                None
+            },
+        })
+    }
+}
+
+/// Fills the overworld with biomes. The edge of the map is left as the profile's `wall_tile`.
+pub fn gen_dungeon_overworld(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()> {
+    let width = big_to_usize(profile.get_int("width")?)?;
+    let height = big_to_usize(profile.get_int("height")?)?;
+    let scale = big_to_usize(profile.get_int("scale")?)?;
+    let octaves = big_to_usize(profile.get_int("octaves")?)?;
+    let sea_level = f64::from(bigr_to_f32(profile.get_frac("sea_level")?)?);
+    let height_scale = f64::from(big_to_i32(profile.get_int("height_scale")?)?);
+
+    let mut biomes = Vec::new();
+    for data in profile.get_arr("biomes")?.iter() {
+        let data = data.get_obj()?;
+        biomes.push(Biome::new(&data).context(format!("Could not load biome:\n{}", data))?);
+    }
+
+    dungeon.init_grid(width, height, &profile.get_obj("wall_tile")?)?;
+
+    let elevation = Noise::new(width, height, scale, octaves);
+    let moisture = Noise::new(width, height, scale, octaves);
+
+    for x in 1..width - 1 {
+        for y in 1..height - 1 {
+            let coord = Coord::new(x as i32, y as i32);
+            let (e, m) = (elevation.get(x, y), moisture.get(x, y));
+            let biome = match biomes
+                .iter()
+                .find(|biome| e <= biome.max_elevation && m <= biome.max_moisture)
+            {
+                Some(biome) => biome,
+                None => return err_convert(e, "No biome for elevation"),
+            };
+
+            let tile = &mut dungeon[coord];
+            match biome.scatter {
+                Some((ref scatter, scatter_chance)) if chance(scatter_chance) => {
+                    tile.set_tile_info(scatter)?
+                }
+                _ => tile.set_tile_info(&biome.tile)?,
+            }
+
+            let rise = ((e - sea_level) * height_scale).round() as i32;
+            match biome.liquid {
+                Some(ref liquid) => {
+                    let depth = (-rise).max(1);
+                    tile.height = -depth;
+                    tile.set_feature(liquid, depth as u32)?;
+                }
+                None => tile.height = rise.max(0),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Clears out towns in the landscape, away from each other.
+fn gen_towns(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()> {
+    let num_towns = rand_int(
+        big_to_usize(profile.get_int("min_num_towns")?)?,
+        big_to_usize(profile.get_int("max_num_towns")?)?,
+    );
+    let town_width = big_to_i32(profile.get_int("town_width")?)?;
+    let town_height = big_to_i32(profile.get_int("town_height")?)?;
+
+    let width = dungeon.width() as i32;
+    let height = dungeon.height() as i32;
+    if town_width + 2 >= width || town_height + 2 >= height {
+        return err_convert(town_width, "Towns don't fit in the overworld");
+    }
+
+    let mut towns: Vec<Rectangle> = Vec::new();
+    for _ in 0..num_towns {
+        for _ in 0..TOWN_TRIES {
+            let left = rand_int(1, width - town_width - 1);
+            let top = rand_int(1, height - town_height - 1);
+            let rect =
+                Rectangle::from_dimensions(left, top, town_width as usize, town_height as usize);
+
+            if towns.iter().any(|town| town.overlaps(&rect)) {
+                continue;
+            }
+
+            gen_town(dungeon, profile, &rect)?;
+            towns.push(rect);
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+// Clears the area at `rect`, builds houses in it and fills it with townsfolk.
+fn gen_town(dungeon: &mut Dungeon, profile: &Obj, rect: &Rectangle) -> GameResult<()> {
+    let town_floor = profile.get_obj("town_floor_tile")?;
+    for x in rect.left..=rect.right {
+        for y in rect.top..=rect.bottom {
+            let tile = &mut dungeon[Coord::new(x, y)];
+            tile.set_tile_info(&town_floor)?;
+            tile.height = 0;
+            tile.feature = None;
+        }
+    }
+
+    // Houses keep a street's width away from the edge of town and from each other.
+    let min_size = big_to_i32(profile.get_int("min_house_size")?)?;
+    let max_size = big_to_i32(profile.get_int("max_house_size")?)?;
+    let max_num_houses = big_to_usize(profile.get_int("max_num_houses")?)?;
+    let mut houses: Vec<Rectangle> = Vec::new();
+
+    for _ in 0..max_num_houses {
+        for _ in 0..HOUSE_TRIES {
+            let (w, h) = (rand_int(min_size, max_size), rand_int(min_size, max_size));
+            if rect.left + 1 > rect.right - w || rect.top + 1 > rect.bottom - h {
+                break;
+            }
+            let left = rand_int(rect.left + 1, rect.right - w);
+            let top = rand_int(rect.top + 1, rect.bottom - h);
+            let house = Rectangle::from_dimensions(left, top, w as usize, h as usize);
+            let street = Rectangle::new(left - 1, top - 1, house.right + 1, house.bottom + 1);
+
+            if houses.iter().any(|other| other.overlaps(&street)) {
+                continue;
+            }
+
+            gen_house(dungeon, profile, &house)?;
+            houses.push(house);
+            break;
+        }
+    }
+
+    // Townsfolk walk the streets.
+    let townsfolk = profile.get_arr("townsfolk")?;
+    let num_townsfolk = rand_int(
+        big_to_usize(profile.get_int("min_num_townsfolk")?)?,
+        big_to_usize(profile.get_int("max_num_townsfolk")?)?,
+    );
+    let mut open: Vec<Coord> = (rect.left..=rect.right)
+        .flat_map(|x| (rect.top..=rect.bottom).map(move |y| Coord::new(x, y)))
+        .filter(|coord| dungeon.open_for_spawn(*coord))
+        .collect();
+
+    for _ in 0..num_townsfolk {
+        let index = match open.choose_index() {
+            Some(index) => index,
+            None => break,
+        };
+        let coord = open.swap_remove(index);
+        let actor_data = pick_obj_from_tup_arr(&townsfolk)
+            .context("Parsing \"townsfolk\" Arr in \"overworld.over\"")?;
+        gen_monster(dungeon, coord, &actor_data)?;
+    }
+
+    Ok(())
+}
+
+// Builds a house with walls around `rect` and a door in the middle of one of them.
+fn gen_house(dungeon: &mut Dungeon, profile: &Obj, rect: &Rectangle) -> GameResult<()> {
+    let wall = profile.get_obj("house_wall_tile")?;
+    let floor = profile.get_obj("house_floor_tile")?;
+
+    for x in rect.left..=rect.right {
+        for y in rect.top..=rect.bottom {
+            let edge = x == rect.left || x == rect.right || y == rect.top || y == rect.bottom;
+            dungeon[Coord::new(x, y)].set_tile_info(if edge { &wall } else { &floor })?;
+        }
+    }
+
+    let (mid_x, mid_y) = ((rect.left + rect.right) / 2, (rect.top + rect.bottom) / 2);
+    let door = match rand_int(0, 3) {
+        0 => Coord::new(mid_x, rect.top),
+        1 => Coord::new(rect.right, mid_y),
+        2 => Coord::new(mid_x, rect.bottom),
+        _ => Coord::new(rect.left, mid_y),
+    };
+    dungeon[door].set_tile_info(&floor)?;
+
+    let door_data = profile.get_obj("house_door")?;
+    let object = Object::new(door, &door_data, false)
+        .context(format!("Could not load door:\n{}", door_data))?;
+    dungeon.add_object(object);
+
+    Ok(())
+}
+
+// Places the entrances to the dungeon in random open spots.
+fn gen_entrances(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()> {
+    let entrance = profile.get_obj("entrance_tile")?;
+
+    for _ in 0..big_to_usize(profile.get_int("num_entrances")?)? {
+        let coord = match dungeon.random_open_coord_actor() {
+            Some(coord) => coord,
+            None => return err_unexpected("Ran out of tiles for dungeon entrances"),
+        };
+        dungeon[coord].set_tile_info(&entrance)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! Monster spawning.
+//!
+//! Profiles with `spawns` get between `min_num_spawns` and `max_num_spawns` spawns. Each spawn
+//! picks a monster from the spawn table for the dungeon's danger level, or with a chance of
+//! `out_of_depth_chance` from the table `out_of_depth_levels` deeper. Monsters with a
+//! `min_group_size` and `max_group_size` come in groups of their own kind, and monsters with a
+//! `pack` are followed by `min_pack_size` to `max_pack_size` of the actor with that key. Monsters
+//! may carry loot from their `drops` table, which they leave behind when they die.
+
+use crate::actor::Actor;
+use crate::constants::SPAWN_GROUP_RADIUS;
+use crate::coord::Coord;
+use crate::defs::*;
+use crate::dungeon::Dungeon;
+use crate::generate::loot::gen_drops;
+use crate::generate::util::{danger_table, pick_obj_from_tup_arr};
+use crate::util::rand::{chance, rand_int};
+use crate::{GameResult, DATABASE};
+use failure::ResultExt;
+use over::Obj;
+use std::collections::{HashSet, VecDeque};
+
+/// Spawns monsters picked from the profile's spawn tables into `dungeon`. Profiles without `spawns`
+/// leave the dungeon untouched.
+pub fn gen_monsters(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()> {
+    if profile.get("spawns").is_none() {
+        return Ok(());
+    }
+
+    let spawns = profile.get_arr("spawns")?;
+    let num_spawns = rand_int(
+        big_to_usize(profile.get_int("min_num_spawns")?)?,
+        big_to_usize(profile.get_int("max_num_spawns")?)?,
+    );
+    let out_of_depth_chance = bigr_to_gamer(profile.get_frac("out_of_depth_chance")?)?;
+    let out_of_depth_levels = big_to_u32(profile.get_int("out_of_depth_levels")?)?;
+
+    for _ in 0..num_spawns {
+        let mut danger_level = dungeon.danger_level;
+        if chance(out_of_depth_chance) {
+            danger_level += out_of_depth_levels;
+        }
+
+        let table = match danger_table(&spawns, danger_level, "monsters")? {
+            Some(table) => table,
+            None => continue,
+        };
+        let actor_data = pick_obj_from_tup_arr(&table)
+            .context("Parsing \"monsters\" Arr in \"dungeon_profiles.over\"")?;
+
+        // Stop once the level is full.
+        let coord = match dungeon.random_open_coord_actor() {
+            Some(coord) => coord,
+            None => break,
+        };
+        gen_group(dungeon, coord, &actor_data)?;
+    }
+
+    Ok(())
+}
+
+/// Spawns the monster `actor_data` at `coord`, together with the rest of its group and its pack.
+/// Returns the number of monsters spawned.
+pub fn gen_group(dungeon: &mut Dungeon, coord: Coord, actor_data: &Obj) -> GameResult<usize> {
+    let group_size = if actor_data.get("min_group_size").is_some() {
+        rand_int(
+            big_to_usize(actor_data.get_int("min_group_size")?)?,
+            big_to_usize(actor_data.get_int("max_group_size")?)?,
+        )
+    } else {
+        1
+    };
+
+    let mut members = vec![actor_data.clone(); group_size];
+    if actor_data.get("pack").is_some() {
+        let pack_data = DATABASE
+            .read()
+            .unwrap()
+            .get_obj("actors")?
+            .get_obj(&actor_data.get_str("pack")?)?;
+        let pack_size = rand_int(
+            big_to_usize(actor_data.get_int("min_pack_size")?)?,
+            big_to_usize(actor_data.get_int("max_pack_size")?)?,
+        );
+        members.extend(vec![pack_data; pack_size]);
+    }
+
+    let coords = open_coords_near(dungeon, coord, members.len());
+    for (coord, data) in coords.iter().zip(&members) {
+        gen_monster(dungeon, *coord, data)?;
+    }
+
+    Ok(coords.len())
+}
+
+/// Spawns the monster `actor_data` at `coord`, carrying whatever it drops when it dies.
+pub fn gen_monster(dungeon: &mut Dungeon, coord: Coord, actor_data: &Obj) -> GameResult<()> {
+    let mut actor =
+        Actor::new(coord, actor_data).context(format!("Could not load actor:\n{}", actor_data))?;
+    gen_drops(&mut actor, actor_data)?;
+    dungeon.add_actor(actor);
+
+    Ok(())
+}
+
+// Returns up to `num` tiles where actors can be spawned, reachable from `coord` within
+// `SPAWN_GROUP_RADIUS` steps and closest first.
+fn open_coords_near(dungeon: &Dungeon, coord: Coord, num: usize) -> Vec<Coord> {
+    let mut coords = Vec::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(coord);
+    queue.push_back((coord, 0));
+
+    while let Some((coord, distance)) = queue.pop_front() {
+        if coords.len() == num {
+            break;
+        }
+        if dungeon.open_for_spawn(coord) {
+            coords.push(coord);
+        }
+        if distance == SPAWN_GROUP_RADIUS {
+            continue;
+        }
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let neighbor = coord + Coord::new(dx, dy);
+                if dungeon.in_bounds(neighbor)
+                    && dungeon[neighbor].passable()
+                    && !dungeon[neighbor].hazard()
+                    && visited.insert(neighbor)
+                {
+                    queue.push_back((neighbor, distance + 1));
+                }
+            }
+        }
+    }
+
+    coords
+}
@@ -0,0 +1,105 @@
+//! Terrain: rolling ground, pits, chasms and pools.
+//!
+//! Profiles with `terrain` raise and lower the open tiles of a level with noise, between
+//! `min_height` and `max_height`. The noise varies over `scale` tiles, with `octaves` layers of
+//! finer detail on top. With a `chasm` feature, tiles that sink to `constants::CHASM_HEIGHT` open
+//! up into chasms, unless there is no depth below. With a `liquid_level`, every tile below it is
+//! flooded with a liquid picked from the `liquids` table for the dungeon's danger level, as deep as
+//! it lies below that level. Vaults and tunnels dug later are level with the ground.
+
+use crate::constants::CHASM_HEIGHT;
+use crate::coord::Coord;
+use crate::defs::*;
+use crate::dungeon::Dungeon;
+use crate::error::err_argument;
+use crate::generate::util::{danger_table, pick_obj_from_tup_arr};
+use crate::util::noise::Noise;
+use crate::GameResult;
+use failure::ResultExt;
+use over::Obj;
+
+/// Fills the heightmap and features of `dungeon` from the profile's `terrain` parameters. Chasms
+/// only open up if there is a depth below, as told by `down`. Profiles without `terrain` leave the
+/// dungeon flat.
+pub fn gen_terrain(dungeon: &mut Dungeon, profile: &Obj, down: bool) -> GameResult<()> {
+    if profile.get("terrain").is_none() {
+        return Ok(());
+    }
+
+    let terrain = profile.get_obj("terrain")?;
+    let min_height = big_to_i32(terrain.get_int("min_height")?)?;
+    let max_height = big_to_i32(terrain.get_int("max_height")?)?;
+    let scale = big_to_usize(terrain.get_int("scale")?)?;
+    let octaves = big_to_usize(terrain.get_int("octaves")?)?;
+    if min_height > max_height {
+        return err_argument(
+            format!("{} > {}", min_height, max_height),
+            "Terrain min_height must not be above max_height",
+        );
+    }
+    if scale == 0 {
+        return err_argument(scale.to_string(), "Terrain scale must be positive");
+    }
+
+    let (width, height) = (dungeon.width(), dungeon.height());
+    let noise = Noise::new(width, height, scale, octaves);
+
+    for x in 0..width {
+        for y in 0..height {
+            let coord = Coord::new(x as i32, y as i32);
+            if !dungeon[coord].passable() {
+                continue;
+            }
+
+            let range = f64::from(max_height - min_height);
+            dungeon[coord].height = min_height + (noise.get(x, y) * range).round() as i32;
+        }
+    }
+
+    let chasm = if down && terrain.get("chasm").is_some() {
+        Some(terrain.get_obj("chasm")?)
+    } else {
+        None
+    };
+    let liquid = if terrain.get("liquid_level").is_some() {
+        let level = big_to_i32(terrain.get_int("liquid_level")?)?;
+        match danger_table(
+            &terrain.get_arr("liquids")?,
+            dungeon.danger_level,
+            "liquids",
+        )? {
+            Some(table) => Some((
+                level,
+                pick_obj_from_tup_arr(&table)
+                    .context("Parsing \"liquids\" Arr in \"dungeon_profiles.over\"")?,
+            )),
+            None => None,
+        }
+    } else {
+        None
+    };
+
+    for x in 0..width {
+        for y in 0..height {
+            let coord = Coord::new(x as i32, y as i32);
+            if !dungeon[coord].passable() {
+                continue;
+            }
+
+            let tile_height = dungeon[coord].height;
+            if let Some(ref chasm) = chasm {
+                if tile_height <= CHASM_HEIGHT {
+                    dungeon[coord].set_feature(chasm, 0)?;
+                    continue;
+                }
+            }
+            if let Some((level, ref liquid)) = liquid {
+                if tile_height < level {
+                    dungeon[coord].set_feature(liquid, (level - tile_height) as u32)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -4,9 +4,19 @@ use crate::defs::*;
 use crate::util::rand::rand_ratio;
 use crate::GameResult;
 use over::arr::Arr;
+use over::value::Value;
 use over::Obj;
 
 pub fn pick_obj_from_tup_arr(arr: &Arr) -> GameResult<Obj> {
+    Ok(pick_from_tup_arr(arr)?.get_obj()?)
+}
+
+/// Picks a string, such as the key of an item, from an array of (string, ratio) tuples.
+pub fn pick_str_from_tup_arr(arr: &Arr) -> GameResult<String> {
+    Ok(pick_from_tup_arr(arr)?.get_str()?)
+}
+
+fn pick_from_tup_arr(arr: &Arr) -> GameResult<Value> {
     let mut roll_count = GameRatio::new(0, 1);
     let len = arr.len();
     let roll = rand_ratio(0, 1, 100);
@@ -15,12 +25,27 @@ pub fn pick_obj_from_tup_arr(arr: &Arr) -> GameResult<Obj> {
         let tup = arr.get(i)?.get_tup()?;
         roll_count = bigr_to_gamer(tup.get(1)?.get_frac()?)? + roll_count;
         if roll <= roll_count {
-            return Ok(tup.get(0)?.get_obj()?);
+            return Ok(tup.get(0)?);
         }
     }
 
     Err(format_err!(
-        "Exhausted array of (object, ratio) tuples:\n{}",
+        "Exhausted array of (value, ratio) tuples:\n{}",
         arr
     ))
 }
+
+/// Returns the array at `field` of the last table in `tables` whose `min_danger_level` has been
+/// reached by `danger_level`, or `None` if there is no such table.
+pub fn danger_table(tables: &Arr, danger_level: u32, field: &str) -> GameResult<Option<Arr>> {
+    let mut table = None;
+
+    for data in tables.iter() {
+        let data = data.get_obj()?;
+        if big_to_u32(data.get_int("min_danger_level")?)? <= danger_level {
+            table = Some(data.get_arr(field)?);
+        }
+    }
+
+    Ok(table)
+}
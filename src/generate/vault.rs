@@ -0,0 +1,343 @@
+//! Vaults: hand-made rooms stamped into generated levels.
+//!
+//! A vault is an ASCII `map` with a `legend` that says what each glyph stands for. Each legend
+//! entry may set a `tile`, an `object` (with its `active` state and a `link`), an `actor`, the key
+//! of an `item` and `loot`, which picks an item from the profile's loot tables. A few glyphs work
+//! without a legend entry: `#` is the profile's `wall_tile`, `.` is its `floor_tile` and a space
+//! leaves the level untouched.
+//!
+//! Vaults are only placed in solid rock, and a tunnel is dug from their edge to the nearest open
+//! tile so that they can always be reached.
+
+use crate::coord::Coord;
+use crate::defs::*;
+use crate::dungeon::Dungeon;
+use crate::error::err_convert;
+use crate::generate::loot::gen_loot;
+use crate::generate::spawn::gen_monster;
+use crate::generate::util::{danger_table, pick_obj_from_tup_arr};
+use crate::item::{Item, ItemStack};
+use crate::object::Object;
+use crate::util::direction::CardinalDirection;
+use crate::util::rand::{chance, rand_int};
+use crate::util::rectangle::Rectangle;
+use crate::GameResult;
+use failure::ResultExt;
+use over::arr::Arr;
+use over::Obj;
+use std::collections::{HashMap, VecDeque};
+
+/// Number of spots to try for each vault before giving up on it.
+const VAULT_TRIES: usize = 50;
+
+/// Stamps vaults from the profile's `vaults` array into `dungeon`. Up to `max_num_vaults` vaults
+/// are placed, each with a chance of `vault_chance`, and only if the dungeon's danger level is in
+/// the vault's range. Profiles without vaults leave the dungeon untouched.
+pub fn gen_vaults(dungeon: &mut Dungeon, profile: &Obj) -> GameResult<()> {
+    if profile.get("vaults").is_none() {
+        return Ok(());
+    }
+
+    let vaults = profile.get_arr("vaults")?;
+    let vault_chance = bigr_to_gamer(profile.get_frac("vault_chance")?)?;
+    let max_num_vaults = big_to_usize(profile.get_int("max_num_vaults")?)?;
+    let mut placed = Vec::new();
+
+    for _ in 0..max_num_vaults {
+        if !chance(vault_chance) {
+            continue;
+        }
+
+        let vault_data = pick_obj_from_tup_arr(&vaults)
+            .context("Parsing \"vaults\" Arr in \"dungeon_profiles.over\"")?;
+        let vault = Vault::new(&vault_data, profile)
+            .context(format!("Could not load vault:\n{}", vault_data))?;
+        if !vault.allowed(dungeon.danger_level) {
+            continue;
+        }
+
+        if let Some(rect) = place_vault(dungeon, &vault, &placed, placed.len())? {
+            placed.push(rect);
+        }
+    }
+
+    Ok(())
+}
+
+/// Tries to stamp `vault` into a random spot in `dungeon`, away from the vaults in `placed`.
+/// Returns the area taken up by the vault, or `None` if no spot was found.
+pub fn place_vault(
+    dungeon: &mut Dungeon,
+    vault: &Vault,
+    placed: &[Rectangle],
+    id: usize,
+) -> GameResult<Option<Rectangle>> {
+    let width = dungeon.width() as i32;
+    let height = dungeon.height() as i32;
+    if vault.width + 2 >= width || vault.height + 2 >= height {
+        return Ok(None);
+    }
+
+    for _ in 0..VAULT_TRIES {
+        let left = rand_int(1, width - vault.width - 1);
+        let top = rand_int(1, height - vault.height - 1);
+        let rect =
+            Rectangle::from_dimensions(left, top, vault.width as usize, vault.height as usize);
+
+        if !in_rock(dungeon, &rect) {
+            continue;
+        }
+
+        if let Some(tunnel) = find_tunnel(dungeon, vault, &rect, placed) {
+            vault.stamp(dungeon, Coord::new(left, top), id)?;
+            for coord in tunnel {
+                dungeon[coord].set_tile_info(&vault.floor)?;
+                dungeon[coord].height = 0;
+                dungeon[coord].feature = None;
+            }
+
+            return Ok(Some(rect));
+        }
+    }
+
+    Ok(None)
+}
+
+/// A vault loaded from the database.
+pub struct Vault {
+    map: Vec<Vec<char>>,
+    legend: HashMap<char, Obj>,
+    width: i32,
+    height: i32,
+
+    min_danger_level: u32,
+    max_danger_level: Option<u32>,
+
+    wall: Obj,
+    floor: Obj,
+    loot: Option<Arr>,
+}
+
+impl Vault {
+    /// Loads a vault, using the wall and floor tiles of `profile` for the `#` and `.` glyphs and
+    /// its `vault_loot` or `loot` tables for treasure.
+    pub fn new(data: &Obj, profile: &Obj) -> GameResult<Vault> {
+        let mut map = Vec::new();
+        for row in data.get_arr("map")?.iter() {
+            map.push(row.get_str()?.chars().collect::<Vec<_>>());
+        }
+        let width = map.iter().map(Vec::len).max().unwrap_or(0);
+        if width == 0 {
+            return err_convert(data, "Vault map is empty");
+        }
+        // Pad short rows with spaces.
+        for row in &mut map {
+            row.resize(width, ' ');
+        }
+
+        let mut legend = HashMap::new();
+        if data.get("legend").is_some() {
+            for entry in data.get_arr("legend")?.iter() {
+                let entry = entry.get_tup()?;
+                legend.insert(entry.get(0)?.get_char()?, entry.get(1)?.get_obj()?);
+            }
+        }
+
+        let vault = Vault {
+            width: width as i32,
+            height: map.len() as i32,
+            map,
+            legend,
+
+            min_danger_level: big_to_u32(data.get_int("min_danger_level")?)?,
+            max_danger_level: if data.get("max_danger_level").is_some() {
+                Some(big_to_u32(data.get_int("max_danger_level")?)?)
+            } else {
+                None
+            },
+
+            wall: profile.get_obj("wall_tile")?,
+            floor: profile.get_obj("floor_tile")?,
+            loot: if profile.get("vault_loot").is_some() {
+                Some(profile.get_arr("vault_loot")?)
+            } else if profile.get("loot").is_some() {
+                Some(profile.get_arr("loot")?)
+            } else {
+                None
+            },
+        };
+
+        // Check for unknown glyphs up front.
+        for row in &vault.map {
+            for c in row {
+                vault.glyph_tile(*c)?;
+            }
+        }
+
+        Ok(vault)
+    }
+
+    /// Returns true if the vault may appear at `danger_level`.
+    pub fn allowed(&self, danger_level: u32) -> bool {
+        match self.max_danger_level {
+            Some(max) => danger_level >= self.min_danger_level && danger_level <= max,
+            None => danger_level >= self.min_danger_level,
+        }
+    }
+
+    /// Stamps the vault into `dungeon` with its top left corner at `corner`. Links are made unique
+    /// to this vault with `id`, so that levers only control objects in their own vault.
+    pub fn stamp(&self, dungeon: &mut Dungeon, corner: Coord, id: usize) -> GameResult<()> {
+        for (y, row) in self.map.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                let coord = corner + Coord::new(x as i32, y as i32);
+
+                if let Some(tile) = self.glyph_tile(*c)? {
+                    dungeon[coord].set_tile_info(&tile)?;
+                    dungeon[coord].height = 0;
+                    dungeon[coord].feature = None;
+                }
+
+                let entry = match self.legend.get(c) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+
+                if entry.get("object").is_some() {
+                    let data = entry.get_obj("object")?;
+                    let active = if entry.get("active").is_some() {
+                        entry.get_bool("active")?
+                    } else {
+                        true
+                    };
+                    let mut object = Object::new(coord, &data, active)
+                        .context(format!("Could not load object:\n{}", data))?;
+                    if entry.get("link").is_some() {
+                        object.set_link(Some(format!("vault{}:{}", id, entry.get_str("link")?)));
+                    }
+                    dungeon.add_object(object);
+                }
+                if entry.get("actor").is_some() {
+                    gen_monster(dungeon, coord, &entry.get_obj("actor")?)?;
+                }
+                if entry.get("item").is_some() {
+                    let item = Item::from_key(&entry.get_str("item")?)?;
+                    dungeon.add_item(coord, ItemStack::new(item, 1));
+                }
+                if entry.get("loot").is_some() && entry.get_bool("loot")? {
+                    if let Some(ref loot) = self.loot {
+                        if let Some(table) = danger_table(loot, dungeon.danger_level, "items")? {
+                            dungeon.add_item(coord, gen_loot(&table)?);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the tile for glyph `c`, or `None` if the glyph leaves the tile untouched.
+    fn glyph_tile(&self, c: char) -> GameResult<Option<Obj>> {
+        Ok(match self.legend.get(&c) {
+            Some(entry) if entry.get("tile").is_some() => Some(entry.get_obj("tile")?),
+            Some(_) => Some(self.floor.clone()),
+            None => match c {
+                ' ' => None,
+                '#' => Some(self.wall.clone()),
+                '.' => Some(self.floor.clone()),
+                _ => return err_convert(c, "Vault glyph missing from legend"),
+            },
+        })
+    }
+
+    // Returns true if the tile at map position (`x`, `y`) can be walked on once stamped.
+    fn open(&self, x: i32, y: i32) -> bool {
+        match self.glyph_tile(self.map[y as usize][x as usize]) {
+            Ok(Some(tile)) => tile.get_bool("passable").unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+// Returns true if `rect` and the tiles around it are all solid rock.
+fn in_rock(dungeon: &Dungeon, rect: &Rectangle) -> bool {
+    for x in rect.left - 1..=rect.right + 1 {
+        for y in rect.top - 1..=rect.bottom + 1 {
+            let tile = &dungeon[Coord::new(x, y)];
+            if tile.passable() || tile.object.is_some() {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+// Finds the shortest tunnel from an open tile on the edge of the vault at `rect` to an open tile in
+// `dungeon`, going around the vault and the vaults in `placed`. Returns the tiles to dig out.
+fn find_tunnel(
+    dungeon: &Dungeon,
+    vault: &Vault,
+    rect: &Rectangle,
+    placed: &[Rectangle],
+) -> Option<Vec<Coord>> {
+    let width = dungeon.width() as i32;
+    let height = dungeon.height() as i32;
+    let blocked = |coord: Coord| {
+        coord.x <= 0
+            || coord.y <= 0
+            || coord.x >= width - 1
+            || coord.y >= height - 1
+            || contains(rect, coord)
+            || placed.iter().any(|other| contains(other, coord))
+    };
+
+    let mut parents: HashMap<Coord, Option<Coord>> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    // Start just outside each opening in the vault's edge.
+    for y in 0..vault.height {
+        for x in 0..vault.width {
+            let on_edge = x == 0 || y == 0 || x == vault.width - 1 || y == vault.height - 1;
+            if !on_edge || !vault.open(x, y) {
+                continue;
+            }
+
+            let coord = Coord::new(rect.left + x, rect.top + y);
+            for dir in CardinalDirection::all().iter() {
+                let start = coord.coord_in_dir(dir, 1);
+                if !blocked(start) && !parents.contains_key(&start) {
+                    parents.insert(start, None);
+                    queue.push_back(start);
+                }
+            }
+        }
+    }
+
+    while let Some(coord) = queue.pop_front() {
+        if dungeon[coord].passable() {
+            let mut tunnel = Vec::new();
+            let mut next = parents[&coord];
+            while let Some(coord) = next {
+                tunnel.push(coord);
+                next = parents[&coord];
+            }
+            return Some(tunnel);
+        }
+
+        for dir in CardinalDirection::all().iter() {
+            let neighbor = coord.coord_in_dir(dir, 1);
+            if !blocked(neighbor) && !parents.contains_key(&neighbor) {
+                parents.insert(neighbor, Some(coord));
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    None
+}
+
+fn contains(rect: &Rectangle, coord: Coord) -> bool {
+    coord.x >= rect.left && coord.x <= rect.right && coord.y >= rect.top && coord.y <= rect.bottom
+}
@@ -0,0 +1,151 @@
+//! Module for item identification.
+//!
+//! Some kinds of items, such as most potions and scrolls, start out unidentified. Each game they
+//! are given a random appearance like "red glass potion", made up of a color and the adjective of
+//! a material, and are known only by that appearance until the player identifies them.
+
+use crate::console::Color;
+use crate::error::err_unexpected;
+use crate::save::arr_from_vec;
+use crate::util::rand::rand_int;
+use crate::{GameResult, DATABASE, GAMEDATA};
+use over::arr::Arr;
+use over::Obj;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// How an unidentified kind of item looks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Appearance {
+    pub name: String,
+    pub color: Color,
+}
+
+/// The player's knowledge of which kinds of items are identified.
+#[derive(Clone, Debug, Default)]
+pub struct ItemKnowledge {
+    /// Appearances of the kinds of items that are still unidentified, by item key.
+    appearances: HashMap<String, Appearance>,
+}
+
+impl ItemKnowledge {
+    /// Gives every kind of item that needs identifying a random appearance. Appearances are unique
+    /// among items sharing the same unidentified name, e.g. among all potions.
+    pub fn generate() -> GameResult<ItemKnowledge> {
+        let database = DATABASE.read().unwrap();
+
+        // Sort everything by key so that the appearances only depend on the game seed.
+        let mut colors: Vec<(String, Color)> = Vec::new();
+        let background = GAMEDATA.read().unwrap().console_settings.background_color();
+        for (key, value) in database.get_obj("colors")?.iter() {
+            let color = Color::from_str(&value.get_str()?)?;
+            if color != background {
+                colors.push((key.replace('_', " "), color));
+            }
+        }
+        colors.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut adjectives: Vec<String> = GAMEDATA
+            .read()
+            .unwrap()
+            .material_info_list()
+            .iter()
+            .map(|material| material.adjective.clone())
+            .collect();
+        adjectives.sort();
+        adjectives.dedup();
+
+        let items = database.get_obj("items")?;
+        let mut keys: Vec<&String> = items.keys().collect();
+        keys.sort();
+
+        let mut appearances = HashMap::new();
+        let mut used = HashSet::new();
+        let combinations = colors.len() * adjectives.len();
+
+        for key in keys {
+            let data = items.get_obj(key)?;
+            if data.get_bool("identified")? {
+                continue;
+            }
+
+            let noun = data.get_str("appearance")?;
+            let mut index = rand_int(0, combinations - 1);
+            // Take the next free combination, giving up once they have all been used.
+            for _ in 0..combinations {
+                if !used.contains(&(noun.clone(), index)) {
+                    break;
+                }
+                index = (index + 1) % combinations;
+            }
+            if !used.insert((noun.clone(), index)) {
+                return err_unexpected("Too many unidentified items for the available appearances");
+            }
+
+            let (color_name, color) = &colors[index % colors.len()];
+            let adjective = &adjectives[index / colors.len()];
+            appearances.insert(
+                key.clone(),
+                Appearance {
+                    name: format!("{} {} {}", color_name, adjective, noun),
+                    color: *color,
+                },
+            );
+        }
+
+        Ok(ItemKnowledge { appearances })
+    }
+
+    /// Restores item knowledge from data written by `to_save_arr`.
+    pub fn from_save_arr(data: &Arr) -> GameResult<ItemKnowledge> {
+        let mut appearances = HashMap::new();
+
+        for appearance in data.iter() {
+            let appearance = appearance.get_obj()?;
+            appearances.insert(
+                appearance.get_str("key")?,
+                Appearance {
+                    name: appearance.get_str("name")?,
+                    color: Color::from_str(&appearance.get_str("color")?)?,
+                },
+            );
+        }
+
+        Ok(ItemKnowledge { appearances })
+    }
+
+    /// Returns the full state of the item knowledge for saving.
+    pub fn to_save_arr(&self) -> GameResult<Arr> {
+        let mut keys: Vec<&String> = self.appearances.keys().collect();
+        keys.sort();
+
+        let appearances = keys
+            .into_iter()
+            .map(|key| {
+                let appearance = &self.appearances[key];
+                Obj::from_map_unchecked(map! {
+                    "key".into() => key.clone().into(),
+                    "name".into() => appearance.name.clone().into(),
+                    "color".into() => appearance.color.to_code().into(),
+                })
+                .into()
+            })
+            .collect();
+
+        arr_from_vec(appearances)
+    }
+
+    /// Returns the appearance of the item with database key `key`, or `None` if it is identified.
+    pub fn appearance(&self, key: &str) -> Option<&Appearance> {
+        self.appearances.get(key)
+    }
+
+    pub fn is_identified(&self, key: &str) -> bool {
+        !self.appearances.contains_key(key)
+    }
+
+    /// Identifies the item with database key `key`. Returns false if it was already identified.
+    pub fn identify(&mut self, key: &str) -> bool {
+        self.appearances.remove(key).is_some()
+    }
+}
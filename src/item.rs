@@ -1,30 +1,398 @@
 //! Module containing Item structs.
+//!
+//! Items lying on a tile form an `ItemStash`, of which only the top-most item can be seen from
+//! afar. Identical items are merged into a single `ItemStack` holding their amount.
 
+use crate::actor::Actor;
 use crate::console::Color;
+use crate::defs::{big_to_u32, big_to_usize, bigr_to_f32, f32_to_bigr};
+use crate::equipment::{EquipSlot, Modifiers};
+use crate::error::err_convert;
+use crate::game_data::GameData;
+use crate::material::MaterialInfo;
+use crate::save::arr_from_vec;
 use crate::ui::Draw;
+use crate::{GameError, GameResult, DATABASE, GAMEDATA};
+use over::arr::Arr;
+use over::Obj;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Kinds of items.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemCategory {
+    Weapon,
+    Armor,
+    Potion,
+    Scroll,
+    Food,
+    Corpse,
+    Misc,
+}
+
+impl FromStr for ItemCategory {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use self::ItemCategory::*;
+
+        Ok(match s {
+            "weapon" => Weapon,
+            "armor" => Armor,
+            "potion" => Potion,
+            "scroll" => Scroll,
+            "food" => Food,
+            "corpse" => Corpse,
+            "misc" => Misc,
+            _ => {
+                return Err(GameError::ConversionError {
+                    val: s.into(),
+                    msg: "Invalid item category",
+                });
+            }
+        })
+    }
+}
+
+impl fmt::Display for ItemCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ItemCategory::*;
+
+        write!(
+            f,
+            "{}",
+            match *self {
+                Weapon => "weapon",
+                Armor => "armor",
+                Potion => "potion",
+                Scroll => "scroll",
+                Food => "food",
+                Corpse => "corpse",
+                Misc => "misc",
+            }
+        )
+    }
+}
+
+/// What happens when an item is used, e.g. by drinking a potion or reading a scroll.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemEffect {
+    None,
+    /// Restores up to `power` hit points.
+    Heal,
+    /// Deals `power` damage.
+    Harm,
+    /// Lets the user identify another item.
+    Identify,
+}
+
+impl FromStr for ItemEffect {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use self::ItemEffect::*;
+
+        Ok(match s {
+            "none" => None,
+            "heal" => Heal,
+            "harm" => Harm,
+            "identify" => Identify,
+            _ => {
+                return Err(GameError::ConversionError {
+                    val: s.into(),
+                    msg: "Invalid item effect",
+                });
+            }
+        })
+    }
+}
+
+impl fmt::Display for ItemEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ItemEffect::*;
+
+        write!(
+            f,
+            "{}",
+            match *self {
+                None => "none",
+                Heal => "heal",
+                Harm => "harm",
+                Identify => "identify",
+            }
+        )
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Item {
+    /// Key of this item in the database.
+    key: String,
+    name: String,
+    category: ItemCategory,
+    material: Arc<MaterialInfo>,
+    volume: f32, // cm^3
+
+    /// Where the item can be equipped, if anywhere.
+    slot: Option<EquipSlot>,
+    /// Stat changes granted while the item is equipped.
+    modifiers: Modifiers,
+    effect: ItemEffect,
+    /// Strength of the effect.
+    power: u32,
+
     c: char,
     color: Color,
 }
 
+impl Item {
+    /// Creates an item from its database entry.
+    pub fn new(key: &str, data: &Obj) -> GameResult<Item> {
+        Item::with_game_data(&GAMEDATA.read().unwrap(), key, data)
+    }
+
+    /// Creates an item from its database entry, looking up its material in `game_data`.
+    pub fn with_game_data(game_data: &GameData, key: &str, data: &Obj) -> GameResult<Item> {
+        let material = data.get_obj("material")?;
+        let material = game_data.material_info(material.id());
+
+        let color = if data.get("color").is_some() {
+            Color::from_str(&data.get_str("color")?)?
+        } else {
+            material.color
+        };
+
+        Ok(Item {
+            key: key.into(),
+            name: data.get_str("name")?,
+            category: ItemCategory::from_str(&data.get_str("category")?)?,
+            material,
+            volume: bigr_to_f32(data.get_frac("volume")?)?,
+
+            slot: get_slot(data)?,
+            modifiers: Modifiers::new(data)?,
+            effect: ItemEffect::from_str(&data.get_str("effect")?)?,
+            power: big_to_u32(data.get_int("power")?)?,
+
+            c: data.get_char("c")?,
+            color,
+        })
+    }
+
+    /// Creates the item with database key `key`.
+    pub fn from_key(key: &str) -> GameResult<Item> {
+        let data = DATABASE.read().unwrap().get_obj("items")?.get_obj(key)?;
+        Item::new(key, &data)
+    }
+
+    /// Creates the corpse left behind by a dead actor.
+    pub fn corpse(actor: &Actor) -> Item {
+        let mut corpse = GAMEDATA.read().unwrap().corpse().clone();
+        corpse.name = format!("{} corpse", actor.name());
+        corpse.color = actor.draw_color();
+
+        corpse
+    }
+
+    /// Returns the database key of this item.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the name of this item as known to the player. Unidentified items are named after
+    /// their appearance.
+    pub fn name(&self) -> String {
+        match GAMEDATA.read().unwrap().item_appearance(&self.key) {
+            Some(appearance) => appearance.name,
+            None => self.name.clone(),
+        }
+    }
+
+    /// Returns the name of this item, whether or not it is identified.
+    pub fn real_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_identified(&self) -> bool {
+        GAMEDATA.read().unwrap().is_item_identified(&self.key)
+    }
+
+    pub fn category(&self) -> ItemCategory {
+        self.category
+    }
+
+    pub fn material(&self) -> &MaterialInfo {
+        &self.material
+    }
+
+    /// Returns the slot this item can be equipped in, if any.
+    pub fn slot(&self) -> Option<EquipSlot> {
+        self.slot
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub fn effect(&self) -> ItemEffect {
+        self.effect
+    }
+
+    pub fn power(&self) -> u32 {
+        self.power
+    }
+
+    /// Returns the weight of this item in grams.
+    pub fn weight(&self) -> f32 {
+        self.volume * self.material.density
+    }
+
+    /// Restores an item from data written by `to_save_obj`.
+    pub fn from_save_obj(data: &Obj) -> GameResult<Item> {
+        let material_key = data.get_str("material")?;
+        let material = match GAMEDATA.read().unwrap().material_info_by_key(&material_key) {
+            Some(material) => material,
+            None => return err_convert(material_key, "Unknown material"),
+        };
+
+        Ok(Item {
+            key: data.get_str("key")?,
+            name: data.get_str("name")?,
+            category: ItemCategory::from_str(&data.get_str("category")?)?,
+            material,
+            volume: bigr_to_f32(data.get_frac("volume")?)?,
+
+            slot: get_slot(data)?,
+            modifiers: Modifiers::new(data)?,
+            effect: ItemEffect::from_str(&data.get_str("effect")?)?,
+            power: big_to_u32(data.get_int("power")?)?,
+
+            c: data.get_char("c")?,
+            color: Color::from_str(&data.get_str("color")?)?,
+        })
+    }
+
+    /// Returns the full state of this item for saving.
+    pub fn to_save_obj(&self) -> Obj {
+        let mut map = map! {
+            "key".into() => self.key.clone().into(),
+            "name".into() => self.name.clone().into(),
+            "category".into() => self.category.to_string().into(),
+            "material".into() => self.material.key.clone().into(),
+            "volume".into() => f32_to_bigr(self.volume).into(),
+
+            "attack".into() => self.modifiers.attack.into(),
+            "defense".into() => self.modifiers.defense.into(),
+            "damage".into() => self.modifiers.damage.into(),
+            "effect".into() => self.effect.to_string().into(),
+            "power".into() => self.power.into(),
+
+            "c".into() => self.c.into(),
+            "color".into() => self.color.to_code().into(),
+        };
+        if let Some(slot) = self.slot {
+            map.insert("slot".into(), slot.to_string().into());
+        }
+
+        Obj::from_map_unchecked(map)
+    }
+}
+
+// Returns the optional equipment slot in `data`, which may be inherited from a parent.
+fn get_slot(data: &Obj) -> GameResult<Option<EquipSlot>> {
+    if data.get("slot").is_some() {
+        Ok(Some(EquipSlot::from_str(&data.get_str("slot")?)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Items are equal if they are indistinguishable, in which case they can share a stack.
+impl PartialEq for Item {
+    fn eq(&self, other: &Item) -> bool {
+        self.key == other.key
+            && self.name == other.name
+            && self.category == other.category
+            && self.material.key == other.material.key
+            && self.volume == other.volume
+            && self.slot == other.slot
+            && self.modifiers == other.modifiers
+            && self.effect == other.effect
+            && self.power == other.power
+            && self.c == other.c
+            && self.color == other.color
+    }
+}
+
 impl Draw for Item {
     fn draw_c(&self) -> char {
         self.c
     }
 
+    /// Unidentified items are drawn in the color of their appearance.
     fn draw_color(&self) -> Color {
-        self.color
+        match GAMEDATA.read().unwrap().item_appearance(&self.key) {
+            Some(appearance) => appearance.color,
+            None => self.color,
+        }
     }
 }
 
+/// A number of identical items.
 #[derive(Clone, Debug)]
 pub struct ItemStack {
     item: Item,
     amount: usize,
 }
 
+impl ItemStack {
+    pub fn new(item: Item, amount: usize) -> ItemStack {
+        debug_assert!(amount > 0);
+        ItemStack { item, amount }
+    }
+
+    pub fn item(&self) -> &Item {
+        &self.item
+    }
+
+    pub fn amount(&self) -> usize {
+        self.amount
+    }
+
+    /// Returns the total weight of the stack in grams.
+    pub fn weight(&self) -> f32 {
+        self.item.weight() * self.amount as f32
+    }
+
+    /// Restores an item stack from data written by `to_save_obj`.
+    pub fn from_save_obj(data: &Obj) -> GameResult<ItemStack> {
+        Ok(ItemStack {
+            item: Item::from_save_obj(&data.get_obj("item")?)?,
+            amount: big_to_usize(data.get_int("amount")?)?,
+        })
+    }
+
+    /// Returns the full state of this item stack for saving.
+    pub fn to_save_obj(&self) -> Obj {
+        Obj::from_map_unchecked(map! {
+            "item".into() => self.item.to_save_obj().into(),
+            "amount".into() => self.amount.into(),
+        })
+    }
+}
+
+/// Displays the item name along with the amount, e.g. "dagger" or "dagger (x3)".
+impl fmt::Display for ItemStack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.amount == 1 {
+            write!(f, "{}", self.item.name())
+        } else {
+            write!(f, "{} (x{})", self.item.name(), self.amount)
+        }
+    }
+}
+
 impl Draw for ItemStack {
     fn draw_c(&self) -> char {
         self.item.draw_c()
@@ -35,12 +403,35 @@ impl Draw for ItemStack {
     }
 }
 
+/// An ordered collection of item stacks, such as the items on a tile or in an inventory.
+/// The last stack is on top.
 #[derive(Clone, Debug, Default)]
 pub struct ItemStash {
     items: Vec<ItemStack>,
 }
 
 impl ItemStash {
+    /// Restores an item stash from data written by `to_save_arr`.
+    pub fn from_save_arr(data: &Arr) -> GameResult<ItemStash> {
+        let mut stash = ItemStash::default();
+
+        for stack in data.iter() {
+            stash.add(ItemStack::from_save_obj(&stack.get_obj()?)?);
+        }
+
+        Ok(stash)
+    }
+
+    /// Returns the full state of this item stash for saving.
+    pub fn to_save_arr(&self) -> GameResult<Arr> {
+        arr_from_vec(
+            self.items
+                .iter()
+                .map(|stack| stack.to_save_obj().into())
+                .collect(),
+        )
+    }
+
     pub fn len(&self) -> usize {
         self.items.len()
     }
@@ -49,17 +440,58 @@ impl ItemStash {
         self.len() == 0
     }
 
-    pub fn add(&mut self, i: ItemStack) {
-        self.items.push(i);
+    /// Adds a stack on top of the stash. If the stash already holds identical items, the stack is
+    /// merged with them and the merged stack is moved to the top.
+    pub fn add(&mut self, stack: ItemStack) {
+        let merged = match self.items.iter().position(|other| other.item == stack.item) {
+            Some(index) => {
+                let mut other = self.items.remove(index);
+                other.amount += stack.amount;
+                other
+            }
+            None => stack,
+        };
+
+        self.items.push(merged);
     }
 
+    /// Removes and returns the stack at `index`.
     pub fn remove(&mut self, index: usize) -> ItemStack {
         self.items.remove(index)
     }
 
+    /// Removes `amount` items from the stack at `index` and returns them, splitting the stack if
+    /// necessary.
+    pub fn take(&mut self, index: usize, amount: usize) -> ItemStack {
+        let stack = &mut self.items[index];
+        debug_assert!(amount > 0 && amount <= stack.amount);
+
+        if amount >= stack.amount {
+            self.items.remove(index)
+        } else {
+            stack.amount -= amount;
+            ItemStack::new(stack.item.clone(), amount)
+        }
+    }
+
+    /// Returns the stack at `index`.
+    pub fn get(&self, index: usize) -> &ItemStack {
+        &self.items[index]
+    }
+
     pub fn top(&self) -> &ItemStack {
         &self.items[self.items.len() - 1]
     }
+
+    /// Returns an iterator over the stacks, from the bottom to the top.
+    pub fn iter(&self) -> impl Iterator<Item = &ItemStack> {
+        self.items.iter()
+    }
+
+    /// Returns the total weight of the stash in grams.
+    pub fn weight(&self) -> f32 {
+        self.items.iter().map(ItemStack::weight).sum()
+    }
 }
 
 impl Draw for ItemStash {
@@ -17,10 +17,12 @@ extern crate flame;
 extern crate failure;
 #[macro_use]
 extern crate lazy_static;
+extern crate libc;
 extern crate num;
 extern crate num_traits;
 extern crate over;
 extern crate rand;
+extern crate rand_pcg;
 extern crate tcod;
 
 // These modules are public so that they can be used in external integration tests.
@@ -29,18 +31,26 @@ extern crate tcod;
 pub mod util;
 
 pub mod actor;
+pub mod ai;
 pub mod console;
 pub mod coord;
 pub mod defs;
 pub mod dungeon;
+pub mod equipment;
 pub mod error;
+pub mod fov;
 pub mod game_data;
 pub mod generate;
+pub mod identify;
 pub mod item;
 pub mod material;
+pub mod message;
 pub mod name_gen;
 pub mod object;
+pub mod options;
+pub mod path;
 pub mod player;
+pub mod save;
 pub mod tile;
 pub mod ui;
 
@@ -52,6 +62,8 @@ use crate::console::DrawConsole;
 use crate::dungeon::{Dungeon, DungeonList};
 use crate::error::GameError;
 use crate::game_data::{GameData, GameLoopOutcome};
+use crate::identify::ItemKnowledge;
+use crate::options::GameOptions;
 use over::Obj;
 use std::sync::{Arc, Mutex, RwLock};
 
@@ -69,22 +81,35 @@ lazy_static! {
         RwLock::new(handle_error(GameData::new()))
     };
 
-    /// Drawing console.
+    /// Drawing console. Tests draw to memory so that they can run without a window.
     pub static ref CONSOLE: Mutex<DrawConsole> = {
         let game_data = GAMEDATA.read().unwrap();
-        Mutex::new(dev_time!(DrawConsole::new(&game_data.console_settings),
+        let settings = &game_data.console_settings;
+        Mutex::new(dev_time!(if cfg!(test) {
+                                 DrawConsole::new_memory(settings)
+                             } else {
+                                 handle_error(DrawConsole::new(settings))
+                             },
                              "Initializing draw console..."))
     };
 }
 
 /// Runs the main game loop.
-pub fn run_game() -> GameResult<()> {
+pub fn run_game(options: &GameOptions) -> GameResult<()> {
     // Load database.
     lazy_static::initialize(&DATABASE);
 
     // Load global game data.
     lazy_static::initialize(&GAMEDATA);
 
+    // Seed the random number generator before anything random happens.
+    if let Some(seed) = options.seed {
+        GAMEDATA.write().unwrap().set_seed(seed);
+    }
+    if let Some(frontend) = options.frontend {
+        GAMEDATA.write().unwrap().console_settings.frontend = frontend;
+    }
+
     // Initialize the console.
     lazy_static::initialize(&CONSOLE);
 
@@ -104,29 +129,47 @@ pub fn run_game() -> GameResult<()> {
         println!();
     }
 
-    // Initialize a brand new game.
-    let mut dungeon_list = init_new_game()?;
+    // Continue a saved game if there is one, otherwise initialize a brand new game.
+    let mut dungeon_list =
+        if save::save_exists() && ui::prompt_yes_no("A saved game was found. Continue it?") {
+            dev_time!(save::load_game()?, "Loading saved game...")
+        } else {
+            init_new_game()?
+        };
 
-    loop {
+    let farewell = loop {
         // Get the current dungeon from the list.
         let dungeon = dungeon_list.current_dungeon();
         debug_assert!(dungeon.num_actors() > 0);
 
         // Main game loop.
         match dungeon.run_loop() {
-            GameLoopOutcome::DepthChanged => {
-                unimplemented!(); // TODO
+            GameLoopOutcome::DepthChanged(direction) => {
+                dungeon_list.change_depth(direction)?;
+            }
+            GameLoopOutcome::FellDown => {
+                dungeon_list.fall_down()?;
             }
             GameLoopOutcome::WindowClosed => {
-                println!("\nWindow closed. Goodbye!");
-                break;
+                // Save the game so that the current run isn't lost.
+                save::save_game(&dungeon_list)?;
+                break "Window closed. Game saved. Goodbye!";
             }
             GameLoopOutcome::PlayerDead => {
-                unimplemented!(); // TODO
+                // Death is permanent, so there is no game left to continue.
+                save::delete_save()?;
+                ui::draw_game_over(dungeon);
+                break "You have died. Goodbye!";
+            }
+            GameLoopOutcome::SaveGame => {
+                save::save_game(&dungeon_list)?;
+                GAMEDATA
+                    .read()
+                    .unwrap()
+                    .add_message("Game saved.", constants::MESSAGE_COLOR);
             }
             GameLoopOutcome::QuitGame => {
-                println!("\nQuitting. Goodbye!");
-                break;
+                break "Quitting. Goodbye!";
             }
             GameLoopOutcome::NoActors => {
                 unreachable!();
@@ -135,7 +178,10 @@ pub fn run_game() -> GameResult<()> {
                 unimplemented!(); // TODO
             }
         }
-    }
+    };
+
+    console::restore_terminal();
+    println!("\n{}", farewell);
 
     Ok(())
 }
@@ -146,6 +192,10 @@ fn load_data() -> GameResult<Obj> {
 }
 
 fn init_new_game() -> GameResult<DungeonList> {
+    // Disguise unidentified items. This depends on the seed, so do it before anything else random.
+    let item_knowledge = ItemKnowledge::generate()?;
+    GAMEDATA.read().unwrap().set_item_knowledge(item_knowledge);
+
     // Generate game.
     let dungeon_list = dev_time!(DungeonList::new()?, "Generating game world...");
 
@@ -155,6 +205,8 @@ fn init_new_game() -> GameResult<DungeonList> {
 /// Process error if one is contained in `result`.
 pub fn handle_error<T>(result: Result<T, failure::Error>) -> T {
     if let Err(error) = result {
+        console::restore_terminal();
+
         // Handle errors.
         // Just display them for now.
         println!("------");
@@ -1,7 +1,7 @@
 //! Game materials.
 
 use crate::console::Color;
-use crate::defs::bigr_to_f32;
+use crate::defs::{bigr_to_f32, bigr_to_gamer, GameRatio};
 use crate::game_data::GameData;
 use crate::GameResult;
 use over::Obj;
@@ -9,27 +9,49 @@ use std::str::FromStr;
 
 #[derive(Debug)]
 pub struct MaterialInfo {
+    /// Key of this material in the database.
+    pub key: String,
     pub name: String,
     pub adjective: String,
 
     pub color: Color,
     pub density: f32, // g/cm^3
+
+    /// Does the material flow?
+    pub liquid: bool,
+    /// Chance each turn that a liquid spreads to lower ground.
+    pub flow: GameRatio,
 }
 
 impl MaterialInfo {
-    pub fn new(game_data: &GameData, material_data: &Obj) -> GameResult<MaterialInfo> {
+    pub fn new(game_data: &GameData, key: &str, material_data: &Obj) -> GameResult<MaterialInfo> {
         let name = material_data.get_str("name")?;
         let adjective = material_data.get_str("adjective")?;
 
         let color = Color::from_str(&material_data.get_str("color")?)?;
         let density = bigr_to_f32(material_data.get_frac("density")?)?;
 
+        let liquid = if material_data.get("liquid").is_some() {
+            material_data.get_bool("liquid")?
+        } else {
+            false
+        };
+        let flow = if material_data.get("flow").is_some() {
+            bigr_to_gamer(material_data.get_frac("flow")?)?
+        } else {
+            GameRatio::from_integer(0)
+        };
+
         Ok(MaterialInfo {
+            key: key.into(),
             name,
             adjective,
 
             color,
             density,
+
+            liquid,
+            flow,
         })
     }
 }
@@ -0,0 +1,64 @@
+//! Module for messages shown to the player.
+
+use crate::console::Color;
+use crate::defs::*;
+use crate::GameResult;
+use over::Obj;
+use std::fmt;
+use std::str::FromStr;
+
+/// A message in the message log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    text: String,
+    color: Color,
+    /// Game turn on which the message was logged.
+    turn: GameRatio,
+}
+
+impl Message {
+    pub fn new(text: &str, color: Color, turn: GameRatio) -> Message {
+        Message {
+            text: text.into(),
+            color,
+            turn,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn turn(&self) -> GameRatio {
+        self.turn
+    }
+
+    /// Restores a message from data written by `to_save_obj`.
+    pub fn from_save_obj(data: &Obj) -> GameResult<Message> {
+        Ok(Message {
+            text: data.get_str("text")?,
+            color: Color::from_str(&data.get_str("color")?)?,
+            turn: bigr_to_gamer(data.get_frac("turn")?)?,
+        })
+    }
+
+    /// Returns the full state of this message for saving.
+    pub fn to_save_obj(&self) -> Obj {
+        Obj::from_map_unchecked(map! {
+            "text".into() => self.text.clone().into(),
+            "color".into() => self.color.to_code().into(),
+            "turn".into() => gamer_to_bigr(self.turn).into(),
+        })
+    }
+}
+
+/// Displays the message with its turn stamp, e.g. "[12] You hit the rat."
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {}", self.turn.to_integer(), self.text)
+    }
+}
@@ -1,11 +1,15 @@
 //! Game objects.
 
+use crate::actor::{Actor, Behavior};
 use crate::console::Color;
+use crate::constants;
 use crate::coord::Coord;
 use crate::defs::*;
 use crate::dungeon::{ActResult, Dungeon};
+use crate::error::err_convert;
 use crate::game_data::GameData;
 use crate::material::MaterialInfo;
+use crate::save::{coord_to_value, get_coord};
 use crate::ui::Draw;
 use crate::util::rand;
 use crate::{GameError, GameResult, GAMEDATA};
@@ -14,6 +18,7 @@ use over::Obj;
 use std::cell::Cell;
 use std::cell::RefCell;
 use std::cmp::Ordering;
+use std::fmt;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
@@ -31,6 +36,17 @@ pub struct ObjectInner {
     speed: GameRatio,
 
     pub transparent: bool,
+
+    /// Attributes of traps, `None` for other objects.
+    trap: Option<TrapInfo>,
+    /// Attributes of doors, `None` for other objects.
+    door: Option<DoorInfo>,
+    /// Attributes of fountains, `None` for other objects.
+    fountain: Option<FountainInfo>,
+    /// Levers affect the objects that share their link.
+    link: Option<String>,
+    /// Hidden objects, such as undetected traps, are not drawn.
+    hidden: bool,
 }
 
 impl ObjectInner {
@@ -49,7 +65,39 @@ impl ObjectInner {
     }
 
     pub fn visible(&self) -> bool {
-        self.active || self.object_type != ObjectType::Door
+        match self.object_type {
+            ObjectType::Door => self.active,
+            ObjectType::Trap => !self.hidden,
+            ObjectType::Fountain | ObjectType::Lever => true,
+        }
+    }
+
+    pub fn hidden(&self) -> bool {
+        self.hidden
+    }
+
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden
+    }
+
+    pub fn trap(&self) -> Option<TrapInfo> {
+        self.trap
+    }
+
+    pub fn door(&self) -> Option<&DoorInfo> {
+        self.door.as_ref()
+    }
+
+    pub fn door_mut(&mut self) -> Option<&mut DoorInfo> {
+        self.door.as_mut()
+    }
+
+    pub fn fountain(&self) -> Option<FountainInfo> {
+        self.fountain
+    }
+
+    pub fn link(&self) -> Option<&str> {
+        self.link.as_deref()
     }
 
     pub fn active(&self) -> bool {
@@ -100,6 +148,15 @@ impl Object {
 
         let transparent = object_data.get_bool("transparent")?;
 
+        let (trap, mut door, fountain) = load_type_info(object_type, object_data)?;
+        if let Some(ref mut door) = door {
+            // Closed doors that aren't locked may be stuck.
+            let stuck_chance = bigr_to_gamer(object_data.get_frac("stuck_chance")?)?;
+            door.stuck = active && !door.locked && rand::chance(stuck_chance);
+        }
+        let link = get_link(object_data)?;
+        let hidden = object_data.get_bool("hidden")?;
+
         // Create the object instance.
 
         let mut object = Object {
@@ -116,6 +173,12 @@ impl Object {
                 speed,
 
                 transparent,
+
+                trap,
+                door,
+                fountain,
+                link,
+                hidden,
             })),
         };
         object.update_turn();
@@ -123,6 +186,100 @@ impl Object {
         Ok(object)
     }
 
+    /// Restores an object from data written by `to_save_obj`.
+    pub fn from_save_obj(data: &Obj) -> GameResult<Object> {
+        let object_type = ObjectType::from_str(&data.get_str("type")?)?;
+        let material_key = data.get_str("material")?;
+        let material = match GAMEDATA.read().unwrap().material_info_by_key(&material_key) {
+            Some(material) => material,
+            None => return err_convert(material_key, "Unknown material"),
+        };
+        let (trap, mut door, mut fountain) = load_type_info(object_type, data)?;
+        // Restore the state that changes during the game.
+        if let Some(ref mut door) = door {
+            door.stuck = data.get_bool("stuck")?;
+            door.broken = data.get_bool("broken")?;
+            door.open_turns = big_to_u32(data.get_int("open_turns")?)?;
+        }
+        if let Some(ref mut fountain) = fountain {
+            fountain.charges = big_to_u32(data.get_int("charges")?)?;
+        }
+
+        Ok(Object {
+            inner: Arc::new(Mutex::new(ObjectInner {
+                object_type,
+                material,
+                active: data.get_bool("active")?,
+
+                name: data.get_str("name")?,
+                c: data.get_char("c")?,
+
+                coord: get_coord(data, "coord")?,
+                turn: bigr_to_gamer(data.get_frac("turn")?)?,
+                speed: bigr_to_gamer(data.get_frac("speed")?)?,
+
+                transparent: data.get_bool("transparent")?,
+
+                trap,
+                door,
+                fountain,
+                link: get_link(data)?,
+                hidden: data.get_bool("hidden")?,
+            })),
+        })
+    }
+
+    /// Returns the full state of this object for saving.
+    pub fn to_save_obj(&self) -> Obj {
+        let inner = self.inner.lock().unwrap();
+
+        let mut map = map! {
+            "type".into() => inner.object_type.to_string().into(),
+            "material".into() => inner.material.key.clone().into(),
+            "active".into() => inner.active.into(),
+
+            "name".into() => inner.name.clone().into(),
+            "c".into() => inner.c.into(),
+
+            "coord".into() => coord_to_value(inner.coord),
+            "turn".into() => gamer_to_bigr(inner.turn).into(),
+            "speed".into() => gamer_to_bigr(inner.speed).into(),
+
+            "transparent".into() => inner.transparent.into(),
+
+            "hidden".into() => inner.hidden.into(),
+        };
+        if let Some(trap) = inner.trap {
+            map.insert("effect".into(), trap.effect.to_string().into());
+            map.insert("power".into(), trap.power.into());
+            map.insert("detect".into(), gamer_to_bigr(trap.detect).into());
+        }
+        if let Some(ref door) = inner.door {
+            map.insert("close_delay".into(), door.close_delay.into());
+            if let Some(ref key) = door.key {
+                map.insert("key".into(), key.clone().into());
+            }
+            map.insert("strength".into(), door.strength.into());
+            map.insert("open_cost".into(), gamer_to_bigr(door.open_cost).into());
+            map.insert("unlock_cost".into(), gamer_to_bigr(door.unlock_cost).into());
+            map.insert("force_cost".into(), gamer_to_bigr(door.force_cost).into());
+            map.insert("locked".into(), door.locked.into());
+            map.insert("stuck".into(), door.stuck.into());
+            map.insert("broken".into(), door.broken.into());
+            map.insert("open_turns".into(), door.open_turns.into());
+        }
+        if let Some(fountain) = inner.fountain {
+            map.insert("power".into(), fountain.power.into());
+            map.insert("charges".into(), fountain.charges.into());
+            map.insert("max_charges".into(), fountain.max_charges.into());
+        }
+        if let Some(ref link) = inner.link {
+            map.insert("link".into(), link.clone().into());
+        }
+
+        Obj::from_map_unchecked(map)
+    }
+
     pub fn insert_new(
         dungeon: &mut Dungeon,
         coord: Coord,
@@ -153,6 +310,11 @@ impl Object {
         self.inner.lock().unwrap().turn
     }
 
+    /// Sets this object's next turn value.
+    pub fn set_turn(&mut self, turn: GameRatio) {
+        self.inner.lock().unwrap().turn = turn;
+    }
+
     /// Updates this object's turn based on its speed.
     pub fn update_turn(&mut self) {
         let mut inner = self.inner.lock().unwrap();
@@ -165,18 +327,97 @@ impl Object {
         self.inner.lock().unwrap().speed
     }
 
+    pub fn object_type(&self) -> ObjectType {
+        self.inner.lock().unwrap().object_type
+    }
+
     pub fn passable(&self) -> bool {
         let inner = self.inner.lock().unwrap();
         match inner.object_type {
-            ObjectType::Door => inner.active, // For doors, active means open
+            ObjectType::Door => !inner.active, // For doors, active means closed
             ObjectType::Trap => true,
+            ObjectType::Fountain | ObjectType::Lever => false,
         }
     }
 
+    /// Returns true if an actor can make this object passable by opening it without a key.
+    pub fn openable(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        match inner.door {
+            Some(ref door) => inner.active && !door.locked,
+            None => false,
+        }
+    }
+
+    /// Returns true if this is a locked door.
+    pub fn locked(&self) -> bool {
+        match self.inner.lock().unwrap().door {
+            Some(ref door) => door.locked,
+            None => false,
+        }
+    }
+
+    /// Returns a copy of the door attributes if this object is a door.
+    pub fn door(&self) -> Option<DoorInfo> {
+        self.inner.lock().unwrap().door.clone()
+    }
+
+    /// Opens a door, unlocking and unsticking it if necessary. Open doors start counting down to
+    /// closing again.
+    pub fn open(&mut self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.active = false;
+        if let Some(ref mut door) = inner.door {
+            door.locked = false;
+            door.stuck = false;
+            door.open_turns = 0;
+        }
+    }
+
+    /// Closes a door, locking it too if `lock` is true. Broken doors can't be closed.
+    pub fn close(&mut self, lock: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.door {
+            Some(ref mut door) if door.broken => return,
+            Some(ref mut door) => door.locked = lock,
+            None => (),
+        }
+        inner.active = true;
+    }
+
+    /// Returns the fountain attributes if this object is a fountain.
+    pub fn fountain(&self) -> Option<FountainInfo> {
+        self.inner.lock().unwrap().fountain()
+    }
+
+    /// Returns the link shared by a lever and the objects it controls.
+    pub fn link(&self) -> Option<String> {
+        self.inner.lock().unwrap().link.clone()
+    }
+
+    pub fn set_link(&mut self, link: Option<String>) {
+        self.inner.lock().unwrap().link = link;
+    }
+
     pub fn visible(&self) -> bool {
         self.inner.lock().unwrap().visible()
     }
 
+    /// Returns true if this is a trap the player hasn't found yet.
+    pub fn hidden(&self) -> bool {
+        self.inner.lock().unwrap().hidden()
+    }
+
+    /// Returns the trap attributes if this object is a trap.
+    pub fn trap(&self) -> Option<TrapInfo> {
+        self.inner.lock().unwrap().trap()
+    }
+
+    /// Reveals a hidden object.
+    pub fn reveal(&mut self) {
+        self.inner.lock().unwrap().set_hidden(false);
+    }
+
     pub fn transparent(&self) -> bool {
         let inner = self.inner.lock().unwrap();
         inner.transparent || !inner.visible()
@@ -184,19 +425,278 @@ impl Object {
 
     /// Acts out the object's turn. Yes, objects can act, too.
     /// Could change itself or the dungeon as a side effect.
-    pub fn act(&mut self, dungeon: &Dungeon) -> ActResult {
-        let mut inner = self.inner.lock().unwrap();
-        // TODO: rework this
-        if !inner.active
-            && inner.object_type == ObjectType::Door
-            && dungeon[inner.coord()].actor.is_none()
-            && rand::dice(1, 2)
-        {
-            inner.active = true;
+    pub fn act(&mut self, dungeon: &mut Dungeon) -> ActResult {
+        match self.object_type() {
+            ObjectType::Door => self.door_act(dungeon),
+            ObjectType::Fountain => self.fountain_act(),
+            ObjectType::Trap | ObjectType::Lever => (),
         }
 
         ActResult::None
     }
+
+    /// Responds to `actor` bumping into this object, which is not passable. Returns true if the
+    /// actor uses up a turn.
+    pub fn interact(&mut self, actor: &mut Actor, dungeon: &mut Dungeon) -> (ActResult, bool) {
+        match self.object_type() {
+            ObjectType::Door => self.try_open(actor, dungeon),
+            ObjectType::Fountain => self.fountain_interact(actor, dungeon),
+            ObjectType::Lever => self.lever_interact(actor, dungeon),
+            ObjectType::Trap => (ActResult::None, false),
+        }
+    }
+
+    // Closes an auto-closing door once it has stayed open for its delay, unless something is in
+    // the doorway.
+    fn door_act(&mut self, dungeon: &mut Dungeon) {
+        let coord = {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.active {
+                return;
+            }
+            let door = match inner.door {
+                Some(ref mut door) if door.close_delay > 0 && !door.broken => door,
+                _ => return,
+            };
+
+            door.open_turns += 1;
+            if door.open_turns < door.close_delay {
+                return;
+            }
+            inner.coord
+        };
+
+        let tile = &dungeon[coord];
+        if tile.actor.is_some() || tile.item_stash.is_some() {
+            return;
+        }
+
+        self.close(false);
+        let text = format!("The {} swings shut.", self.name());
+        dungeon.add_message(&[coord], &text, constants::MESSAGE_COLOR);
+    }
+
+    /// Tries to open this door for `actor`. Locked doors need the right key, and stuck doors have
+    /// to be forced open, which may take several tries. Returns true if the actor uses up a turn.
+    pub fn try_open(&mut self, actor: &mut Actor, dungeon: &mut Dungeon) -> (ActResult, bool) {
+        let door = match self.door() {
+            Some(door) => door,
+            None => return (ActResult::None, false),
+        };
+        let coords = [actor.coord(), self.coord()];
+        let is_player = actor.behavior() == Behavior::Player;
+        let name = self.name();
+
+        if !self.inner.lock().unwrap().active {
+            if is_player {
+                let text = format!("The {} is already open.", name);
+                dungeon.add_message(&coords, &text, constants::MESSAGE_COLOR);
+            }
+            return (ActResult::None, false);
+        }
+
+        if door.locked {
+            let has_key = match door.key {
+                Some(ref key) => actor.has_item(key),
+                None => false,
+            };
+            if !has_key {
+                if is_player {
+                    let text = format!("The {} is locked.", name);
+                    dungeon.add_message(&coords, &text, constants::MESSAGE_COLOR);
+                }
+                return (ActResult::None, false);
+            }
+
+            let verb = actor.verb("unlock", "unlocks");
+            let text = format!("{} {} the {}.", actor.the_name(), verb, name);
+            dungeon.add_message(&coords, &text, constants::MESSAGE_COLOR);
+            actor.set_action_cost(door.unlock_cost);
+        } else if door.stuck {
+            actor.set_action_cost(door.force_cost);
+            if !force_succeeds(actor, door.strength) {
+                let verb = actor.verb("push", "pushes");
+                let text = format!("{} {} against the stuck {}.", actor.the_name(), verb, name);
+                dungeon.add_message(&coords, &text, constants::MESSAGE_COLOR);
+                return (ActResult::None, true);
+            }
+
+            let verb = actor.verb("force", "forces");
+            let text = format!("{} {} the {} open.", actor.the_name(), verb, name);
+            dungeon.add_message(&coords, &text, constants::MESSAGE_COLOR);
+        } else {
+            actor.set_action_cost(door.open_cost);
+        }
+
+        self.open();
+        (ActResult::None, true)
+    }
+
+    /// Tries to close this door for `actor`. Doors can't be closed on anything in the doorway.
+    /// Returns true if the actor uses up a turn.
+    pub fn try_close(&mut self, actor: &mut Actor, dungeon: &mut Dungeon) -> (ActResult, bool) {
+        let door = match self.door() {
+            Some(door) => door,
+            None => return (ActResult::None, false),
+        };
+        let coord = self.coord();
+        let is_player = actor.behavior() == Behavior::Player;
+
+        let problem = if self.inner.lock().unwrap().active {
+            Some(format!("The {} is already closed.", self.name()))
+        } else if door.broken {
+            Some(format!("The {} is broken.", self.name()))
+        } else if dungeon[coord].actor.is_some() || dungeon[coord].item_stash.is_some() {
+            Some("Something is in the way.".to_string())
+        } else {
+            None
+        };
+        if let Some(text) = problem {
+            if is_player {
+                dungeon.add_message(&[coord], &text, constants::MESSAGE_COLOR);
+            }
+            return (ActResult::None, false);
+        }
+
+        actor.set_action_cost(door.open_cost);
+        self.close(false);
+        (ActResult::None, true)
+    }
+
+    /// Lets `actor` kick this door, breaking it down for good if the kick is strong enough. Even
+    /// locked doors can be broken down. Returns true if the actor uses up a turn.
+    pub fn kick(&mut self, actor: &mut Actor, dungeon: &mut Dungeon) -> (ActResult, bool) {
+        let door = match self.door() {
+            Some(door) => door,
+            None => return (ActResult::None, false),
+        };
+        let coords = [actor.coord(), self.coord()];
+        let is_player = actor.behavior() == Behavior::Player;
+
+        if !self.inner.lock().unwrap().active {
+            if is_player {
+                let text = format!("The {} is already open.", self.name());
+                dungeon.add_message(&coords, &text, constants::MESSAGE_COLOR);
+            }
+            return (ActResult::None, false);
+        }
+
+        actor.set_action_cost(door.force_cost);
+        if !force_succeeds(actor, door.strength) {
+            dungeon.add_message(&coords, "WHAMM!", constants::MESSAGE_COLOR);
+            return (ActResult::None, true);
+        }
+
+        let verb = actor.verb("break", "breaks");
+        let text = format!("{} {} the {} down!", actor.the_name(), verb, self.name());
+        dungeon.add_message(&coords, &text, constants::MESSAGE_COLOR);
+
+        self.open();
+        if let Some(ref mut door) = self.inner.lock().unwrap().door {
+            door.broken = true;
+        }
+        (ActResult::None, true)
+    }
+
+    // Fountains slowly refill, one charge per turn.
+    fn fountain_act(&mut self) {
+        if let Some(ref mut fountain) = self.inner.lock().unwrap().fountain {
+            fountain.charges = (fountain.charges + 1).min(fountain.max_charges);
+        }
+    }
+
+    // Lets the actor drink from the fountain, healing it if the fountain isn't dry.
+    fn fountain_interact(&mut self, actor: &mut Actor, dungeon: &mut Dungeon) -> (ActResult, bool) {
+        let coords = [actor.coord(), self.coord()];
+        let subject = actor.the_name();
+        let verb = actor.verb("drink", "drinks");
+
+        let power = {
+            let mut inner = self.inner.lock().unwrap();
+            match inner.fountain {
+                Some(ref mut fountain) if fountain.charges > 0 => {
+                    fountain.charges -= 1;
+                    Some(fountain.power)
+                }
+                _ => None,
+            }
+        };
+
+        let text = match power {
+            Some(power) => {
+                actor.heal(power);
+                format!(
+                    "{} {} from the {}. It is refreshing!",
+                    subject,
+                    verb,
+                    self.name()
+                )
+            }
+            None => format!("The {} is dry.", self.name()),
+        };
+        dungeon.add_message(&coords, &text, constants::MESSAGE_COLOR);
+
+        (ActResult::None, power.is_some())
+    }
+
+    // Pulls the lever, which opens the linked doors if it was up and locks them if it was down.
+    fn lever_interact(&mut self, actor: &Actor, dungeon: &mut Dungeon) -> (ActResult, bool) {
+        let pulled = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.active = !inner.active;
+            inner.active
+        };
+
+        let verb = actor.verb("pull", "pulls");
+        let text = format!("{} {} the {}.", actor.the_name(), verb, self.name());
+        dungeon.add_message(&[self.coord()], &text, constants::MESSAGE_COLOR);
+
+        if let Some(link) = self.link() {
+            for mut object in dungeon.linked_objects(&link) {
+                if object.object_type() != ObjectType::Door {
+                    continue;
+                }
+
+                let coord = object.coord();
+                // Doors can't shut on whatever is in the doorway.
+                if pulled {
+                    object.open();
+                } else if dungeon[coord].actor.is_none() && dungeon[coord].item_stash.is_none() {
+                    object.close(true);
+                }
+            }
+        }
+
+        (ActResult::None, true)
+    }
+}
+
+// Rolls whether `actor` manages to force open or break down a door of the given strength.
+fn force_succeeds(actor: &Actor, strength: u32) -> bool {
+    let power = actor.damage().max(1);
+    rand::dice(power, power + strength)
+}
+
+// Loads the attributes specific to `object_type`.
+fn load_type_info(
+    object_type: ObjectType,
+    data: &Obj,
+) -> GameResult<(Option<TrapInfo>, Option<DoorInfo>, Option<FountainInfo>)> {
+    Ok(match object_type {
+        ObjectType::Trap => (Some(TrapInfo::new(data)?), None, None),
+        ObjectType::Door => (None, Some(DoorInfo::new(data)?), None),
+        ObjectType::Fountain => (None, None, Some(FountainInfo::new(data)?)),
+        ObjectType::Lever => (None, None, None),
+    })
+}
+
+// Loads the optional `link` field.
+fn get_link(data: &Obj) -> GameResult<Option<String>> {
+    Ok(if data.get("link").is_some() {
+        Some(data.get_str("link")?)
+    } else {
+        None
+    })
 }
 
 // Traits for priority queue
@@ -230,6 +730,9 @@ pub enum ObjectType {
     /// Door type. A value of `true` for `active` means the door is closed.
     Door,
     Trap,
+    Fountain,
+    /// Lever type. A value of `true` for `active` means the lever has been pulled.
+    Lever,
 }
 
 impl FromStr for ObjectType {
@@ -239,6 +742,8 @@ impl FromStr for ObjectType {
         Ok(match s {
             "door" => ObjectType::Door,
             "trap" => ObjectType::Trap,
+            "fountain" => ObjectType::Fountain,
+            "lever" => ObjectType::Lever,
             _ => {
                 return Err(GameError::ConversionError {
                     val: s.into(),
@@ -248,3 +753,164 @@ impl FromStr for ObjectType {
         })
     }
 }
+
+impl fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                ObjectType::Door => "door",
+                ObjectType::Trap => "trap",
+                ObjectType::Fountain => "fountain",
+                ObjectType::Lever => "lever",
+            }
+        )
+    }
+}
+
+/// What happens to an actor that steps on a trap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrapEffect {
+    /// Deals `power` damage.
+    Damage,
+    /// Moves the actor to a random location on the same depth.
+    Teleport,
+    /// Alerts every hostile actor on the depth to the trap's location.
+    Alarm,
+    /// Drops the actor to the next depth.
+    Pit,
+}
+
+impl FromStr for TrapEffect {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use self::TrapEffect::*;
+
+        Ok(match s {
+            "damage" => Damage,
+            "teleport" => Teleport,
+            "alarm" => Alarm,
+            "pit" => Pit,
+            _ => {
+                return Err(GameError::ConversionError {
+                    val: s.into(),
+                    msg: "Invalid trap effect.",
+                });
+            }
+        })
+    }
+}
+
+impl fmt::Display for TrapEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::TrapEffect::*;
+
+        write!(
+            f,
+            "{}",
+            match *self {
+                Damage => "damage",
+                Teleport => "teleport",
+                Alarm => "alarm",
+                Pit => "pit",
+            }
+        )
+    }
+}
+
+/// Attributes specific to traps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrapInfo {
+    pub effect: TrapEffect,
+    /// Strength of the effect, e.g. the damage dealt.
+    pub power: u32,
+    /// Chance of finding the trap when searching next to it.
+    pub detect: GameRatio,
+}
+
+impl TrapInfo {
+    pub fn new(data: &Obj) -> GameResult<TrapInfo> {
+        Ok(TrapInfo {
+            effect: TrapEffect::from_str(&data.get_str("effect")?)?,
+            power: big_to_u32(data.get_int("power")?)?,
+            detect: bigr_to_gamer(data.get_frac("detect")?)?,
+        })
+    }
+}
+
+/// Attributes specific to doors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DoorInfo {
+    /// Number of turns an open door stays open before swinging shut. Zero means it stays open.
+    pub close_delay: u32,
+    /// Database key of the item that unlocks the door, if any.
+    pub key: Option<String>,
+    /// How hard the door is to force open or break down.
+    pub strength: u32,
+
+    // Time taken by each interaction, as a multiple of the actor's speed.
+    /// Opening or closing the door.
+    pub open_cost: GameRatio,
+    /// Unlocking and opening the door with a key.
+    pub unlock_cost: GameRatio,
+    /// Forcing the door open or kicking it.
+    pub force_cost: GameRatio,
+
+    /// Locked doors can only be opened with a key, by a lever or by breaking them down.
+    pub locked: bool,
+    /// Stuck doors have to be forced open.
+    pub stuck: bool,
+    /// Broken doors stay open for good.
+    pub broken: bool,
+    /// Number of turns the door has been open.
+    pub open_turns: u32,
+}
+
+impl DoorInfo {
+    pub fn new(data: &Obj) -> GameResult<DoorInfo> {
+        let key = if data.get("key").is_some() {
+            Some(data.get_str("key")?)
+        } else {
+            None
+        };
+
+        Ok(DoorInfo {
+            close_delay: big_to_u32(data.get_int("close_delay")?)?,
+            key,
+            strength: big_to_u32(data.get_int("strength")?)?,
+
+            open_cost: bigr_to_gamer(data.get_frac("open_cost")?)?,
+            unlock_cost: bigr_to_gamer(data.get_frac("unlock_cost")?)?,
+            force_cost: bigr_to_gamer(data.get_frac("force_cost")?)?,
+
+            locked: data.get_bool("locked")?,
+            stuck: false,
+            broken: false,
+            open_turns: 0,
+        })
+    }
+}
+
+/// Attributes specific to fountains.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FountainInfo {
+    /// Hit points healed by a drink.
+    pub power: u32,
+    /// Drinks left. One charge is refilled every turn the fountain acts.
+    pub charges: u32,
+    pub max_charges: u32,
+}
+
+impl FountainInfo {
+    pub fn new(data: &Obj) -> GameResult<FountainInfo> {
+        let max_charges = big_to_u32(data.get_int("max_charges")?)?;
+
+        Ok(FountainInfo {
+            power: big_to_u32(data.get_int("power")?)?,
+            charges: max_charges,
+            max_charges,
+        })
+    }
+}
@@ -0,0 +1,90 @@
+//! Command-line options.
+
+use crate::console::Frontend;
+use crate::error::{err_argument, err_convert};
+use crate::GameResult;
+use std::str::FromStr;
+
+const USAGE: &str = "Usage: taurus [--seed <SEED>] [--frontend <tcod|terminal>]";
+
+/// Options passed to the game on startup.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameOptions {
+    /// Seed for the game's random number generator. A random seed is picked if `None`.
+    pub seed: Option<u64>,
+    /// Frontend to display the game in. The one in the settings is used if `None`.
+    pub frontend: Option<Frontend>,
+}
+
+impl GameOptions {
+    /// Parses options from a list of command-line arguments, not including the program name.
+    pub fn from_args<I>(args: I) -> GameResult<GameOptions>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut options = GameOptions::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = match args.next() {
+                        Some(value) => value,
+                        None => return err_argument(arg, "Missing value for seed"),
+                    };
+                    options.seed = Some(parse_seed(&value)?);
+                }
+                "--frontend" => {
+                    let value = match args.next() {
+                        Some(value) => value,
+                        None => return err_argument(arg, "Missing value for frontend"),
+                    };
+                    options.frontend = Some(Frontend::from_str(&value)?);
+                }
+                _ => return err_argument(arg, USAGE),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_seed(s: &str) -> GameResult<u64> {
+    match s.parse() {
+        Ok(seed) => Ok(seed),
+        Err(_) => err_convert(s, "Seed must be a non-negative integer"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_from_args() {
+        assert_eq!(GameOptions::from_args(args(&[])).unwrap().seed, None);
+        assert_eq!(
+            GameOptions::from_args(args(&["--seed", "42"]))
+                .unwrap()
+                .seed,
+            Some(42)
+        );
+
+        assert!(GameOptions::from_args(args(&["--seed"])).is_err());
+        assert!(GameOptions::from_args(args(&["--seed", "-1"])).is_err());
+        assert!(GameOptions::from_args(args(&["--bogus"])).is_err());
+
+        assert_eq!(
+            GameOptions::from_args(args(&["--frontend", "terminal", "--seed", "1"]))
+                .unwrap()
+                .frontend,
+            Some(Frontend::Terminal)
+        );
+        assert!(GameOptions::from_args(args(&["--frontend"])).is_err());
+        assert!(GameOptions::from_args(args(&["--frontend", "curses"])).is_err());
+    }
+}
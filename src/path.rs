@@ -0,0 +1,257 @@
+//! Pathfinding over the dungeon grid.
+//!
+//! Paths move in all eight directions, like actors do. Closed doors can be crossed at an extra cost
+//! since actors are able to open them, and traps at a much higher cost since actors avoid them.
+//! Hazards such as lava and chasms are never crossed.
+
+use crate::constants;
+use crate::coord::Coord;
+use crate::dungeon::Dungeon;
+use crate::util::direction::CompassDirection;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Returns the cost of stepping from `from` onto `to`, or `None` if `to` can't be entered.
+/// Hazards can't be entered, nor can tiles occupied by actors if `avoid_actors` is true. Climbing
+/// and wading cost extra, in proportion to how much longer they take than walking.
+pub fn step_cost(dungeon: &Dungeon, from: Coord, to: Coord, avoid_actors: bool) -> Option<u32> {
+    if !dungeon.in_bounds(to) {
+        return None;
+    }
+
+    let tile = &dungeon[to];
+    if !tile.passable() || tile.hazard() || (avoid_actors && tile.actor.is_some()) {
+        return None;
+    }
+
+    let cost = match tile.object {
+        Some(ref object) if object.openable() => constants::PATH_DOOR_COST,
+        Some(ref object) if object.trap().is_some() => constants::PATH_TRAP_COST,
+        Some(ref object) if !object.passable() => return None,
+        _ => constants::PATH_STEP_COST,
+    };
+    let terrain = (dungeon.move_cost(from, to) - 1) * constants::PATH_STEP_COST;
+
+    Some(cost + terrain.ceil().to_integer())
+}
+
+/// Finds the cheapest path from `start` to `goal` using A*.
+///
+/// The returned path excludes `start` and ends with `goal`. The goal may be occupied by an actor,
+/// such as the target of an attack, but any other actors block the way.
+pub fn find_path(dungeon: &Dungeon, start: Coord, goal: Coord) -> Option<Vec<Coord>> {
+    if !dungeon.in_bounds(start) || !dungeon.in_bounds(goal) {
+        return None;
+    }
+    if start == goal {
+        return Some(Vec::new());
+    }
+
+    let grid = GridIndex::new(dungeon);
+    let heuristic = |coord: Coord| coord.distance(goal) as u32 * constants::PATH_STEP_COST;
+
+    let mut costs: Vec<Option<u32>> = vec![None; grid.size()];
+    let mut came_from: Vec<Option<Coord>> = vec![None; grid.size()];
+    let mut open = BinaryHeap::new();
+
+    costs[grid.index(start)] = Some(0);
+    open.push(Reverse((heuristic(start), grid.index(start))));
+
+    while let Some(Reverse((estimate, index))) = open.pop() {
+        let coord = grid.coord(index);
+        let cost = costs[index].unwrap();
+
+        if coord == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+            while let Some(prev) = came_from[grid.index(current)] {
+                if prev == start {
+                    break;
+                }
+                path.push(prev);
+                current = prev;
+            }
+            path.reverse();
+
+            return Some(path);
+        }
+
+        // Skip stale entries for tiles that have since been reached more cheaply.
+        if estimate > cost + heuristic(coord) {
+            continue;
+        }
+
+        for dir in CompassDirection::all().iter() {
+            let next = coord.coord_in_dir(dir, 1);
+            let step = match step_cost(dungeon, coord, next, next != goal) {
+                Some(step) => step,
+                None => continue,
+            };
+
+            let next_index = grid.index(next);
+            let next_cost = cost + step;
+            if improves(costs[next_index], next_cost) {
+                costs[next_index] = Some(next_cost);
+                came_from[next_index] = Some(coord);
+                open.push(Reverse((next_cost + heuristic(next), next_index)));
+            }
+        }
+    }
+
+    None
+}
+
+/// A map of the cost of reaching the nearest of a set of goals from every tile.
+///
+/// Actors move by rolling "downhill" on the map. Since actors move around, they don't block the
+/// way on a map, which lets one map be shared by all actors heading for the same goals.
+#[derive(Clone, Debug)]
+pub struct DijkstraMap {
+    grid: GridIndex,
+    values: Vec<Option<i32>>,
+}
+
+impl DijkstraMap {
+    /// Creates a map leading towards the nearest of `goals`.
+    pub fn approach(dungeon: &Dungeon, goals: &[Coord]) -> DijkstraMap {
+        let seeds = goals
+            .iter()
+            .filter(|&&goal| dungeon.in_bounds(goal))
+            .map(|&goal| (goal, 0))
+            .collect();
+
+        Self::scan(dungeon, seeds)
+    }
+
+    /// Creates a map leading away from `threats`.
+    ///
+    /// Rather than simply inverting the approach map, which would lead into the nearest dead end,
+    /// the inverted values are scanned again so that actors prefer to flee past their threats
+    /// towards open areas.
+    pub fn flee(dungeon: &Dungeon, threats: &[Coord]) -> DijkstraMap {
+        let approach = Self::approach(dungeon, threats);
+
+        let seeds = approach
+            .values
+            .iter()
+            .enumerate()
+            .filter_map(|(index, value)| {
+                value.map(|value| {
+                    (
+                        approach.grid.coord(index),
+                        -value * constants::PATH_FLEE_PERCENT / 100,
+                    )
+                })
+            })
+            .collect();
+
+        Self::scan(dungeon, seeds)
+    }
+
+    // Fills in the map from seed tiles with known values.
+    fn scan(dungeon: &Dungeon, seeds: Vec<(Coord, i32)>) -> DijkstraMap {
+        let grid = GridIndex::new(dungeon);
+        let mut values: Vec<Option<i32>> = vec![None; grid.size()];
+        let mut open = BinaryHeap::new();
+
+        for (coord, value) in seeds {
+            let index = grid.index(coord);
+            if improves(values[index], value) {
+                values[index] = Some(value);
+                open.push(Reverse((value, index)));
+            }
+        }
+
+        while let Some(Reverse((value, index))) = open.pop() {
+            // Skip stale entries.
+            if values[index] != Some(value) {
+                continue;
+            }
+
+            let coord = grid.coord(index);
+            for dir in CompassDirection::all().iter() {
+                let next = coord.coord_in_dir(dir, 1);
+                let step = match step_cost(dungeon, coord, next, false) {
+                    Some(step) => step as i32,
+                    None => continue,
+                };
+
+                let next_index = grid.index(next);
+                let next_value = value + step;
+                if improves(values[next_index], next_value) {
+                    values[next_index] = Some(next_value);
+                    open.push(Reverse((next_value, next_index)));
+                }
+            }
+        }
+
+        DijkstraMap { grid, values }
+    }
+
+    /// Returns the value of the map at `coord`, or `None` if no goal can be reached from there.
+    pub fn value(&self, coord: Coord) -> Option<i32> {
+        if self.grid.in_bounds(coord) {
+            self.values[self.grid.index(coord)]
+        } else {
+            None
+        }
+    }
+
+    /// Returns the adjacent tile with the lowest value that an actor at `coord` could step onto, or
+    /// `None` if no tile is lower than `coord` itself.
+    pub fn downhill(&self, dungeon: &Dungeon, coord: Coord) -> Option<Coord> {
+        let current = self.value(coord);
+
+        CompassDirection::all()
+            .iter()
+            .map(|dir| coord.coord_in_dir(dir, 1))
+            .filter(|&next| step_cost(dungeon, coord, next, true).is_some())
+            .filter_map(|next| self.value(next).map(|value| (value, next)))
+            .filter(|&(value, _)| improves(current, value))
+            .min_by_key(|&(value, _)| value)
+            .map(|(_, next)| next)
+    }
+}
+
+// Returns true if `new` is lower than `old` or there is no `old` value yet.
+fn improves<T: Ord>(old: Option<T>, new: T) -> bool {
+    match old {
+        Some(old) => new < old,
+        None => true,
+    }
+}
+
+// Converts between coordinates and indices into a flat grid with the same layout as the dungeon.
+#[derive(Clone, Copy, Debug)]
+struct GridIndex {
+    width: usize,
+    height: usize,
+}
+
+impl GridIndex {
+    fn new(dungeon: &Dungeon) -> GridIndex {
+        GridIndex {
+            width: dungeon.width(),
+            height: dungeon.height(),
+        }
+    }
+
+    fn size(self) -> usize {
+        self.width * self.height
+    }
+
+    fn in_bounds(self, coord: Coord) -> bool {
+        coord.x >= 0
+            && coord.y >= 0
+            && (coord.x as usize) < self.width
+            && (coord.y as usize) < self.height
+    }
+
+    fn index(self, coord: Coord) -> usize {
+        coord.x as usize * self.height + coord.y as usize
+    }
+
+    fn coord(self, index: usize) -> Coord {
+        Coord::new((index / self.height) as i32, (index % self.height) as i32)
+    }
+}
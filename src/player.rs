@@ -5,12 +5,15 @@ use crate::console::KeyCode::*;
 use crate::console::*;
 use crate::constants;
 use crate::coord::Coord;
-use crate::dungeon::{ActResult, Dungeon};
+use crate::dungeon::{ActResult, DepthDirection, Dungeon};
+use crate::equipment::EquipSlot;
 use crate::game_data::GameData;
-use crate::ui;
+use crate::item::{ItemCategory, ItemEffect, ItemStack};
+use crate::ui::{self, ListEntry};
 use crate::util;
 use crate::util::direction::CompassDirection;
-use crate::{GameResult, CONSOLE};
+use crate::util::rand::chance;
+use crate::{GameResult, CONSOLE, GAMEDATA};
 #[cfg(feature = "dev")]
 use flame;
 use std::fs::File;
@@ -34,20 +37,23 @@ pub fn player_act(player: &mut Actor, dungeon: &mut Dungeon) -> ActResult {
 
         // Wait for user input.
 
-        let console = CONSOLE.lock().unwrap();
+        let (flags, event) = {
+            // Release the console before responding, since responses may draw to it.
+            let mut console = CONSOLE.lock().unwrap();
 
-        let (flags, event) = loop {
-            // Check if the window was closed by the user.
-            if console.window_closed() {
-                return ActResult::WindowClosed;
-            }
+            loop {
+                // Check if the window was closed by the user.
+                if console.window_closed() {
+                    return ActResult::WindowClosed;
+                }
 
-            if let Some((flags, event)) = console.check_for_event(input_flags) {
-                break (flags, event);
-            }
+                if let Some((flags, event)) = console.check_for_event(input_flags) {
+                    break (flags, event);
+                }
 
-            // Sleep a bit so we don't tax the CPU.
-            thread::sleep(time::Duration::from_millis(1));
+                // Sleep a bit so we don't tax the CPU.
+                thread::sleep(time::Duration::from_millis(1));
+            }
         };
 
         // Respond to user input.
@@ -79,13 +85,35 @@ pub fn player_process_event(
             if flags.contains(input::KEY_PRESS) {
                 #[allow(clippy::match_same_arms)]
                 match key.code {
-                    Left => return player.try_move_dir(dungeon, CompassDirection::W),
-                    Up => return player.try_move_dir(dungeon, CompassDirection::N),
-                    Right => return player.try_move_dir(dungeon, CompassDirection::E),
-                    Down => return player.try_move_dir(dungeon, CompassDirection::S),
+                    Left => return player_move(player, dungeon, CompassDirection::W),
+                    Up => return player_move(player, dungeon, CompassDirection::N),
+                    Right => return player_move(player, dungeon, CompassDirection::E),
+                    Down => return player_move(player, dungeon, CompassDirection::S),
 
                     Escape => return (ActResult::QuitGame, false),
 
+                    Char => match typed_char(key) {
+                        '>' => return player_take_stairs(player, dungeon, DepthDirection::Down),
+                        '<' => return player_take_stairs(player, dungeon, DepthDirection::Up),
+                        'S' => return (ActResult::SaveGame, false),
+                        'g' | ',' => return player_pick_up(player, dungeon),
+                        'd' => return player_drop(player, dungeon),
+                        'i' => ui::show_items("Inventory", &player.inventory()),
+                        'w' => return player_equip(player, dungeon, true),
+                        'W' => return player_equip(player, dungeon, false),
+                        'T' => return player_unequip(player, dungeon),
+                        'e' => ui::show_equipment(&player.equipment()),
+                        'q' => return player_use(player, dungeon, ItemCategory::Potion),
+                        'r' => return player_use(player, dungeon, ItemCategory::Scroll),
+                        'l' => player_look(player, dungeon),
+                        's' => return player_search(player, dungeon),
+                        'o' => return player_door(player, dungeon, DoorAction::Open),
+                        'c' => return player_door(player, dungeon, DoorAction::Close),
+                        'k' => return player_door(player, dungeon, DoorAction::Kick),
+                        'm' => ui::show_message_history(),
+                        _ => (),
+                    },
+
                     F1 => {
                         // Dump the profiler report to disk.
                         #[cfg(feature = "dev")]
@@ -137,13 +165,334 @@ pub fn player_process_event(
     (ActResult::None, false)
 }
 
+// Returns the character typed with `key`, taking the shift key into account.
+fn typed_char(key: Key) -> char {
+    if !key.shift {
+        return key.printable;
+    }
+
+    match key.printable {
+        '.' => '>',
+        ',' => '<',
+        c => c.to_ascii_uppercase(),
+    }
+}
+
+// Moves the player in `dir`, pointing out any items it steps onto. The player has to confirm
+// stepping onto a known trap.
+fn player_move(
+    player: &mut Actor,
+    dungeon: &mut Dungeon,
+    dir: CompassDirection,
+) -> (ActResult, bool) {
+    let coord = player.coord();
+    let target = coord.coord_in_dir(&dir, 1);
+
+    if dungeon.in_bounds(target) && dungeon[target].actor.is_none() {
+        if let Some(ref object) = dungeon[target].object {
+            if object.trap().is_some() && !object.hidden() {
+                let question = format!("Really step onto the {}?", object.name());
+                if !ui::prompt_yes_no(&question) {
+                    return (ActResult::None, false);
+                }
+            }
+        }
+    }
+
+    let result = player.try_move_dir(dungeon, dir);
+
+    if result.0 == ActResult::None && player.coord() != coord {
+        match dungeon[player.coord()].item_stash {
+            Some(ref stash) if stash.len() == 1 => {
+                add_message(&format!("You see here: {}.", stash.top()));
+            }
+            Some(_) => add_message("There are several items here."),
+            None => (),
+        }
+    }
+
+    result
+}
+
+// Picks up a stack of items from the player's tile, asking which one if there are several.
+// Returns true if the player uses up a turn.
+fn player_pick_up(player: &mut Actor, dungeon: &mut Dungeon) -> (ActResult, bool) {
+    let stash = match dungeon[player.coord()].item_stash {
+        Some(ref stash) => stash.clone(),
+        None => {
+            add_message("There is nothing here to pick up.");
+            return (ActResult::None, false);
+        }
+    };
+
+    let index = if stash.len() == 1 {
+        0
+    } else {
+        match ui::select_item("Pick up what?", &stash) {
+            Some(index) => index,
+            None => return (ActResult::None, false),
+        }
+    };
+
+    player.pick_up(dungeon, index, stash.get(index).amount());
+    (ActResult::None, true)
+}
+
+// Drops a stack of items chosen from the player's inventory.
+// Returns true if the player uses up a turn.
+fn player_drop(player: &mut Actor, dungeon: &mut Dungeon) -> (ActResult, bool) {
+    let inventory = player.inventory();
+    if inventory.is_empty() {
+        add_message("You are not carrying anything.");
+        return (ActResult::None, false);
+    }
+
+    match ui::select_item("Drop what?", &inventory) {
+        Some(index) => {
+            player.drop_item(dungeon, index, inventory.get(index).amount());
+            (ActResult::None, true)
+        }
+        None => (ActResult::None, false),
+    }
+}
+
+// Wields a weapon or wears armor chosen from the player's inventory.
+// Returns true if the player uses up a turn.
+fn player_equip(player: &mut Actor, dungeon: &mut Dungeon, wield: bool) -> (ActResult, bool) {
+    let inventory = player.inventory();
+    let choices: Vec<usize> = (0..inventory.len())
+        .filter(|index| match inventory.get(*index).item().slot() {
+            Some(slot) => slot.is_wielded() == wield,
+            None => false,
+        })
+        .collect();
+
+    if choices.is_empty() {
+        let noun = if wield { "weapons" } else { "armor" };
+        add_message(&format!("You have no {} to equip.", noun));
+        return (ActResult::None, false);
+    }
+
+    let entries: Vec<ListEntry> = choices
+        .iter()
+        .map(|index| ListEntry::from_stack(inventory.get(*index)))
+        .collect();
+    let title = if wield { "Wield what?" } else { "Wear what?" };
+
+    match ui::select_from_list(title, &entries) {
+        Some(choice) => {
+            player.equip(dungeon, choices[choice]);
+            (ActResult::None, true)
+        }
+        None => (ActResult::None, false),
+    }
+}
+
+// Takes off or puts away an equipped item.
+// Returns true if the player uses up a turn.
+fn player_unequip(player: &mut Actor, dungeon: &mut Dungeon) -> (ActResult, bool) {
+    let equipment = player.equipment();
+    if equipment.is_empty() {
+        add_message("You have nothing equipped.");
+        return (ActResult::None, false);
+    }
+
+    let (slots, entries): (Vec<EquipSlot>, Vec<ListEntry>) = equipment
+        .iter()
+        .map(|(slot, item)| {
+            let mut entry = ListEntry::from_stack(&ItemStack::new(item.clone(), 1));
+            entry.text = format!("{} ({})", entry.text, slot);
+            (slot, entry)
+        })
+        .unzip();
+
+    match ui::select_from_list("Remove what?", &entries) {
+        Some(choice) => {
+            player.unequip(dungeon, slots[choice]);
+            (ActResult::None, true)
+        }
+        None => (ActResult::None, false),
+    }
+}
+
+// Drinks a potion or reads a scroll from the player's inventory, depending on `category`.
+// Returns true if the player uses up a turn.
+fn player_use(
+    player: &mut Actor,
+    dungeon: &mut Dungeon,
+    category: ItemCategory,
+) -> (ActResult, bool) {
+    let inventory = player.inventory();
+    let choices: Vec<usize> = (0..inventory.len())
+        .filter(|index| inventory.get(*index).item().category() == category)
+        .collect();
+
+    let (noun, title) = match category {
+        ItemCategory::Potion => ("potions", "Drink what?"),
+        ItemCategory::Scroll => ("scrolls", "Read what?"),
+        _ => ("items", "Use what?"),
+    };
+    if choices.is_empty() {
+        add_message(&format!("You have no {}.", noun));
+        return (ActResult::None, false);
+    }
+
+    let entries: Vec<ListEntry> = choices
+        .iter()
+        .map(|index| ListEntry::from_stack(inventory.get(*index)))
+        .collect();
+    let index = match ui::select_from_list(title, &entries) {
+        Some(choice) => choices[choice],
+        None => return (ActResult::None, false),
+    };
+
+    let effect = inventory.get(index).item().effect();
+    let result = player.use_item(dungeon, index);
+
+    if result == ActResult::None && effect == ItemEffect::Identify {
+        player_identify(player);
+    }
+
+    (result, true)
+}
+
+// Lets the player identify an unidentified item in their inventory.
+fn player_identify(player: &Actor) {
+    let inventory = player.inventory();
+    let choices: Vec<usize> = (0..inventory.len())
+        .filter(|index| !inventory.get(*index).item().is_identified())
+        .collect();
+
+    if choices.is_empty() {
+        add_message("You have nothing left to identify.");
+        return;
+    }
+
+    let entries: Vec<ListEntry> = choices
+        .iter()
+        .map(|index| ListEntry::from_stack(inventory.get(*index)))
+        .collect();
+    // Identifying can't be put off until later, so keep asking.
+    let choice = loop {
+        if let Some(choice) = ui::select_from_list("Identify what?", &entries) {
+            break choice;
+        }
+        if CONSOLE.lock().unwrap().window_closed() {
+            return;
+        }
+    };
+
+    let item = inventory.get(choices[choice]).item();
+    let text = format!("The {} is a {}.", item.name(), item.real_name());
+    let game_data = GAMEDATA.read().unwrap();
+    game_data.identify_item(item.key());
+    game_data.add_message(&text, constants::MESSAGE_GOOD_COLOR);
+}
+
+// Examines the items on the player's tile.
+fn player_look(player: &Actor, dungeon: &Dungeon) {
+    match dungeon[player.coord()].item_stash {
+        Some(ref stash) => ui::show_items("Things that are here:", stash),
+        None => add_message("There is nothing here."),
+    }
+}
+
+// Adds a message meant for the player only.
+fn add_message(text: &str) {
+    GAMEDATA
+        .read()
+        .unwrap()
+        .add_message(text, constants::MESSAGE_COLOR);
+}
+
+// Things the player can do to a door.
+#[derive(Clone, Copy)]
+enum DoorAction {
+    Open,
+    Close,
+    Kick,
+}
+
+// Asks for a direction and opens, closes or kicks the door there. Returns true if the player uses
+// up a turn.
+fn player_door(player: &mut Actor, dungeon: &mut Dungeon, action: DoorAction) -> (ActResult, bool) {
+    let question = match action {
+        DoorAction::Open => "Open in which direction?",
+        DoorAction::Close => "Close in which direction?",
+        DoorAction::Kick => "Kick in which direction?",
+    };
+    let coord = match ui::prompt_direction(question) {
+        Some(dir) => player.coord().coord_in_dir(&dir, 1),
+        None => return (ActResult::None, false),
+    };
+
+    let mut door = match dungeon[coord].object {
+        Some(ref object) if object.door().is_some() => object.clone(),
+        _ => {
+            add_message("There is no door there.");
+            return (ActResult::None, false);
+        }
+    };
+
+    match action {
+        DoorAction::Open => door.try_open(player, dungeon),
+        DoorAction::Close => door.try_close(player, dungeon),
+        DoorAction::Kick => door.kick(player, dungeon),
+    }
+}
+
+// Searches the tiles around the player for hidden traps. Always uses up a turn.
+fn player_search(player: &Actor, dungeon: &mut Dungeon) -> (ActResult, bool) {
+    let mut found = false;
+
+    for dir in CompassDirection::all().iter() {
+        let coord = player.coord().coord_in_dir(dir, 1);
+        if !dungeon.in_bounds(coord) {
+            continue;
+        }
+
+        if let Some(ref mut object) = dungeon[coord].object {
+            match object.trap() {
+                Some(info) if object.hidden() && chance(info.detect) => {
+                    object.reveal();
+                    add_message(&format!("You find a {}!", object.name()));
+                    found = true;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    if !found {
+        add_message("You find nothing.");
+    }
+
+    (ActResult::None, true)
+}
+
+// Tries to take the staircase the player is standing on. Returns true if the player uses up a turn.
+fn player_take_stairs(
+    player: &Actor,
+    dungeon: &Dungeon,
+    direction: DepthDirection,
+) -> (ActResult, bool) {
+    if dungeon[player.coord()].staircase().leads(direction) {
+        (ActResult::DepthChanged(direction), true)
+    } else {
+        let message = match direction {
+            DepthDirection::Up => "There is no staircase leading up here.",
+            DepthDirection::Down => "There is no staircase leading down here.",
+        };
+        add_message(message);
+
+        (ActResult::None, false)
+    }
+}
+
 /// Calculates FOV around the player.
 /// This should be called whenever the player moves.
 /// Messages, for example, are only added if the player sees the event.
 pub fn calc_fov(player: &Actor, dungeon: &mut Dungeon) {
-    // TODO: Replace libtcod FOV algorithm.
-    // We only need to get FOV for a small section of the dungeon.
-
     let inner = player.inner.lock().unwrap();
     let origin = inner.coord;
     let fov_radius = inner.fov_radius as i32;
@@ -0,0 +1,204 @@
+//! Saving and loading games.
+//!
+//! Save files are written in the same `.over` format as the game database. Every save file
+//! records the version of the format it was written with, and loading a save from a different
+//! version is an error.
+
+use crate::constants;
+use crate::coord::Coord;
+use crate::defs::*;
+use crate::dungeon::DungeonList;
+use crate::error::{err_unexpected, GameError};
+use crate::player;
+use crate::{GameResult, GAMEDATA};
+use failure::ResultExt;
+use over::arr::Arr;
+use over::tup::Tup;
+use over::value::Value;
+use over::Obj;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Version of the save file format. Increment this whenever the format changes.
+pub const SAVE_VERSION: u32 = 13;
+
+/// Returns true if a saved game exists on disk.
+pub fn save_exists() -> bool {
+    Path::new(constants::SAVE_PATH).is_file()
+}
+
+/// Writes the current game to disk, overwriting any previous save.
+pub fn save_game(dungeon_list: &DungeonList) -> GameResult<()> {
+    let game_data = GAMEDATA.read().unwrap().to_save_obj()?;
+    let dungeon_list = dungeon_list.to_save_obj()?;
+
+    let save = Obj::from_map_unchecked(map! {
+        "version".into() => SAVE_VERSION.into(),
+        "game_data".into() => game_data.into(),
+        "dungeon_list".into() => dungeon_list.into(),
+    });
+
+    save.write_to_file(constants::SAVE_PATH)
+        .context(format!("Writing save file \"{}\"", constants::SAVE_PATH))?;
+
+    Ok(())
+}
+
+/// Loads the saved game from disk, restoring the global game data and returning the dungeons.
+pub fn load_game() -> GameResult<DungeonList> {
+    let save = Obj::from_file(constants::SAVE_PATH)
+        .context(format!("Reading save file \"{}\"", constants::SAVE_PATH))?;
+
+    let version = big_to_u32(save.get_int("version")?)?;
+    if version != SAVE_VERSION {
+        return Err(GameError::SaveVersionError {
+            found: version,
+            expected: SAVE_VERSION,
+        }
+        .into());
+    }
+
+    GAMEDATA
+        .write()
+        .unwrap()
+        .load_save_obj(&save.get_obj("game_data")?)
+        .context("Loading game data from save")?;
+
+    let mut dungeon_list = DungeonList::from_save_obj(&save.get_obj("dungeon_list")?)
+        .context("Loading dungeons from save")?;
+
+    // Restore the reference to the player.
+    let dungeon = dungeon_list.current_dungeon();
+    let player = match dungeon.find_player() {
+        Some(player) => player,
+        None => return err_unexpected("No player found in saved dungeon"),
+    };
+    GAMEDATA.write().unwrap().set_player(player.clone());
+    player::calc_fov(&player, dungeon);
+
+    Ok(dungeon_list)
+}
+
+/// Deletes the saved game, if there is one.
+pub fn delete_save() -> GameResult<()> {
+    if save_exists() {
+        fs::remove_file(constants::SAVE_PATH)?;
+    }
+
+    Ok(())
+}
+
+/// Creates an `Arr` from `vec`, which must contain values of the same type.
+pub fn arr_from_vec(vec: Vec<Value>) -> GameResult<Arr> {
+    Ok(Arr::from_vec(vec)?)
+}
+
+/// Converts a `Coord` into a `Value` for saving.
+pub fn coord_to_value(coord: Coord) -> Value {
+    Tup::from_vec(vec![coord.x.into(), coord.y.into()]).into()
+}
+
+/// Reads a `Coord` saved with `coord_to_value` from field `field` of `data`.
+pub fn get_coord(data: &Obj, field: &str) -> GameResult<Coord> {
+    let tup = data.get_tup(field)?;
+
+    Ok(Coord::new(
+        big_to_i32(tup.get(0)?.get_int()?)?,
+        big_to_i32(tup.get(1)?.get_int()?)?,
+    ))
+}
+
+/// Run-length encodes `values` into a compact string of the form "3*0,1*2".
+pub fn encode_rle<T>(values: &[T]) -> String
+where
+    T: Display + PartialEq,
+{
+    let mut runs: Vec<String> = Vec::new();
+    let mut iter = values.iter();
+
+    let mut current = match iter.next() {
+        Some(value) => value,
+        None => return String::new(),
+    };
+    let mut count = 1;
+
+    for value in iter {
+        if value == current {
+            count += 1;
+        } else {
+            runs.push(format!("{}*{}", count, current));
+            current = value;
+            count = 1;
+        }
+    }
+    runs.push(format!("{}*{}", count, current));
+
+    runs.join(",")
+}
+
+/// Decodes a string created by `encode_rle`.
+pub fn decode_rle<T>(s: &str) -> GameResult<Vec<T>>
+where
+    T: Clone + FromStr,
+{
+    let mut values = Vec::new();
+
+    if s.is_empty() {
+        return Ok(values);
+    }
+
+    for run in s.split(',') {
+        let mut parts = run.splitn(2, '*');
+        let (count, value) = match (parts.next(), parts.next()) {
+            (Some(count), Some(value)) => (count, value),
+            _ => return Err(rle_error(run)),
+        };
+        let count: usize = count.parse().map_err(|_| rle_error(run))?;
+        let value: T = value.parse().map_err(|_| rle_error(run))?;
+
+        let len = values.len();
+        values.resize(len + count, value);
+    }
+
+    Ok(values)
+}
+
+fn rle_error(run: &str) -> failure::Error {
+    GameError::ConversionError {
+        val: run.into(),
+        msg: "Invalid run-length encoded value",
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rle() {
+        let values = vec![0, 0, 0, 1, 2, 2, -1];
+        let encoded = encode_rle(&values);
+
+        assert_eq!(encoded, "3*0,1*1,2*2,1*-1");
+        assert_eq!(decode_rle::<i32>(&encoded).unwrap(), values);
+
+        let empty: Vec<usize> = Vec::new();
+        assert_eq!(encode_rle(&empty), "");
+        assert_eq!(decode_rle::<usize>("").unwrap(), empty);
+
+        assert!(decode_rle::<usize>("3").is_err());
+        assert!(decode_rle::<usize>("a*3").is_err());
+        assert!(decode_rle::<usize>("3*-1").is_err());
+    }
+
+    #[test]
+    fn test_coord_value() {
+        let coord = Coord::new(-3, 7);
+        let data = Obj::from_map_unchecked(map! { "coord".into() => coord_to_value(coord) });
+
+        assert_eq!(get_coord(&data, "coord").unwrap(), coord);
+    }
+}
@@ -1,9 +1,11 @@
 //! Actor tests.
 
-use crate::actor::Actor;
+use crate::actor::{Actor, Behavior};
 use crate::coord::Coord;
 use crate::defs::GameRatio;
+use crate::dungeon::ActResult;
 use crate::tests::common;
+use crate::util::direction::CompassDirection;
 use crate::DATABASE;
 use std::rc::Rc;
 
@@ -57,3 +59,91 @@ fn set_actor_coord_panic() {
     // Try setting to an occupied coordinate, inducing a panic.
     dungeon.move_actor(coord1, coord2);
 }
+
+// Test that bumping into a hostile actor attacks it until it dies and leaves a corpse.
+#[test]
+fn melee_kill() {
+    let _lock = common::lock_turn();
+    let mut dungeon = common::setup_dungeon().unwrap();
+    let data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+    let test = data.get_obj("test").unwrap();
+
+    let coord1 = Coord::new(1, 1);
+    let coord2 = Coord::new(1, 2);
+
+    let mut attacker = Actor::new(coord1, &test).unwrap();
+    attacker.set_behavior(Behavior::Player);
+    dungeon.add_actor(attacker.clone());
+    Actor::insert_new(&mut dungeon, coord2, &test).unwrap();
+
+    for _ in 0..1000 {
+        if dungeon[coord2].actor.is_none() {
+            break;
+        }
+
+        let (result, end_turn) = attacker.try_move_dir(&mut dungeon, CompassDirection::S);
+        assert_eq!(result, ActResult::None);
+        assert!(end_turn);
+    }
+
+    // The attacker stays put and the target's corpse is left behind.
+    assert!(dungeon[coord2].actor.is_none());
+    assert_eq!(attacker.coord(), coord1);
+    assert_eq!(dungeon.num_actors(), 1);
+    assert_eq!(dungeon.stash_size(coord2), 1);
+    match dungeon[coord2].item_stash {
+        Some(ref stash) => assert_eq!(stash.top().item().name(), "test corpse"),
+        None => panic!(),
+    }
+}
+
+// Test that killing the player ends the game.
+#[test]
+fn melee_player_dead() {
+    let _lock = common::lock_turn();
+    let mut dungeon = common::setup_dungeon().unwrap();
+    let data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+    let test = data.get_obj("test").unwrap();
+
+    let coord1 = Coord::new(1, 1);
+    let coord2 = Coord::new(2, 1);
+
+    let mut attacker = Actor::new(coord1, &test).unwrap();
+    dungeon.add_actor(attacker.clone());
+    let mut player = Actor::new(coord2, &test).unwrap();
+    player.set_behavior(Behavior::Player);
+    dungeon.add_actor(player.clone());
+
+    let mut result = ActResult::None;
+    for _ in 0..1000 {
+        result = attacker.try_move_dir(&mut dungeon, CompassDirection::E).0;
+        if result != ActResult::None {
+            break;
+        }
+    }
+
+    assert_eq!(result, ActResult::PlayerDead);
+    assert!(player.is_dead());
+    assert!(dungeon.find_player().is_none());
+}
+
+// Test that actors that aren't hostile to each other don't fight.
+#[test]
+fn melee_not_hostile() {
+    let _lock = common::lock_turn();
+    let mut dungeon = common::setup_dungeon().unwrap();
+    let data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+    let test = data.get_obj("test").unwrap();
+
+    let coord1 = Coord::new(1, 1);
+    let coord2 = Coord::new(1, 2);
+
+    let mut attacker = Actor::new(coord1, &test).unwrap();
+    dungeon.add_actor(attacker.clone());
+    Actor::insert_new(&mut dungeon, coord2, &test).unwrap();
+
+    let (result, end_turn) = attacker.try_move_dir(&mut dungeon, CompassDirection::S);
+    assert_eq!(result, ActResult::None);
+    assert!(!end_turn);
+    assert_eq!(dungeon[coord2].actor.as_ref().unwrap().hp(), 5);
+}
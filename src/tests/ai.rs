@@ -0,0 +1,179 @@
+//! AI tests.
+
+use crate::actor::{Actor, Behavior};
+use crate::coord::Coord;
+use crate::defs::GameRatio;
+use crate::dungeon::Dungeon;
+use crate::game_data::GameLoopOutcome;
+use crate::tests::common;
+use crate::DATABASE;
+
+// Returns a room with the player at `coord`. The player never gets a turn during the tests.
+fn room_with_player(coord: Coord) -> (Dungeon, Actor) {
+    let (dungeon, mut player) = common::room_with_player(coord);
+    // The player is alone in the actor queue, so its order is unaffected.
+    player.set_turn(GameRatio::from_integer(1_000_000));
+
+    (dungeon, player)
+}
+
+// Inserts a test actor with the given behavior at `coord`.
+fn insert_test(dungeon: &mut Dungeon, coord: Coord, behavior: Behavior) -> Actor {
+    let data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+
+    let mut actor = Actor::new(coord, &data.get_obj("test").unwrap()).unwrap();
+    actor.set_behavior(behavior);
+    dungeon.add_actor(actor.clone());
+
+    actor
+}
+
+// Runs `n` turns of the dungeon, stopping early if `done` returns true.
+fn run_turns<F>(dungeon: &mut Dungeon, n: usize, done: F)
+where
+    F: Fn() -> bool,
+{
+    let mut actor_turn = None;
+    let mut object_turn = None;
+
+    for _ in 0..n {
+        if done() {
+            return;
+        }
+        assert_eq!(
+            dungeon.step_turn(&mut actor_turn, &mut object_turn),
+            GameLoopOutcome::None
+        );
+    }
+}
+
+// Test that a hostile actor notices the player, hunts it down and attacks it.
+#[test]
+fn hostile_hunts_player() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, player) = room_with_player(Coord::new(12, 3));
+    let actor = insert_test(&mut dungeon, Coord::new(3, 3), Behavior::Hostile);
+
+    run_turns(&mut dungeon, 1, || false);
+    assert_eq!(actor.behavior(), Behavior::Hunting);
+    assert_eq!(actor.target(), Some(player.coord()));
+
+    run_turns(&mut dungeon, 8, || {
+        actor.coord().is_adjacent(player.coord())
+    });
+    assert!(actor.coord().is_adjacent(player.coord()));
+
+    run_turns(&mut dungeon, 1000, || player.hp() < player.hp_max() as i32);
+    assert!(player.hp() < player.hp_max() as i32);
+}
+
+// Test that a hostile actor with no player around stays hostile.
+#[test]
+fn hostile_wanders() {
+    let _lock = common::lock_turn();
+    let mut dungeon = common::setup_room().unwrap();
+    let actor = insert_test(&mut dungeon, Coord::new(1, 1), Behavior::Hostile);
+
+    run_turns(&mut dungeon, 20, || false);
+    assert_eq!(actor.behavior(), Behavior::Hostile);
+    assert_eq!(actor.target(), None);
+}
+
+// Test that wandering up a hill takes as long as climbing it on purpose.
+#[test]
+fn wander_climbs() {
+    let _lock = common::lock_turn();
+    let mut dungeon = common::setup_room().unwrap();
+    let start = Coord::new(1, 1);
+    let mut actor = insert_test(&mut dungeon, start, Behavior::Hostile);
+    for coord in &[Coord::new(2, 1), Coord::new(1, 2), Coord::new(2, 2)] {
+        dungeon[*coord].height = 1;
+    }
+
+    for _ in 0..100 {
+        let turn = actor.turn();
+        actor.act(&mut dungeon);
+        actor.update_turn();
+
+        if actor.coord() != start {
+            assert_eq!(actor.turn(), turn + actor.speed() * GameRatio::new(3, 2));
+            return;
+        }
+        assert_eq!(actor.turn(), turn + actor.speed());
+    }
+    panic!("The actor never wandered off");
+}
+
+// Test that a hunting actor that loses track of the player goes back to being hostile.
+#[test]
+fn hunting_gives_up() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, _player) = room_with_player(Coord::new(18, 18));
+    let mut actor = insert_test(&mut dungeon, Coord::new(1, 1), Behavior::Hunting);
+    actor.set_target(Some(Coord::new(3, 1)));
+
+    run_turns(&mut dungeon, 2, || false);
+    assert_eq!(actor.coord(), Coord::new(3, 1));
+    assert_eq!(actor.target(), None);
+
+    run_turns(&mut dungeon, 1, || false);
+    assert_eq!(actor.behavior(), Behavior::Hostile);
+}
+
+// Test that a hunting actor whose trail ends at an ally, which it can't move into, gives up.
+#[test]
+fn hunting_blocked_by_ally() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, _player) = room_with_player(Coord::new(18, 18));
+    let ally = insert_test(&mut dungeon, Coord::new(6, 5), Behavior::Defensive);
+    let mut actor = insert_test(&mut dungeon, Coord::new(5, 5), Behavior::Hunting);
+    actor.set_target(Some(ally.coord()));
+
+    run_turns(&mut dungeon, 2, || false);
+    assert_eq!(actor.coord(), Coord::new(5, 5));
+    assert_eq!(actor.target(), None);
+}
+
+// Test that a badly hurt actor flees from the player.
+#[test]
+fn hurt_actor_flees() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, player) = room_with_player(Coord::new(10, 10));
+    let mut actor = insert_test(&mut dungeon, Coord::new(8, 10), Behavior::Hunting);
+    actor.take_damage(4);
+
+    let distance = actor.coord().distance(player.coord());
+    run_turns(&mut dungeon, 1, || false);
+    assert_eq!(actor.behavior(), Behavior::Wary);
+    assert!(actor.coord().distance(player.coord()) > distance);
+}
+
+// Test that a defensive actor holds its ground until it is hurt.
+#[test]
+fn defensive_fights_back() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, player) = room_with_player(Coord::new(10, 10));
+    let mut actor = insert_test(&mut dungeon, Coord::new(7, 10), Behavior::Defensive);
+
+    run_turns(&mut dungeon, 10, || false);
+    assert_eq!(actor.behavior(), Behavior::Defensive);
+    assert_eq!(actor.coord(), Coord::new(7, 10));
+
+    actor.take_damage(1);
+    run_turns(&mut dungeon, 1, || false);
+    assert_eq!(actor.behavior(), Behavior::Hunting);
+    assert!(actor.coord().distance(player.coord()) < 3);
+}
+
+// Test that a friendly actor keeps close to the player without attacking it.
+#[test]
+fn friendly_follows_player() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, player) = room_with_player(Coord::new(15, 10));
+    let actor = insert_test(&mut dungeon, Coord::new(3, 10), Behavior::Friendly);
+
+    run_turns(&mut dungeon, 100, || false);
+    assert_eq!(actor.behavior(), Behavior::Friendly);
+    assert!(actor.coord().distance(player.coord()) <= 3);
+    assert_eq!(player.hp(), player.hp_max() as i32);
+}
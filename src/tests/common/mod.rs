@@ -1,9 +1,32 @@
+use crate::actor::Actor;
+use crate::coord::Coord;
+use crate::defs::GameRatio;
 use crate::dungeon::Dungeon;
 use crate::game_data::GameData;
 use crate::generate;
+use crate::player;
 use crate::GameResult;
-use crate::DATABASE;
+use crate::{DATABASE, GAMEDATA};
 use std::io;
+use std::sync::{Mutex, MutexGuard};
+
+lazy_static! {
+    static ref TURN_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Locks the global game turn for the duration of a test and resets it to zero.
+///
+/// Tests that run the turn queue, depend on the current turn, log messages, or use the global
+/// player, item knowledge or console must hold this, since tests run in parallel and share
+/// `GAMEDATA` and `CONSOLE`.
+pub fn lock_turn() -> MutexGuard<'static, ()> {
+    let lock = TURN_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    GAMEDATA
+        .write()
+        .unwrap()
+        .set_turn(GameRatio::from_integer(0));
+    lock
+}
 
 pub fn setup_dungeon() -> GameResult<Dungeon> {
     let profile = DATABASE
@@ -16,3 +39,40 @@ pub fn setup_dungeon() -> GameResult<Dungeon> {
 
     Ok(dungeon)
 }
+
+/// Returns the test dungeon with its inside covered in floor tiles.
+pub fn setup_room() -> GameResult<Dungeon> {
+    let mut dungeon = setup_dungeon()?;
+    let floor = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("tiles")?
+        .get_obj("stone_floor")?;
+
+    for x in 1..dungeon.width() as i32 - 1 {
+        for y in 1..dungeon.height() as i32 - 1 {
+            dungeon[Coord::new(x, y)].set_tile_info(&floor)?;
+        }
+    }
+
+    Ok(dungeon)
+}
+
+/// Inserts the player into `dungeon` at `coord` and computes their field of view.
+pub fn insert_player(dungeon: &mut Dungeon, coord: Coord) -> Actor {
+    let data = DATABASE.read().unwrap().get_obj("player").unwrap();
+
+    let player = Actor::new(coord, &data).unwrap();
+    dungeon.add_actor(player.clone());
+    player::calc_fov(&player, dungeon);
+
+    player
+}
+
+/// Returns the test room with the player at `coord`.
+pub fn room_with_player(coord: Coord) -> (Dungeon, Actor) {
+    let mut dungeon = setup_room().unwrap();
+    let player = insert_player(&mut dungeon, coord);
+
+    (dungeon, player)
+}
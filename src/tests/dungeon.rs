@@ -0,0 +1,758 @@
+//! Dungeon tests.
+
+use crate::actor::{Actor, Behavior};
+use crate::constants::DUNGEON_TRIES;
+use crate::coord::Coord;
+use crate::defs::GameRatio;
+use crate::dungeon::{ActResult, DepthDirection, Dungeon, DungeonList};
+use crate::error::GameError;
+use crate::generate::{
+    connect_dungeon, danger_table, gen_group, gen_loot, gen_monster, gen_overworld,
+    gen_valid_dungeon, is_connected, place_vault, Vault,
+};
+use crate::item::{Item, ItemStack};
+use crate::tests::common;
+use crate::DATABASE;
+use over::Obj;
+use std::collections::HashSet;
+
+// Returns a test dungeon with a room of floor tiles and a staircase at `stairs`.
+fn stairs_dungeon(stairs: Coord, stairs_tile: &str) -> Dungeon {
+    let mut dungeon = common::setup_dungeon().unwrap();
+    let tiles = DATABASE.read().unwrap().get_obj("tiles").unwrap();
+    let floor = tiles.get_obj("stone_floor").unwrap();
+
+    for x in 1..10 {
+        for y in 1..10 {
+            dungeon[Coord::new(x, y)].set_tile_info(&floor).unwrap();
+        }
+    }
+    dungeon[stairs]
+        .set_tile_info(&tiles.get_obj(stairs_tile).unwrap())
+        .unwrap();
+
+    dungeon
+}
+
+// Test moving the player and a follower between depths.
+#[test]
+fn change_depth() {
+    let test_data = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("actors")
+        .unwrap()
+        .get_obj("test")
+        .unwrap();
+
+    let down = Coord::new(2, 2);
+    let up = Coord::new(5, 5);
+    let follower = Coord::new(3, 2);
+
+    let mut dungeon0 = stairs_dungeon(down, "stone_stairs_down");
+    let dungeon1 = stairs_dungeon(up, "stone_stairs_up");
+    common::insert_player(&mut dungeon0, down);
+    Actor::insert_new(&mut dungeon0, follower, &test_data).unwrap();
+
+    assert_eq!(dungeon0.find_staircase(DepthDirection::Down), Some(down));
+    assert_eq!(dungeon1.find_staircase(DepthDirection::Up), Some(up));
+    assert_eq!(dungeon1.find_staircase(DepthDirection::Down), None);
+
+    let mut dungeon_list = DungeonList::from_dungeons(vec![dungeon0, dungeon1]);
+
+    // Can't go up from the first depth.
+    assert!(dungeon_list.change_depth(DepthDirection::Up).is_err());
+
+    dungeon_list.change_depth(DepthDirection::Down).unwrap();
+    assert_eq!(dungeon_list.current_depth, 1);
+    assert_eq!(dungeon_list[0].num_actors(), 0);
+    assert_eq!(dungeon_list[1].num_actors(), 2);
+
+    let player = dungeon_list[1].find_player().unwrap();
+    assert_eq!(player.coord(), up);
+    let followers = (-1..=1)
+        .flat_map(|dx| (-1..=1).map(move |dy| up + Coord::new(dx, dy)))
+        .filter(|coord| *coord != up && dungeon_list[1][coord].actor.is_some())
+        .count();
+    assert_eq!(followers, 1);
+
+    dungeon_list.change_depth(DepthDirection::Up).unwrap();
+    assert_eq!(dungeon_list.current_depth, 0);
+    assert_eq!(dungeon_list[0].find_player().unwrap().coord(), down);
+}
+
+// Test travelling between the overworld and the first depth through a dungeon entrance.
+#[test]
+fn overworld_travel() {
+    let entrance1 = Coord::new(2, 2);
+    let entrance2 = Coord::new(7, 7);
+    let up = Coord::new(5, 5);
+
+    let mut overworld = stairs_dungeon(entrance1, "dungeon_entrance");
+    let entrance_tile = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("tiles")
+        .unwrap()
+        .get_obj("dungeon_entrance")
+        .unwrap();
+    overworld[entrance2].set_tile_info(&entrance_tile).unwrap();
+    common::insert_player(&mut overworld, entrance2);
+
+    let mut dungeon_list = DungeonList::from_dungeons(vec![stairs_dungeon(up, "stone_stairs_up")]);
+    dungeon_list.overworld = Some(overworld);
+    dungeon_list.on_overworld = true;
+    assert!(dungeon_list.has_depth_below());
+
+    // There is nothing above the overworld.
+    assert!(dungeon_list.change_depth(DepthDirection::Up).is_err());
+
+    dungeon_list.change_depth(DepthDirection::Down).unwrap();
+    assert!(!dungeon_list.on_overworld);
+    assert_eq!(dungeon_list.current_depth, 0);
+    assert_eq!(
+        dungeon_list
+            .current_dungeon()
+            .find_player()
+            .unwrap()
+            .coord(),
+        up
+    );
+
+    // Coming back up leads out of the entrance the player went down.
+    dungeon_list.change_depth(DepthDirection::Up).unwrap();
+    assert!(dungeon_list.on_overworld);
+    assert_eq!(dungeon_list[0].num_actors(), 0);
+    assert_eq!(
+        dungeon_list
+            .current_dungeon()
+            .find_player()
+            .unwrap()
+            .coord(),
+        entrance2
+    );
+}
+
+// Checks that `dungeon` is walled in, that all of its open space is connected without crossing
+// hazards and that every door can be reached.
+fn assert_connected(dungeon: &Dungeon) {
+    let (width, height) = (dungeon.width() as i32, dungeon.height() as i32);
+    let open_tile = |coord: Coord| dungeon[coord].passable() && !dungeon[coord].hazard();
+
+    let mut open = Vec::new();
+    let mut doors = Vec::new();
+    for x in 0..width {
+        for y in 0..height {
+            let coord = Coord::new(x, y);
+            if dungeon[coord].passable() {
+                assert!(x > 0 && y > 0 && x < width - 1 && y < height - 1);
+            }
+            if open_tile(coord) {
+                open.push(coord);
+            }
+            if let Some(ref object) = dungeon[coord].object {
+                if object.door().is_some() {
+                    doors.push(coord);
+                }
+            }
+        }
+    }
+    assert!(!open.is_empty());
+
+    // Flood fill orthogonally from the first open tile.
+    let mut reached: HashSet<Coord> = HashSet::new();
+    let mut stack = vec![open[0]];
+    reached.insert(open[0]);
+    while let Some(coord) = stack.pop() {
+        for offset in &[(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let neighbor = coord + Coord::new(offset.0, offset.1);
+            if open_tile(neighbor) && reached.insert(neighbor) {
+                stack.push(neighbor);
+            }
+        }
+    }
+    assert_eq!(reached.len(), open.len());
+    for door in doors {
+        assert!(reached.contains(&door), "Unreachable door at {}", door);
+    }
+}
+
+// Generates dungeons from the profile `name`, checks that they are connected and returns the last
+// one.
+fn gen_connected(name: &str) -> Dungeon {
+    let profile = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("dungeon_profiles")
+        .unwrap()
+        .get_obj(name)
+        .unwrap();
+
+    let mut dungeon = Dungeon::new(0, &profile).unwrap();
+    for _ in 0..20 {
+        dungeon = Dungeon::new(0, &profile).unwrap();
+        assert_connected(&dungeon);
+    }
+
+    dungeon
+}
+
+// Test that generated caves are connected.
+#[test]
+fn cave_connected() {
+    gen_connected("cave");
+}
+
+// Test that BSP dungeons are connected and have no dead ends left.
+#[test]
+fn bsp_connected() {
+    let dungeon = gen_connected("bsp");
+
+    for x in 0..dungeon.width() as i32 {
+        for y in 0..dungeon.height() as i32 {
+            let coord = Coord::new(x, y);
+            if !dungeon[coord].passable() {
+                continue;
+            }
+
+            let open = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+                .iter()
+                .filter(|(dx, dy)| dungeon[coord + Coord::new(*dx, *dy)].passable())
+                .count();
+            assert!(open >= 2, "Dead end at {}", coord);
+        }
+    }
+}
+
+// Test stamping every vault into a level and joining it to the open space there.
+#[test]
+fn stamp_vaults() {
+    let database = DATABASE.read().unwrap();
+    let profile = database
+        .get_obj("dungeon_profiles")
+        .unwrap()
+        .get_obj("cave")
+        .unwrap();
+    let floor = database
+        .get_obj("tiles")
+        .unwrap()
+        .get_obj("stone_floor")
+        .unwrap();
+
+    for vault_data in database.get_obj("vaults").unwrap().values() {
+        let vault = Vault::new(&vault_data.get_obj().unwrap(), &profile).unwrap();
+
+        let mut dungeon = common::setup_dungeon().unwrap();
+        for x in 1..dungeon.width() as i32 - 1 {
+            dungeon[Coord::new(x, 18)].set_tile_info(&floor).unwrap();
+        }
+
+        let rect = place_vault(&mut dungeon, &vault, &[], 0).unwrap();
+        assert!(rect.is_some());
+        assert_connected(&dungeon);
+    }
+
+    // The armory's lever controls its door.
+    let vault_data = database
+        .get_obj("vaults")
+        .unwrap()
+        .get_obj("armory")
+        .unwrap();
+    let vault = Vault::new(&vault_data, &profile).unwrap();
+    let mut dungeon = common::setup_dungeon().unwrap();
+    vault.stamp(&mut dungeon, Coord::new(1, 1), 3).unwrap();
+    assert_eq!(dungeon.linked_objects("vault3:gate").len(), 2);
+    assert_eq!(dungeon.num_actors(), 1);
+    assert_eq!(dungeon.stash_size(Coord::new(2, 2)), 1);
+
+    // Vaults only appear within their danger levels.
+    let vault_data = database
+        .get_obj("vaults")
+        .unwrap()
+        .get_obj("rat_nest")
+        .unwrap();
+    let vault = Vault::new(&vault_data, &profile).unwrap();
+    assert!(!vault.allowed(0));
+    assert!(vault.allowed(1));
+    assert!(vault.allowed(12));
+    assert!(!vault.allowed(13));
+}
+
+// Test that disconnected levels are joined by a tunnel.
+#[test]
+fn connect_regions() {
+    let database = DATABASE.read().unwrap();
+    let profile = database
+        .get_obj("dungeon_profiles")
+        .unwrap()
+        .get_obj("cave")
+        .unwrap();
+    let floor = database
+        .get_obj("tiles")
+        .unwrap()
+        .get_obj("stone_floor")
+        .unwrap();
+
+    let mut dungeon = common::setup_dungeon().unwrap();
+    assert!(!is_connected(&dungeon));
+    assert!(!connect_dungeon(&mut dungeon, &profile).unwrap());
+
+    for y in 2..5 {
+        dungeon[Coord::new(2, y)].set_tile_info(&floor).unwrap();
+        dungeon[Coord::new(15, y + 10)]
+            .set_tile_info(&floor)
+            .unwrap();
+    }
+    dungeon[Coord::new(8, 8)].set_tile_info(&floor).unwrap();
+    assert!(!is_connected(&dungeon));
+
+    assert!(connect_dungeon(&mut dungeon, &profile).unwrap());
+    assert_connected(&dungeon);
+}
+
+// Generate thousands of levels from every profile and check that each one is fully connected and
+// has both of its staircases.
+#[test]
+fn generate_many_levels() {
+    let database = DATABASE.read().unwrap();
+    let profiles = database.get_obj("dungeon_profiles").unwrap();
+    let num_dungeons = database
+        .get_obj("dungeons")
+        .unwrap()
+        .get_arr("dungeons")
+        .unwrap()
+        .len() as u32;
+
+    for name in &["normal", "special", "bsp", "cave"] {
+        let profile = profiles.get_obj(name).unwrap();
+
+        for n in 0..500 {
+            let dungeon = gen_valid_dungeon(n % num_dungeons, &profile, true, true).unwrap();
+
+            assert_connected(&dungeon);
+            assert!(dungeon.find_staircase(DepthDirection::Up).is_some());
+            assert!(dungeon.find_staircase(DepthDirection::Down).is_some());
+            assert!(dungeon.num_actors() > 0);
+
+            let num_stashes = (0..dungeon.width() as i32)
+                .flat_map(|x| (0..dungeon.height() as i32).map(move |y| Coord::new(x, y)))
+                .filter(|coord| dungeon.stash_size(*coord) > 0)
+                .count();
+            assert!(num_stashes > 0);
+        }
+    }
+}
+
+// Test that a profile whose levels can never be connected gives up after `DUNGEON_TRIES` tries.
+#[test]
+fn generation_gives_up() {
+    let profile = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("dungeon_profiles")
+        .unwrap()
+        .get_obj("test")
+        .unwrap();
+
+    let err = gen_valid_dungeon(3, &profile, true, true).err().unwrap();
+    match err.downcast::<GameError>() {
+        Ok(GameError::GenerationError { depth, tries }) => {
+            assert_eq!(depth, 3);
+            assert_eq!(tries, DUNGEON_TRIES);
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+// Returns the feature with key `key`.
+fn feature(key: &str) -> Obj {
+    DATABASE
+        .read()
+        .unwrap()
+        .get_obj("tiles")
+        .unwrap()
+        .get_obj(key)
+        .unwrap()
+}
+
+// Test that caves get a heightmap within the profile's bounds, with pools filled up to the liquid
+// level and chasms only if there is a depth below.
+#[test]
+fn terrain_heights() {
+    let profile = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("dungeon_profiles")
+        .unwrap()
+        .get_obj("cave")
+        .unwrap();
+
+    for n in 0..20 {
+        let down = n % 2 == 0;
+        let dungeon = gen_valid_dungeon(4, &profile, true, down).unwrap();
+        let coords: Vec<Coord> = (0..dungeon.width() as i32)
+            .flat_map(|x| (0..dungeon.height() as i32).map(move |y| Coord::new(x, y)))
+            .filter(|coord| dungeon[*coord].passable())
+            .collect();
+
+        let heights: Vec<i32> = coords.iter().map(|coord| dungeon[*coord].height).collect();
+        assert!(heights.iter().all(|h| *h >= -5 && *h <= 3));
+        assert!(heights.iter().any(|h| *h != heights[0]));
+
+        for coord in coords {
+            match dungeon[coord].feature {
+                Some(ref feature) if feature.info.fall => assert!(down),
+                Some(ref feature) => {
+                    assert_eq!(feature.info.key, "water");
+                    assert_eq!(dungeon.surface(coord), -1);
+                }
+                None => assert!(dungeon[coord].height >= -1),
+            }
+        }
+    }
+}
+
+// Test the cost of climbing, wading and swimming.
+#[test]
+fn terrain_movement() {
+    let _lock = common::lock_turn();
+    let (a, b, c) = (Coord::new(2, 2), Coord::new(3, 2), Coord::new(4, 2));
+    let (mut dungeon, mut player) = common::room_with_player(a);
+
+    assert_eq!(dungeon.move_cost(a, b), GameRatio::from_integer(1));
+    dungeon[b].height = 1;
+    assert_eq!(dungeon.move_cost(a, b), GameRatio::new(3, 2));
+    assert_eq!(dungeon.move_cost(b, a), GameRatio::new(3, 2));
+    dungeon[b].height = -2;
+    assert_eq!(dungeon.move_cost(a, b), GameRatio::from_integer(2));
+
+    // Water fills the hole up to ground level, so there is no climbing, only swimming.
+    dungeon[b].set_feature(&feature("water"), 2).unwrap();
+    assert_eq!(dungeon.water_depth(b), 2);
+    assert_eq!(dungeon.water_depth(a), 0);
+    assert_eq!(dungeon.surface(b), 0);
+    assert_eq!(dungeon.move_cost(a, b), GameRatio::from_integer(2));
+    dungeon[c].height = -1;
+    dungeon[c].set_feature(&feature("water"), 1).unwrap();
+    assert_eq!(dungeon.move_cost(b, c), GameRatio::new(3, 2));
+
+    // Moving takes as long as the terrain makes it.
+    let turn = player.turn();
+    let (_, moved) = player.try_move_to(&mut dungeon, b);
+    assert!(moved);
+    player.update_turn();
+    assert_eq!(player.turn(), turn + player.speed() * 2);
+}
+
+// Test that lava burns, chasms drop actors to the next depth and actors go around both.
+#[test]
+fn terrain_hazards() {
+    let _lock = common::lock_turn();
+    let test_data = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("actors")
+        .unwrap()
+        .get_obj("test")
+        .unwrap();
+    let (start, lava, chasm) = (Coord::new(5, 5), Coord::new(6, 5), Coord::new(5, 6));
+    let (mut dungeon, mut player) = common::room_with_player(start);
+
+    dungeon[lava].set_feature(&feature("lava"), 1).unwrap();
+    dungeon[chasm].set_feature(&feature("chasm"), 0).unwrap();
+    assert!(dungeon[lava].hazard() && dungeon[chasm].hazard());
+    assert!(!dungeon.open_for_actor(lava) && !dungeon.open_for_spawn(chasm));
+
+    let hp = player.hp();
+    assert_eq!(
+        player.try_move_to(&mut dungeon, lava),
+        (ActResult::None, true)
+    );
+    assert_eq!(player.hp(), hp - 6);
+
+    player.move_to(&mut dungeon, start);
+    assert_eq!(
+        player.try_move_to(&mut dungeon, chasm),
+        (ActResult::FellDown, true)
+    );
+
+    // Other actors wait to land on the next depth.
+    dungeon.remove_actor(chasm);
+    let other = Coord::new(4, 6);
+    Actor::insert_new(&mut dungeon, other, &test_data).unwrap();
+    let mut actor = dungeon[other].actor.clone().unwrap();
+    assert_eq!(
+        actor.try_move_to(&mut dungeon, chasm),
+        (ActResult::None, true)
+    );
+    assert!(!dungeon.contains_actor(&actor));
+    assert_eq!(dungeon.take_fallen().len(), 1);
+}
+
+// Test that actors that fall into a chasm land on the next depth, along with their belongings,
+// once the player gets there.
+#[test]
+fn fallen_actor_lands_below() {
+    let _lock = common::lock_turn();
+    let test_data = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("actors")
+        .unwrap()
+        .get_obj("test")
+        .unwrap();
+
+    let (down, up) = (Coord::new(2, 2), Coord::new(5, 5));
+    let (other, chasm) = (Coord::new(7, 7), Coord::new(8, 8));
+    let mut dungeon0 = stairs_dungeon(down, "stone_stairs_down");
+    let dungeon1 = stairs_dungeon(up, "stone_stairs_up");
+    dungeon0[chasm].set_feature(&feature("chasm"), 0).unwrap();
+    common::insert_player(&mut dungeon0, down);
+    Actor::insert_new(&mut dungeon0, other, &test_data).unwrap();
+
+    let mut actor = dungeon0[other].actor.clone().unwrap();
+    actor.add_item(ItemStack::new(Item::from_key("dagger").unwrap(), 1));
+    actor.try_move_to(&mut dungeon0, chasm);
+    assert_eq!(dungeon0.num_actors(), 1);
+
+    let mut dungeon_list = DungeonList::from_dungeons(vec![dungeon0, dungeon1]);
+    dungeon_list.change_depth(DepthDirection::Down).unwrap();
+    assert_eq!(dungeon_list[1].num_actors(), 2);
+
+    let landed = (1..9)
+        .flat_map(|x| (1..9).map(move |y| Coord::new(x, y)))
+        .filter_map(|coord| dungeon_list[1][coord].actor.clone())
+        .find(|actor| actor.name() == "test")
+        .unwrap();
+    assert!(landed.has_item("dagger"));
+
+    // Nobody is left waiting to land after a round trip.
+    dungeon_list.change_depth(DepthDirection::Up).unwrap();
+    dungeon_list.change_depth(DepthDirection::Down).unwrap();
+    assert_eq!(dungeon_list[1].num_actors(), 2);
+}
+
+// Test that liquids flow downhill until they are level and pour away into chasms.
+#[test]
+fn liquid_flow() {
+    let mut dungeon = common::setup_room().unwrap();
+    let water = feature("water");
+    let (source, basin) = (Coord::new(5, 5), Coord::new(6, 5));
+
+    // A column of water next to a hole.
+    dungeon[source].set_feature(&water, 4).unwrap();
+    dungeon[basin].height = -2;
+    for _ in 0..200 {
+        dungeon.flow_liquids();
+    }
+
+    let total: u32 = (0..20)
+        .flat_map(|x| (0..20).map(move |y| Coord::new(x, y)))
+        .filter_map(|coord| dungeon[coord].feature.as_ref().map(|f| f.amount))
+        .sum();
+    assert_eq!(total, 4);
+    assert!(dungeon[basin].feature.is_some());
+    assert!(dungeon.surface(source) - dungeon.surface(basin) <= 1);
+
+    // Everything drains into a chasm next to the basin.
+    let chasm = Coord::new(7, 5);
+    dungeon[chasm].height = -6;
+    dungeon[chasm].set_feature(&feature("chasm"), 0).unwrap();
+    for _ in 0..500 {
+        dungeon.flow_liquids();
+    }
+    let wet = (0..20)
+        .flat_map(|x| (0..20).map(move |y| Coord::new(x, y)))
+        .filter(|coord| match dungeon[*coord].feature {
+            Some(ref feature) => feature.liquid(),
+            None => false,
+        })
+        .count();
+    assert!(wet <= 1);
+}
+
+// Test that liquids don't make up for the turns that passed while the player was away.
+#[test]
+fn liquid_catch_up() {
+    let mut dungeon = common::setup_room().unwrap();
+    let source = Coord::new(5, 5);
+    dungeon[source].set_feature(&feature("water"), 10).unwrap();
+
+    dungeon.update_liquids(GameRatio::from_integer(1));
+    let amount = dungeon[source].feature.as_ref().unwrap().amount;
+    assert!(amount >= 9);
+
+    // Liquids flow at most once on the turn the player comes back.
+    let turn = GameRatio::from_integer(10_000);
+    dungeon.catch_up(turn);
+    dungeon.update_liquids(turn);
+    assert!(dungeon[source].feature.as_ref().unwrap().amount >= amount - 1);
+}
+
+// Test that high ground blocks the view unless it is seen from above.
+#[test]
+fn terrain_fov() {
+    let mut dungeon = common::setup_room().unwrap();
+    let (eye, ridge, beyond) = (Coord::new(2, 5), Coord::new(5, 5), Coord::new(8, 5));
+
+    for y in 1..19 {
+        dungeon[Coord::new(5, y)].height = 2;
+    }
+    let fov = dungeon.compute_fov(eye, 10);
+    assert!(fov.is_visible(ridge));
+    assert!(!fov.is_visible(beyond));
+
+    dungeon[eye].height = 1;
+    let fov = dungeon.compute_fov(eye, 10);
+    assert!(fov.is_visible(beyond));
+
+    // Chasms don't block the view.
+    dungeon[eye].height = 0;
+    for y in 1..19 {
+        dungeon[Coord::new(5, y)].height = -6;
+        dungeon[Coord::new(5, y)]
+            .set_feature(&feature("chasm"), 0)
+            .unwrap();
+    }
+    assert!(dungeon.compute_fov(eye, 10).is_visible(beyond));
+}
+
+// Test picking spawn tables by danger level.
+#[test]
+fn spawn_tables() {
+    let database = DATABASE.read().unwrap();
+    let spawns = database
+        .get_obj("dungeon_profiles")
+        .unwrap()
+        .get_obj("normal")
+        .unwrap()
+        .get_arr("spawns")
+        .unwrap();
+    let first = |danger_level| {
+        let table = danger_table(&spawns, danger_level, "monsters")
+            .unwrap()
+            .unwrap();
+        let actor = table.get(0).unwrap().get_tup().unwrap();
+        actor
+            .get(0)
+            .unwrap()
+            .get_obj()
+            .unwrap()
+            .get_str("name")
+            .unwrap()
+    };
+
+    assert_eq!(first(0), "rat");
+    assert_eq!(first(2), "rat");
+    assert_eq!(first(8), "goblin");
+    assert_eq!(first(100), "wolf");
+}
+
+// Test spawning groups and packs of monsters close to their leader.
+#[test]
+fn spawn_groups() {
+    let database = DATABASE.read().unwrap();
+    let actors = database.get_obj("actors").unwrap();
+    let center = Coord::new(10, 10);
+
+    // Nowhere to spawn in a level of solid rock.
+    let dungeon = common::setup_dungeon().unwrap();
+    assert_eq!(dungeon.random_open_coord_actor(), None);
+
+    for _ in 0..20 {
+        let mut dungeon = common::setup_room().unwrap();
+        let num = gen_group(&mut dungeon, center, &actors.get_obj("wolf").unwrap()).unwrap();
+        assert!((2..=4).contains(&num));
+        assert_eq!(dungeon.num_actors(), num);
+
+        let mut dungeon = common::setup_room().unwrap();
+        let chief = actors.get_obj("goblin_chief").unwrap();
+        let num = gen_group(&mut dungeon, center, &chief).unwrap();
+        assert!((3..=5).contains(&num));
+        assert_eq!(
+            dungeon[center].actor.as_ref().unwrap().name(),
+            "goblin chief"
+        );
+
+        for x in 0..dungeon.width() as i32 {
+            for y in 0..dungeon.height() as i32 {
+                if dungeon[Coord::new(x, y)].actor.is_some() {
+                    assert!((x - center.x).abs() <= 4 && (y - center.y).abs() <= 4);
+                }
+            }
+        }
+    }
+}
+
+// Test that every item in the loot tables exists and that monsters leave their drops behind.
+#[test]
+fn loot_and_drops() {
+    let database = DATABASE.read().unwrap();
+    let profile = database
+        .get_obj("dungeon_profiles")
+        .unwrap()
+        .get_obj("normal")
+        .unwrap();
+
+    for field in &["loot", "vault_loot"] {
+        for danger_level in 0..30 {
+            let tables = profile.get_arr(field).unwrap();
+            let table = danger_table(&tables, danger_level, "items")
+                .unwrap()
+                .unwrap();
+            for tup in table.iter() {
+                let key = tup.get_tup().unwrap().get(0).unwrap().get_str().unwrap();
+                Item::from_key(&key).unwrap();
+            }
+            for _ in 0..10 {
+                assert_eq!(gen_loot(&table).unwrap().amount(), 1);
+            }
+        }
+    }
+
+    // The goblin chief always carries a drop.
+    let chief = database
+        .get_obj("actors")
+        .unwrap()
+        .get_obj("goblin_chief")
+        .unwrap();
+    let coord = Coord::new(5, 5);
+    let mut dungeon = common::setup_room().unwrap();
+    gen_monster(&mut dungeon, coord, &chief).unwrap();
+    assert_eq!(dungeon[coord].actor.as_ref().unwrap().inventory().len(), 1);
+
+    // Its drop, two pieces of equipment and its corpse are left behind.
+    dungeon.kill_actor(coord);
+    assert_eq!(dungeon.stash_size(coord), 4);
+}
+
+// Test that the overworld is connected, has dungeon entrances and towns of named townsfolk.
+#[test]
+fn overworld_generation() {
+    let profile = DATABASE.read().unwrap().get_obj("overworld").unwrap();
+
+    for _ in 0..10 {
+        let overworld = gen_overworld(&profile).unwrap();
+        assert_connected(&overworld);
+        assert!(overworld.find_staircase(DepthDirection::Down).is_some());
+        assert!(overworld.find_staircase(DepthDirection::Up).is_none());
+
+        let mut num_townsfolk = 0;
+        for x in 0..overworld.width() as i32 {
+            for y in 0..overworld.height() as i32 {
+                let actor = match overworld[Coord::new(x, y)].actor {
+                    Some(ref actor) => actor,
+                    None => continue,
+                };
+                if actor.behavior() == Behavior::Peaceful {
+                    let given_name = actor.given_name().unwrap();
+                    assert!(!given_name.is_empty());
+                    assert_eq!(actor.the_name(), given_name);
+                    assert!(!actor.follows_player());
+                    num_townsfolk += 1;
+                }
+            }
+        }
+        assert!(num_townsfolk >= 3);
+    }
+}
@@ -0,0 +1,260 @@
+//! Item tests.
+
+use crate::actor::Actor;
+use crate::coord::Coord;
+use crate::defs::big_to_u32;
+use crate::dungeon::ActResult;
+use crate::equipment::EquipSlot;
+use crate::identify::ItemKnowledge;
+use crate::item::{Item, ItemStack, ItemStash};
+use crate::player;
+use crate::tests::common;
+use crate::ui::Draw;
+use crate::{DATABASE, GAMEDATA};
+
+// Test that weight is derived from volume and material density.
+#[test]
+fn weight() {
+    let dagger = Item::from_key("dagger").unwrap();
+    let weight = 40.0 * dagger.material().density;
+
+    assert!((dagger.weight() - weight).abs() < 0.001);
+    assert!((ItemStack::new(dagger, 3).weight() - weight * 3.0).abs() < 0.001);
+}
+
+// Test that identical items merge into one stack, which moves to the top of the stash.
+#[test]
+fn stacking() {
+    let dagger = Item::from_key("dagger").unwrap();
+    let club = Item::from_key("club").unwrap();
+    let mut stash = ItemStash::default();
+
+    stash.add(ItemStack::new(dagger.clone(), 1));
+    stash.add(ItemStack::new(club.clone(), 1));
+    assert_eq!(stash.top().item().key(), "club");
+
+    stash.add(ItemStack::new(dagger.clone(), 2));
+    assert_eq!(stash.len(), 2);
+    assert_eq!(stash.top().item().key(), "dagger");
+    assert_eq!(stash.top().amount(), 3);
+    assert_eq!(stash.top().to_string(), "dagger (x3)");
+
+    // Taking part of a stack splits it.
+    let taken = stash.take(1, 2);
+    assert_eq!(taken.amount(), 2);
+    assert_eq!(stash.top().amount(), 1);
+
+    // Taking all of a stack removes it.
+    let taken = stash.take(0, 1);
+    assert_eq!(taken.item().key(), "club");
+    assert_eq!(stash.len(), 1);
+}
+
+// Test that items which differ in any way don't stack.
+#[test]
+fn stacking_corpses() {
+    let actor_data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+    let coord = Coord::new(1, 1);
+
+    let test = Actor::new(coord, &actor_data.get_obj("test").unwrap()).unwrap();
+    let (_, player) = common::room_with_player(coord);
+
+    let mut stash = ItemStash::default();
+    stash.add(ItemStack::new(Item::corpse(&test), 1));
+    stash.add(ItemStack::new(Item::corpse(&test), 1));
+    stash.add(ItemStack::new(Item::corpse(&player), 1));
+
+    assert_eq!(stash.len(), 2);
+    assert_eq!(stash.get(0).amount(), 2);
+}
+
+// Test that a dying actor leaves its corpse on top of its belongings and equipment.
+#[test]
+fn kill_actor_drops_items() {
+    let _lock = common::lock_turn();
+    let mut dungeon = common::setup_room().unwrap();
+    let data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+    let coord = Coord::new(3, 3);
+
+    Actor::insert_new(&mut dungeon, coord, &data.get_obj("test").unwrap()).unwrap();
+    {
+        let actor = dungeon[coord].actor.as_ref().unwrap();
+        let mut inner = actor.inner.lock().unwrap();
+        inner
+            .inventory
+            .add(ItemStack::new(Item::from_key("ration").unwrap(), 2));
+        inner.equipment.equip(Item::from_key("helmet").unwrap());
+    }
+
+    dungeon.kill_actor(coord);
+
+    assert!(dungeon[coord].actor.is_none());
+    let stash = dungeon[coord].item_stash.as_ref().unwrap();
+    assert_eq!(stash.len(), 3);
+    assert_eq!(stash.get(0).to_string(), "food ration (x2)");
+    assert_eq!(stash.get(1).to_string(), "helmet");
+    assert_eq!(stash.top().to_string(), "test corpse");
+}
+
+// Test that equipment modifies combat stats, and that equipping swaps out the previous item.
+#[test]
+fn equipment_modifiers() {
+    let _lock = common::lock_turn();
+    let mut dungeon = common::setup_room().unwrap();
+    let data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+    let coord = Coord::new(3, 3);
+
+    let mut actor = Actor::new(coord, &data.get_obj("test").unwrap()).unwrap();
+    dungeon.add_actor(actor.clone());
+    {
+        let mut inner = actor.inner.lock().unwrap();
+        inner
+            .inventory
+            .add(ItemStack::new(Item::from_key("hammer").unwrap(), 1));
+        inner
+            .inventory
+            .add(ItemStack::new(Item::from_key("dagger").unwrap(), 2));
+        inner
+            .inventory
+            .add(ItemStack::new(Item::from_key("wooden_shield").unwrap(), 1));
+    }
+    assert_eq!((actor.attack(), actor.defense(), actor.damage()), (0, 0, 1));
+
+    // Stats can't drop below zero.
+    actor.equip(&mut dungeon, 0);
+    assert_eq!(
+        actor.equipment().get(EquipSlot::Weapon).unwrap().key(),
+        "hammer"
+    );
+    assert_eq!((actor.attack(), actor.damage()), (0, 6));
+
+    // Only one dagger is taken from the stack, and the hammer goes back into the inventory.
+    actor.equip(&mut dungeon, 0);
+    assert_eq!(
+        actor.equipment().get(EquipSlot::Weapon).unwrap().key(),
+        "dagger"
+    );
+    let inventory = actor.inventory();
+    assert_eq!(inventory.top().item().key(), "hammer");
+    assert_eq!(inventory.get(0).to_string(), "dagger");
+
+    actor.equip(&mut dungeon, 1);
+    assert_eq!(actor.equipment().len(), 2);
+    assert_eq!((actor.attack(), actor.defense(), actor.damage()), (0, 2, 3));
+
+    actor.unequip(&mut dungeon, EquipSlot::Shield);
+    assert_eq!((actor.attack(), actor.defense()), (1, 0));
+    assert_eq!(actor.inventory().len(), 3);
+}
+
+// Test that actors can start with equipment given in the database.
+#[test]
+fn starting_equipment() {
+    let data = DATABASE.read().unwrap().get_obj("player").unwrap();
+    let (_, player) = common::room_with_player(Coord::new(1, 1));
+
+    let equipment = player.equipment();
+    assert_eq!(equipment.get(EquipSlot::Weapon).unwrap().key(), "dagger");
+    assert_eq!(
+        equipment.get(EquipSlot::Body).unwrap().key(),
+        "leather_armor"
+    );
+    assert!(player.inventory().is_empty());
+
+    let modifiers = equipment.modifiers();
+    assert_eq!(
+        player.damage(),
+        big_to_u32(data.get_int("damage").unwrap()).unwrap() + modifiers.damage as u32
+    );
+    assert_eq!(modifiers.to_string(), "+1 attack, +2 defense, +2 damage");
+}
+
+// Test that unidentified items get unique per-game appearances until they are identified.
+#[test]
+fn identification() {
+    let _lock = common::lock_turn();
+    GAMEDATA.write().unwrap().set_seed(1);
+    let knowledge = ItemKnowledge::generate().unwrap();
+
+    // The same seed gives the same appearances.
+    GAMEDATA.write().unwrap().set_seed(1);
+    let regenerated = ItemKnowledge::generate().unwrap();
+    assert_eq!(
+        knowledge.appearance("potion_poison"),
+        regenerated.appearance("potion_poison")
+    );
+
+    // Common items need no identification.
+    assert!(knowledge.is_identified("potion_healing"));
+    assert!(knowledge.is_identified("dagger"));
+    let poison = knowledge.appearance("potion_poison").unwrap();
+    let water = knowledge.appearance("potion_water").unwrap();
+    assert!(poison.name.ends_with(" potion"));
+    assert_ne!(poison.name, water.name);
+    assert!(knowledge
+        .appearance("scroll_identify")
+        .unwrap()
+        .name
+        .ends_with(" scroll"));
+
+    let saved = knowledge.to_save_arr().unwrap();
+    let loaded = ItemKnowledge::from_save_arr(&saved).unwrap();
+    assert_eq!(loaded.appearance("potion_poison"), Some(poison));
+
+    let poison = poison.clone();
+    GAMEDATA.read().unwrap().set_item_knowledge(knowledge);
+    let item = Item::from_key("potion_poison").unwrap();
+    assert_eq!(item.name(), poison.name);
+    assert_eq!(item.draw_color(), poison.color);
+    assert_eq!(item.real_name(), "potion of poison");
+
+    assert!(GAMEDATA.read().unwrap().identify_item("potion_poison"));
+    assert!(!GAMEDATA.read().unwrap().identify_item("potion_poison"));
+    assert_eq!(item.name(), "potion of poison");
+
+    GAMEDATA
+        .read()
+        .unwrap()
+        .set_item_knowledge(ItemKnowledge::default());
+}
+
+// Test that drinking a potion applies its effect and identifies it.
+#[test]
+fn use_item() {
+    let _lock = common::lock_turn();
+    let coord = Coord::new(3, 3);
+    let (mut dungeon, mut player) = common::room_with_player(coord);
+    GAMEDATA.write().unwrap().set_player(player.clone());
+    GAMEDATA
+        .read()
+        .unwrap()
+        .set_item_knowledge(ItemKnowledge::generate().unwrap());
+    player
+        .inner
+        .lock()
+        .unwrap()
+        .inventory
+        .add(ItemStack::new(Item::from_key("potion_poison").unwrap(), 2));
+
+    let hp = player.hp();
+    assert_eq!(player.use_item(&mut dungeon, 0), ActResult::None);
+    assert_eq!(player.hp(), hp - 6);
+    assert_eq!(player.inventory().top().to_string(), "potion of poison");
+    assert_eq!(
+        GAMEDATA.read().unwrap().recent_messages(1)[0].text(),
+        "That was a potion of poison."
+    );
+
+    player.heal(100);
+    assert_eq!(player.hp(), player.hp_max() as i32);
+
+    // Drinking poison at low health is deadly.
+    player.take_damage(player.hp_max() - 1);
+    assert_eq!(player.use_item(&mut dungeon, 0), ActResult::PlayerDead);
+    assert!(dungeon[coord].actor.is_none());
+
+    GAMEDATA
+        .read()
+        .unwrap()
+        .set_item_knowledge(ItemKnowledge::default());
+}
@@ -1,4 +1,11 @@
 pub mod actor;
+pub mod ai;
+pub mod dungeon;
+pub mod item;
+pub mod object;
+pub mod path;
+pub mod save;
+pub mod ui;
 
 mod common;
 
@@ -7,12 +14,13 @@ use crate::constants;
 use crate::coord::Coord;
 use crate::defs::GameRatio;
 use crate::dungeon::Dungeon;
+use crate::item::Item;
 use crate::object::Object;
 use crate::tile::Tile;
 use crate::util;
 use crate::DATABASE;
 
-// Test creating all actors, objects, and tiles contained in the database.
+// Test creating all actors, items, objects, and tiles contained in the database.
 #[test]
 fn create_everything() {
     let database = DATABASE.read().unwrap();
@@ -25,6 +33,11 @@ fn create_everything() {
         }
     }
 
+    let data = database.get_obj("items").unwrap();
+    for (key, item) in data.iter() {
+        Item::new(key, &item.get_obj().unwrap()).unwrap();
+    }
+
     let data = database.get_obj("objects").unwrap();
     for object in data.values() {
         for active in &[true, false] {
@@ -57,6 +70,7 @@ fn create_everything() {
 // Tests that the game queue system is working.
 #[test]
 fn game_queue() {
+    let _lock = common::lock_turn();
     let mut dungeon = common::setup_dungeon().unwrap();
     let database = DATABASE.read().unwrap();
     let actor_data = database.get_obj("actors").unwrap();
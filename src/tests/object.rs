@@ -0,0 +1,458 @@
+//! Object tests.
+
+use crate::actor::{Actor, Behavior};
+use crate::coord::Coord;
+use crate::dungeon::{ActResult, Dungeon, DungeonList};
+use crate::item::{Item, ItemStack};
+use crate::object::{Object, TrapEffect};
+use crate::path;
+use crate::player;
+use crate::tests::common;
+use crate::{DATABASE, GAMEDATA};
+use over::Obj;
+use std::str::FromStr;
+
+// Returns a room with the player at `coord` and a trap of type `trap` at `trap_coord`.
+fn room_with_trap(coord: Coord, trap: &str, trap_coord: Coord) -> (Dungeon, Actor) {
+    let (mut dungeon, player) = common::room_with_player(coord);
+    let objects = DATABASE.read().unwrap().get_obj("objects").unwrap();
+
+    Object::insert_new(
+        &mut dungeon,
+        trap_coord,
+        &objects.get_obj(trap).unwrap(),
+        true,
+    )
+    .unwrap();
+
+    (dungeon, player)
+}
+
+fn last_message() -> String {
+    GAMEDATA.read().unwrap().recent_messages(1)[0]
+        .text()
+        .to_string()
+}
+
+// Test that traps start out hidden and are revealed when the player sets them off.
+#[test]
+fn trap_hidden_until_triggered() {
+    let _lock = common::lock_turn();
+    let (start, trap) = (Coord::new(5, 5), Coord::new(6, 5));
+    let (mut dungeon, mut player) = room_with_trap(start, "dart_trap", trap);
+
+    {
+        let object = dungeon[trap].object.as_ref().unwrap();
+        assert!(object.hidden());
+        assert!(!object.visible());
+        assert_eq!(object.trap().unwrap().effect, TrapEffect::Damage);
+        assert_eq!(object.trap().unwrap().power, 4);
+    }
+    // Actors steer clear of traps.
+    assert!(!dungeon.open_for_actor(trap));
+
+    let hp = player.hp();
+    assert_eq!(
+        player.try_move_to(&mut dungeon, trap),
+        (ActResult::None, true)
+    );
+    assert_eq!(player.coord(), trap);
+    assert_eq!(player.hp(), hp - 4);
+    assert_eq!(last_message(), "You trigger a dart trap!");
+
+    let object = dungeon[trap].object.as_ref().unwrap();
+    assert!(!object.hidden());
+    assert!(object.visible());
+}
+
+// Test that a damage trap can kill, and that a dead actor isn't put back on the turn queue.
+#[test]
+fn trap_kills_actor() {
+    let _lock = common::lock_turn();
+    let trap = Coord::new(6, 5);
+    let (mut dungeon, _) = room_with_trap(Coord::new(2, 2), "dart_trap", trap);
+    let data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+
+    let mut actor = Actor::new(Coord::new(5, 5), &data.get_obj("test").unwrap()).unwrap();
+    dungeon.add_actor(actor.clone());
+    let hp = actor.hp();
+    actor.take_damage(hp as u32 - 1);
+
+    assert_eq!(
+        actor.try_move_to(&mut dungeon, trap),
+        (ActResult::None, true)
+    );
+    assert!(actor.is_dead());
+    assert!(!dungeon.contains_actor(&actor));
+    assert!(dungeon[trap].actor.is_none());
+    assert!(dungeon[trap].item_stash.is_some());
+    assert_eq!(dungeon.num_actors(), 1);
+}
+
+// Test the teleport, alarm and trap door effects.
+#[test]
+fn trap_effects() {
+    let _lock = common::lock_turn();
+    let (start, trap) = (Coord::new(5, 5), Coord::new(6, 5));
+
+    let (mut dungeon, mut player) = room_with_trap(start, "teleport_trap", trap);
+    player.try_move_to(&mut dungeon, trap);
+    assert_ne!(player.coord(), trap);
+    assert!(dungeon[trap].actor.is_none());
+    assert!(dungeon.contains_actor(&player));
+
+    let (mut dungeon, mut player) = room_with_trap(start, "alarm_trap", trap);
+    let data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+    let mut actor = Actor::new(Coord::new(15, 15), &data.get_obj("test").unwrap()).unwrap();
+    actor.set_behavior(Behavior::Hostile);
+    dungeon.add_actor(actor.clone());
+    player.try_move_to(&mut dungeon, trap);
+    assert_eq!(last_message(), "A loud alarm rings out!");
+    assert_eq!(actor.behavior(), Behavior::Hunting);
+    assert_eq!(actor.target(), Some(trap));
+
+    let (mut dungeon, mut player) = room_with_trap(start, "trap_door", trap);
+    assert_eq!(
+        player.try_move_to(&mut dungeon, trap),
+        (ActResult::FellDown, true)
+    );
+    assert_eq!(last_message(), "You fall through!");
+
+    // Monsters fall through to the next depth too.
+    dungeon.remove_actor(trap);
+    let mut actor = Actor::new(start, &data.get_obj("test").unwrap()).unwrap();
+    dungeon.add_actor(actor.clone());
+    assert_eq!(
+        actor.try_move_to(&mut dungeon, trap),
+        (ActResult::None, true)
+    );
+    assert!(!dungeon.contains_actor(&actor));
+    assert_eq!(dungeon.take_fallen().len(), 1);
+}
+
+// Returns the object of type `key` at `coord`, inserting it first.
+fn insert_object(dungeon: &mut Dungeon, coord: Coord, key: &str, active: bool) -> Object {
+    let objects = DATABASE.read().unwrap().get_obj("objects").unwrap();
+    Object::insert_new(dungeon, coord, &objects.get_obj(key).unwrap(), active).unwrap();
+
+    dungeon[coord].object.clone().unwrap()
+}
+
+// Test that an opened door swings shut after its delay, but not while the doorway is occupied.
+#[test]
+fn door_closes_after_delay() {
+    let _lock = common::lock_turn();
+    let (start, door) = (Coord::new(5, 5), Coord::new(6, 5));
+    let (mut dungeon, mut player) = room_with_trap(start, "dart_trap", Coord::new(2, 2));
+    // Closing the door by hand makes sure it isn't stuck.
+    let mut object = insert_object(&mut dungeon, door, "wooden_door", false);
+    object.close(false);
+    let delay = object.door().unwrap().close_delay;
+
+    // Bumping the door opens it, and the second bump walks through.
+    assert_eq!(
+        player.try_move_to(&mut dungeon, door),
+        (ActResult::None, true)
+    );
+    assert!(object.passable());
+    player.try_move_to(&mut dungeon, door);
+    assert_eq!(player.coord(), door);
+
+    for _ in 0..delay {
+        object.act(&mut dungeon);
+    }
+    assert!(object.passable());
+
+    player.try_move_to(&mut dungeon, start);
+    object.act(&mut dungeon);
+    assert!(!object.passable());
+    assert_eq!(last_message(), "The door swings shut.");
+}
+
+// Test that locked doors need their key, and that unlocking takes longer than a normal turn.
+#[test]
+fn locked_door_needs_key() {
+    let _lock = common::lock_turn();
+    let (start, door) = (Coord::new(5, 5), Coord::new(6, 5));
+    let (mut dungeon, mut player) = room_with_trap(start, "dart_trap", Coord::new(2, 2));
+    let object = insert_object(&mut dungeon, door, "iron_door", true);
+
+    assert_eq!(
+        player.try_move_to(&mut dungeon, door),
+        (ActResult::None, false)
+    );
+    assert!(object.locked());
+    assert_eq!(path::step_cost(&dungeon, start, door, false), None);
+
+    player
+        .inner
+        .lock()
+        .unwrap()
+        .inventory
+        .add(ItemStack::new(Item::from_key("iron_key").unwrap(), 1));
+    assert_eq!(
+        player.try_move_to(&mut dungeon, door),
+        (ActResult::None, true)
+    );
+    assert!(object.passable());
+    assert!(!object.locked());
+    assert_eq!(last_message(), "You unlock the iron door.");
+    // The key is kept.
+    assert!(player.has_item("iron_key"));
+
+    let turn = player.turn();
+    player.update_turn();
+    assert_eq!(player.turn(), turn + player.speed() * 2);
+    player.update_turn();
+    assert_eq!(player.turn(), turn + player.speed() * 3);
+}
+
+// Test forcing open a stuck door, and that monsters have to do the same.
+#[test]
+fn stuck_door() {
+    let _lock = common::lock_turn();
+    let (start, door) = (Coord::new(5, 5), Coord::new(6, 5));
+    let (mut dungeon, _) = room_with_trap(Coord::new(2, 2), "dart_trap", Coord::new(12, 12));
+    let data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+    let mut actor = Actor::new(start, &data.get_obj("test").unwrap()).unwrap();
+    dungeon.add_actor(actor.clone());
+
+    let object = insert_object(&mut dungeon, door, "wooden_door", true);
+    object.inner.lock().unwrap().door_mut().unwrap().stuck = true;
+
+    let mut tries = 0;
+    while !object.passable() {
+        assert_eq!(
+            actor.try_move_to(&mut dungeon, door),
+            (ActResult::None, true)
+        );
+        assert_eq!(actor.coord(), start);
+        tries += 1;
+        assert!(tries < 1000);
+    }
+    assert!(!object.door().unwrap().stuck);
+    assert_eq!(last_message(), "The test forces the door open.");
+}
+
+// Test kicking a locked door until it breaks, after which it can't be closed again.
+#[test]
+fn kick_door() {
+    let _lock = common::lock_turn();
+    let (start, door) = (Coord::new(5, 5), Coord::new(6, 5));
+    let (mut dungeon, mut player) = room_with_trap(start, "dart_trap", Coord::new(2, 2));
+    let mut object = insert_object(&mut dungeon, door, "iron_door", true);
+
+    let mut tries = 0;
+    while !object.passable() {
+        assert_eq!(
+            object.kick(&mut player, &mut dungeon),
+            (ActResult::None, true)
+        );
+        tries += 1;
+        assert!(tries < 1000);
+    }
+    assert!(object.door().unwrap().broken);
+    assert_eq!(last_message(), "You break the iron door down!");
+    assert_eq!(
+        object.kick(&mut player, &mut dungeon),
+        (ActResult::None, false)
+    );
+
+    assert_eq!(
+        object.try_close(&mut player, &mut dungeon),
+        (ActResult::None, false)
+    );
+    assert_eq!(last_message(), "The iron door is broken.");
+    assert!(object.passable());
+}
+
+// Test closing a door, which can't be done while something is in the doorway.
+#[test]
+fn close_door() {
+    let _lock = common::lock_turn();
+    let (start, door) = (Coord::new(5, 5), Coord::new(6, 5));
+    let (mut dungeon, mut player) = room_with_trap(start, "dart_trap", Coord::new(2, 2));
+    let mut object = insert_object(&mut dungeon, door, "wooden_door", false);
+
+    dungeon.add_item(door, ItemStack::new(Item::from_key("dagger").unwrap(), 1));
+    assert_eq!(
+        object.try_close(&mut player, &mut dungeon),
+        (ActResult::None, false)
+    );
+    assert_eq!(last_message(), "Something is in the way.");
+
+    dungeon.take_item(door, 0, 1);
+    assert_eq!(
+        object.try_close(&mut player, &mut dungeon),
+        (ActResult::None, true)
+    );
+    assert!(!object.passable());
+    assert_eq!(
+        object.try_close(&mut player, &mut dungeon),
+        (ActResult::None, false)
+    );
+    assert_eq!(last_message(), "The door is already closed.");
+}
+
+// Test that locked doors stay shut until a linked lever is pulled, and lock again when it is
+// pulled back.
+#[test]
+fn lever_opens_locked_door() {
+    let _lock = common::lock_turn();
+    let (start, door, lever) = (Coord::new(5, 5), Coord::new(6, 5), Coord::new(4, 5));
+    let (mut dungeon, mut player) = room_with_trap(start, "dart_trap", Coord::new(2, 2));
+    let mut door_object = insert_object(&mut dungeon, door, "iron_door", true);
+    let mut lever_object = insert_object(&mut dungeon, lever, "iron_lever", false);
+    door_object.set_link(Some("gate".into()));
+    lever_object.set_link(Some("gate".into()));
+
+    assert!(door_object.locked());
+    assert!(!door_object.openable());
+    assert_eq!(
+        player.try_move_to(&mut dungeon, door),
+        (ActResult::None, false)
+    );
+    assert!(!door_object.passable());
+    assert_eq!(last_message(), "The iron door is locked.");
+
+    assert_eq!(
+        player.try_move_to(&mut dungeon, lever),
+        (ActResult::None, true)
+    );
+    assert_eq!(player.coord(), start);
+    assert!(door_object.passable());
+    assert!(!door_object.locked());
+
+    player.try_move_to(&mut dungeon, lever);
+    assert!(!door_object.passable());
+    assert!(door_object.locked());
+    assert_eq!(last_message(), "You pull the lever.");
+}
+
+// Test drinking from a fountain until it runs dry, and that it refills over time.
+#[test]
+fn fountain_charges() {
+    let _lock = common::lock_turn();
+    let (start, fountain) = (Coord::new(5, 5), Coord::new(6, 5));
+    let (mut dungeon, mut player) = room_with_trap(start, "dart_trap", Coord::new(2, 2));
+    let mut object = insert_object(&mut dungeon, fountain, "stone_fountain", false);
+    let info = object.fountain().unwrap();
+
+    player.take_damage(info.power);
+    let hp = player.hp();
+    assert_eq!(
+        player.try_move_to(&mut dungeon, fountain),
+        (ActResult::None, true)
+    );
+    assert_eq!(player.hp(), hp + info.power as i32);
+    assert_eq!(player.coord(), start);
+
+    for _ in 1..info.max_charges {
+        player.try_move_to(&mut dungeon, fountain);
+    }
+    assert_eq!(
+        player.try_move_to(&mut dungeon, fountain),
+        (ActResult::None, false)
+    );
+    assert_eq!(last_message(), "The fountain is dry.");
+
+    object.act(&mut dungeon);
+    assert_eq!(object.fountain().unwrap().charges, 1);
+}
+
+// Test that traps keep their attributes and hidden state when saved.
+#[test]
+fn trap_round_trip() {
+    let trap = Coord::new(6, 5);
+    let (mut dungeon, _) = room_with_trap(Coord::new(5, 5), "teleport_trap", trap);
+    dungeon[trap].object.as_mut().unwrap().reveal();
+    let objects = DATABASE.read().unwrap().get_obj("objects").unwrap();
+    Object::insert_new(
+        &mut dungeon,
+        Coord::new(7, 5),
+        &objects.get_obj("alarm_trap").unwrap(),
+        true,
+    )
+    .unwrap();
+
+    let save_str = dungeon.to_save_obj().unwrap().write_str();
+    let loaded = Dungeon::from_save_obj(&Obj::from_str(&save_str).unwrap()).unwrap();
+
+    let object = loaded[trap].object.as_ref().unwrap();
+    assert_eq!(object.trap(), dungeon[trap].object.as_ref().unwrap().trap());
+    assert!(!object.hidden());
+    let object = loaded[Coord::new(7, 5)].object.as_ref().unwrap();
+    assert_eq!(object.trap().unwrap().effect, TrapEffect::Alarm);
+    assert!(object.hidden());
+}
+
+// Test that doors, fountains and levers keep their state and links when saved.
+#[test]
+fn object_state_round_trip() {
+    let (mut dungeon, _) = room_with_trap(Coord::new(5, 5), "dart_trap", Coord::new(2, 2));
+    let (door, fountain, lever) = (Coord::new(6, 5), Coord::new(7, 5), Coord::new(8, 5));
+    let mut door_object = insert_object(&mut dungeon, door, "wooden_door", true);
+    door_object.open();
+    door_object.act(&mut dungeon);
+    insert_object(&mut dungeon, fountain, "stone_fountain", false);
+    insert_object(&mut dungeon, lever, "iron_lever", true).set_link(Some("gate".into()));
+
+    let save_str = dungeon.to_save_obj().unwrap().write_str();
+    let loaded = Dungeon::from_save_obj(&Obj::from_str(&save_str).unwrap()).unwrap();
+
+    for coord in &[door, fountain, lever] {
+        let (loaded, object) = (
+            loaded[*coord]
+                .object
+                .as_ref()
+                .unwrap()
+                .inner
+                .lock()
+                .unwrap(),
+            dungeon[*coord]
+                .object
+                .as_ref()
+                .unwrap()
+                .inner
+                .lock()
+                .unwrap(),
+        );
+        assert_eq!(loaded.object_type(), object.object_type());
+        assert_eq!(loaded.active(), object.active());
+        assert_eq!(loaded.door(), object.door());
+        assert_eq!(loaded.fountain(), object.fountain());
+        assert_eq!(loaded.link(), object.link());
+    }
+    assert_eq!(loaded.linked_objects("gate").len(), 1);
+}
+
+// Test that generated depths get traps from their profile, with no trap doors on the last depth.
+#[test]
+fn generate_traps() {
+    let _lock = common::lock_turn();
+    let dungeon_list = DungeonList::new().unwrap();
+    let mut total = 0;
+
+    for (depth, dungeon) in dungeon_list.iter().enumerate() {
+        let mut num_traps = 0;
+        for x in 0..dungeon.width() as i32 {
+            for y in 0..dungeon.height() as i32 {
+                let coord = Coord::new(x, y);
+                let trap = match dungeon[coord].object {
+                    Some(ref object) => object.trap(),
+                    None => None,
+                };
+                if let Some(trap) = trap {
+                    num_traps += 1;
+                    assert!(dungeon[coord].passable());
+                    if depth + 1 == dungeon_list.len() {
+                        assert_ne!(trap.effect, TrapEffect::Pit);
+                    }
+                }
+            }
+        }
+        assert!(num_traps <= 6);
+        total += num_traps;
+    }
+    assert!(total > 0);
+}
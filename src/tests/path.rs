@@ -0,0 +1,200 @@
+//! Pathfinding tests.
+
+use crate::actor::Actor;
+use crate::coord::Coord;
+use crate::dungeon::Dungeon;
+use crate::object::Object;
+use crate::path::{find_path, DijkstraMap};
+use crate::tests::common;
+use crate::DATABASE;
+
+// Returns a room split in two by a wall along x = 10, with a gap at `gap`, if any.
+fn split_room(gap: Option<Coord>) -> Dungeon {
+    let mut dungeon = common::setup_room().unwrap();
+    let wall = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("tiles")
+        .unwrap()
+        .get_obj("stone_wall")
+        .unwrap();
+
+    for y in 1..19 {
+        let coord = Coord::new(10, y);
+        if Some(coord) != gap {
+            dungeon[coord].set_tile_info(&wall).unwrap();
+        }
+    }
+
+    dungeon
+}
+
+// Asserts that `path` leads from `start` to `goal` in single steps.
+fn assert_connected(path: &[Coord], start: Coord, goal: Coord) {
+    assert_eq!(path.last(), Some(&goal));
+
+    let mut prev = start;
+    for &coord in path {
+        assert!(prev.is_adjacent(coord));
+        prev = coord;
+    }
+}
+
+// Test that A* finds the shortest path in an open room.
+#[test]
+fn path_open_room() {
+    let dungeon = common::setup_room().unwrap();
+    let start = Coord::new(2, 2);
+    let goal = Coord::new(12, 7);
+
+    let path = find_path(&dungeon, start, goal).unwrap();
+    assert_connected(&path, start, goal);
+    assert_eq!(path.len(), 10);
+
+    assert_eq!(find_path(&dungeon, start, start), Some(vec![]));
+}
+
+// Test that A* goes around walls and fails when there is no way through.
+#[test]
+fn path_around_walls() {
+    let start = Coord::new(5, 2);
+    let goal = Coord::new(15, 2);
+    let gap = Coord::new(10, 17);
+
+    let dungeon = split_room(Some(gap));
+    let path = find_path(&dungeon, start, goal).unwrap();
+    assert_connected(&path, start, goal);
+    assert!(path.contains(&gap));
+
+    let dungeon = split_room(None);
+    assert_eq!(find_path(&dungeon, start, goal), None);
+    assert_eq!(find_path(&dungeon, start, Coord::new(0, 0)), None);
+}
+
+// Test that A* goes around ledges when there is an easy way, and always around hazards.
+#[test]
+fn path_around_terrain() {
+    let mut dungeon = common::setup_room().unwrap();
+    let start = Coord::new(2, 5);
+    let goal = Coord::new(12, 5);
+
+    // A ridge across the middle of the room, with a gap near the way straight through.
+    for y in 1..19 {
+        if y != 7 {
+            dungeon[Coord::new(7, y)].height = 3;
+        }
+    }
+    let path = find_path(&dungeon, start, goal).unwrap();
+    assert_connected(&path, start, goal);
+    assert!(path.iter().all(|coord| dungeon[*coord].height == 0));
+
+    // Paths lead around chasms however far the way around is, and never through them.
+    let chasm = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("tiles")
+        .unwrap()
+        .get_obj("chasm")
+        .unwrap();
+    for y in 1..19 {
+        dungeon[Coord::new(7, y)].height = 0;
+        if y != 18 {
+            dungeon[Coord::new(7, y)].set_feature(&chasm, 0).unwrap();
+        }
+    }
+    let path = find_path(&dungeon, start, goal).unwrap();
+    assert!(path.contains(&Coord::new(7, 18)));
+
+    dungeon[Coord::new(7, 18)].set_feature(&chasm, 0).unwrap();
+    assert_eq!(find_path(&dungeon, start, goal), None);
+}
+
+// Test that paths lead through doors that can be opened and are blocked by other actors.
+#[test]
+fn path_doors_and_actors() {
+    let database = DATABASE.read().unwrap();
+    let door = database
+        .get_obj("objects")
+        .unwrap()
+        .get_obj("wooden_door")
+        .unwrap();
+    let test = database.get_obj("actors").unwrap().get_obj("test").unwrap();
+
+    let start = Coord::new(5, 2);
+    let goal = Coord::new(15, 2);
+    let gap = Coord::new(10, 2);
+
+    // A closed door is opened on the way.
+    let mut dungeon = split_room(Some(gap));
+    Object::insert_new(&mut dungeon, gap, &door, true).unwrap();
+    let path = find_path(&dungeon, start, goal).unwrap();
+    assert_connected(&path, start, goal);
+    assert!(path.contains(&gap));
+
+    // An actor in the gap blocks the way, but an actor at the goal doesn't.
+    let mut dungeon = split_room(Some(gap));
+    Actor::insert_new(&mut dungeon, gap, &test).unwrap();
+    assert_eq!(find_path(&dungeon, start, goal), None);
+    assert!(find_path(&dungeon, start, gap).is_some());
+}
+
+// Test that rolling downhill on an approach map reaches the nearest goal.
+#[test]
+fn dijkstra_approach() {
+    let gap = Coord::new(10, 17);
+    let dungeon = split_room(Some(gap));
+    let goals = [Coord::new(15, 2), Coord::new(3, 17)];
+    let map = DijkstraMap::approach(&dungeon, &goals);
+
+    assert_eq!(map.value(goals[0]), Some(0));
+    assert_eq!(map.value(goals[1]), Some(0));
+    assert_eq!(map.value(Coord::new(10, 5)), None);
+    assert_eq!(map.value(Coord::new(-1, 5)), None);
+
+    let mut coord = Coord::new(5, 5);
+    for _ in 0..100 {
+        match map.downhill(&dungeon, coord) {
+            Some(next) => coord = next,
+            None => break,
+        }
+    }
+    assert_eq!(coord, goals[1]);
+}
+
+// Test that rolling downhill on a flee map gets away from the threat.
+#[test]
+fn dijkstra_flee() {
+    let dungeon = common::setup_room().unwrap();
+    let threat = Coord::new(9, 9);
+    let map = DijkstraMap::flee(&dungeon, &[threat]);
+
+    let mut coord = Coord::new(10, 10);
+    for _ in 0..100 {
+        match map.downhill(&dungeon, coord) {
+            Some(next) => coord = next,
+            None => break,
+        }
+    }
+    assert!(coord.distance(threat) >= 8);
+
+    // Fleeing never leads into a chasm.
+    let mut dungeon = dungeon;
+    let chasm = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("tiles")
+        .unwrap()
+        .get_obj("chasm")
+        .unwrap();
+    for y in 1..19 {
+        dungeon[Coord::new(13, y)].set_feature(&chasm, 0).unwrap();
+    }
+    let map = DijkstraMap::flee(&dungeon, &[threat]);
+
+    let mut coord = Coord::new(10, 10);
+    while let Some(next) = map.downhill(&dungeon, coord) {
+        assert!(!dungeon[next].hazard());
+        coord = next;
+    }
+    assert!(coord.x < 13);
+}
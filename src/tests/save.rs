@@ -0,0 +1,179 @@
+//! Save file tests.
+
+use crate::actor::Actor;
+use crate::console::Color;
+use crate::coord::Coord;
+use crate::defs::GameRatio;
+use crate::dungeon::{Dungeon, DungeonList};
+use crate::equipment::EquipSlot;
+use crate::item::{Item, ItemStack};
+use crate::message::Message;
+use crate::object::Object;
+use crate::tests::common;
+use crate::util::rand::GameRng;
+use crate::DATABASE;
+use over::Obj;
+use rand::RngCore;
+use std::str::FromStr;
+
+// Test that a dungeon survives being written to and read back from a save file.
+#[test]
+fn dungeon_round_trip() {
+    let mut dungeon = common::setup_dungeon().unwrap();
+    let database = DATABASE.read().unwrap();
+    let actor_data = database.get_obj("actors").unwrap();
+    let object_data = database.get_obj("objects").unwrap();
+
+    let coord1 = Coord::new(1, 2);
+    let coord2 = Coord::new(3, 4);
+    let last_seen = ('#', Color { r: 1, g: 2, b: 3 });
+
+    Actor::insert_new(&mut dungeon, coord1, &actor_data.get_obj("test").unwrap()).unwrap();
+    Object::insert_new(
+        &mut dungeon,
+        coord2,
+        &object_data.get_obj("test_slow").unwrap(),
+        true,
+    )
+    .unwrap();
+    dungeon[coord2].last_seen.set(Some(last_seen));
+    dungeon[coord2].height = -2;
+    let water = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("tiles")
+        .unwrap()
+        .get_obj("water")
+        .unwrap();
+    dungeon[coord2].set_feature(&water, 3).unwrap();
+
+    let save_str = dungeon.to_save_obj().unwrap().write_str();
+    let loaded = Dungeon::from_save_obj(&Obj::from_str(&save_str).unwrap()).unwrap();
+
+    assert_eq!(loaded.width(), dungeon.width());
+    assert_eq!(loaded.height(), dungeon.height());
+    assert_eq!(loaded.dungeon_type, dungeon.dungeon_type);
+    assert_eq!(loaded.num_actors(), 1);
+
+    let actor = loaded[coord1].actor.as_ref().unwrap();
+    assert_eq!(actor.name(), "test");
+    assert_eq!(actor.coord(), coord1);
+    assert_eq!(actor.turn(), dungeon.peek_actor().turn());
+
+    let object = loaded[coord2].object.as_ref().unwrap();
+    assert_eq!(object.name(), "test_slow");
+    assert_eq!(object.turn(), dungeon.peek_object().turn());
+    assert!(object.inner.lock().unwrap().active());
+
+    assert_eq!(loaded[coord2].last_seen.get(), Some(last_seen));
+    assert_eq!(loaded[coord1].last_seen.get(), None);
+    assert_eq!(loaded[coord2].height, -2);
+    let feature = loaded[coord2].feature.as_ref().unwrap();
+    assert_eq!(feature.info.key, "water");
+    assert_eq!(feature.amount, 3);
+    assert!(loaded[coord1].feature.is_none());
+    assert_eq!(loaded[coord2].info.key, dungeon[coord2].info.key);
+}
+
+// Test that a message keeps its color and turn stamp when saved.
+#[test]
+fn message_round_trip() {
+    let message = Message::new("Hello.", Color { r: 1, g: 2, b: 3 }, GameRatio::new(7, 2));
+
+    let save_str = message.to_save_obj().write_str();
+    let loaded = Message::from_save_obj(&Obj::from_str(&save_str).unwrap()).unwrap();
+
+    assert_eq!(loaded, message);
+    assert_eq!(loaded.to_string(), "[3] Hello.");
+}
+
+// Test that a random number generator recreated from its seed and step count carries on where the
+// original left off.
+#[test]
+fn rng_round_trip() {
+    let mut rng = GameRng::new(42);
+    rng.next_u32();
+    rng.next_u64();
+    rng.fill_bytes(&mut [0; 7]);
+    assert_eq!(rng.steps(), 5);
+
+    let mut loaded = GameRng::with_steps(rng.seed(), rng.steps());
+    for _ in 0..10 {
+        assert_eq!(loaded.next_u64(), rng.next_u64());
+    }
+    assert_ne!(GameRng::new(42).next_u64(), rng.next_u64());
+}
+
+// Test that item stashes on the floor and in inventories are saved, along with equipment.
+#[test]
+fn item_round_trip() {
+    let mut dungeon = common::setup_dungeon().unwrap();
+    let actor_data = DATABASE.read().unwrap().get_obj("actors").unwrap();
+    let coord1 = Coord::new(1, 2);
+    let coord2 = Coord::new(3, 4);
+
+    Actor::insert_new(&mut dungeon, coord1, &actor_data.get_obj("test").unwrap()).unwrap();
+    {
+        let actor = dungeon[coord1].actor.as_ref().unwrap();
+        let mut inner = actor.inner.lock().unwrap();
+        inner
+            .inventory
+            .add(ItemStack::new(Item::from_key("dagger").unwrap(), 2));
+        inner.equipment.equip(Item::from_key("hammer").unwrap());
+    }
+    dungeon.add_item(coord2, ItemStack::new(Item::from_key("ration").unwrap(), 1));
+    dungeon.add_item(coord2, ItemStack::new(Item::from_key("club").unwrap(), 1));
+
+    let save_str = dungeon.to_save_obj().unwrap().write_str();
+    let loaded = Dungeon::from_save_obj(&Obj::from_str(&save_str).unwrap()).unwrap();
+
+    let actor = loaded[coord1].actor.as_ref().unwrap();
+    assert_eq!(
+        actor.damage(),
+        dungeon[coord1].actor.as_ref().unwrap().damage()
+    );
+    let hammer = actor.equipment().get(EquipSlot::Weapon).cloned().unwrap();
+    assert!(hammer == Item::from_key("hammer").unwrap());
+
+    let inventory = actor.inventory();
+    assert_eq!(inventory.len(), 1);
+    assert_eq!(inventory.top().to_string(), "dagger (x2)");
+    assert!(inventory.top().item() == &Item::from_key("dagger").unwrap());
+
+    let stash = loaded[coord2].item_stash.as_ref().unwrap();
+    assert_eq!(stash.len(), 2);
+    assert_eq!(stash.top().item().key(), "club");
+    assert!((stash.weight() - dungeon[coord2].item_stash.as_ref().unwrap().weight()).abs() < 0.1);
+}
+
+// Test that the overworld and the names of its townsfolk are saved along with the dungeons.
+#[test]
+fn dungeon_list_round_trip() {
+    let villager = DATABASE
+        .read()
+        .unwrap()
+        .get_obj("actors")
+        .unwrap()
+        .get_obj("villager")
+        .unwrap();
+    let coord = Coord::new(1, 2);
+
+    let mut overworld = common::setup_dungeon().unwrap();
+    Actor::insert_new(&mut overworld, coord, &villager).unwrap();
+    let given_name = overworld[coord].actor.as_ref().unwrap().given_name();
+    assert!(given_name.is_some());
+
+    let mut dungeon_list = DungeonList::from_dungeons(vec![common::setup_dungeon().unwrap()]);
+    dungeon_list.overworld = Some(overworld);
+    dungeon_list.on_overworld = true;
+
+    let save_str = dungeon_list.to_save_obj().unwrap().write_str();
+    let loaded = DungeonList::from_save_obj(&Obj::from_str(&save_str).unwrap()).unwrap();
+
+    assert_eq!(loaded.len(), 1);
+    assert!(loaded.on_overworld);
+    let overworld = loaded.overworld.as_ref().unwrap();
+    let actor = overworld[coord].actor.as_ref().unwrap();
+    assert_eq!(actor.name(), "villager");
+    assert_eq!(actor.given_name(), given_name);
+}
@@ -0,0 +1,470 @@
+//! UI and input tests, run against the in-memory console.
+
+use crate::actor::Actor;
+use crate::console::{Color, KeyCode, MemoryConsole};
+use crate::constants;
+use crate::coord::Coord;
+use crate::defs::GameRatio;
+use crate::dungeon::{ActResult, Dungeon};
+use crate::equipment::EquipSlot;
+use crate::identify::ItemKnowledge;
+use crate::item::{Item, ItemStack};
+use crate::object::Object;
+use crate::player;
+use crate::tests::common;
+use crate::ui::{self, Draw};
+use crate::{CONSOLE, DATABASE, GAMEDATA};
+
+// Returns a room with the player at `coord`, set up as the current player with no queued input.
+fn room_with_player(coord: Coord) -> (Dungeon, Actor) {
+    let (dungeon, player) = common::room_with_player(coord);
+    GAMEDATA.write().unwrap().set_player(player.clone());
+
+    with_console(MemoryConsole::clear_input);
+
+    (dungeon, player)
+}
+
+// Runs `f` on the in-memory console. Assertions should be made outside of `f` so that a failing
+// test doesn't poison the console for the others.
+fn with_console<F, T>(f: F) -> T
+where
+    F: FnOnce(&mut MemoryConsole) -> T,
+{
+    let mut console = CONSOLE.lock().unwrap();
+    f(console.as_memory().unwrap())
+}
+
+// Returns the characters on screen in row `y`.
+fn screen_row(y: i32) -> String {
+    with_console(|console| console.row(y))
+}
+
+// Returns the color of the character on screen at `x` and `y`.
+fn screen_color(x: i32, y: i32) -> Color {
+    with_console(|console| console.color_at(x, y))
+}
+
+// Test that the game view is centered on the player and the status is drawn next to it.
+#[test]
+fn draw_game() {
+    let _lock = common::lock_turn();
+    let (dungeon, player) = room_with_player(Coord::new(10, 10));
+    let (width, height) = {
+        let settings = GAMEDATA.read().unwrap().ui_settings;
+        (settings.game_width as i32, settings.game_height as i32)
+    };
+
+    ui::draw_all(&dungeon);
+
+    let (center_x, center_y) = (width / 2, height / 2);
+    let wall = dungeon[Coord::new(0, 10)].info.draw_c();
+    let floor = dungeon[Coord::new(5, 10)].info.draw_c();
+
+    let row = screen_row(center_y);
+    assert_eq!(row.chars().nth(center_x as usize), Some(player.draw_c()));
+    assert_eq!(screen_color(center_x, center_y), player.draw_color());
+    assert_eq!(row.chars().nth(center_x as usize - 10), Some(wall));
+    assert_eq!(row.chars().nth(center_x as usize - 5), Some(floor));
+    // Nothing lies outside the dungeon.
+    assert_eq!(row.chars().nth(center_x as usize - 11), Some(' '));
+
+    assert!(screen_row(1)[width as usize + 1..].starts_with("HP: 20/20"));
+}
+
+// Test that tiles out of view are drawn as they were last seen.
+#[test]
+fn draw_last_seen() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, player) = room_with_player(Coord::new(10, 10));
+    let center = {
+        let settings = GAMEDATA.read().unwrap().ui_settings;
+        (settings.game_width / 2) as i32
+    };
+
+    ui::draw_all(&dungeon);
+    let color = screen_color(center - 5, center);
+
+    // Only the player's own tile is visible now.
+    dungeon.calc_fov(player.coord(), 0);
+    ui::draw_all(&dungeon);
+
+    let dimmed = screen_color(center - 5, center);
+    assert!(dimmed.r <= color.r && dimmed.g <= color.g && dimmed.b <= color.b);
+    assert_ne!(dimmed, color);
+}
+
+// Test that scripted keypresses drive the player's turn.
+#[test]
+fn player_act_input() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, mut player) = room_with_player(Coord::new(10, 10));
+
+    with_console(|console| console.push_code(KeyCode::Right));
+    assert_eq!(
+        player::player_act(&mut player, &mut dungeon),
+        ActResult::None
+    );
+    assert_eq!(player.coord(), Coord::new(11, 10));
+
+    // Taking stairs that aren't there doesn't use up the turn, so the next key is read too.
+    with_console(|console| {
+        console.push_char('>');
+        console.push_code(KeyCode::Escape);
+    });
+    assert_eq!(
+        player::player_act(&mut player, &mut dungeon),
+        ActResult::QuitGame
+    );
+
+    // The in-memory window closes once it runs out of input.
+    assert_eq!(
+        player::player_act(&mut player, &mut dungeon),
+        ActResult::WindowClosed
+    );
+    assert_eq!(player.coord(), Coord::new(11, 10));
+}
+
+// Test that only messages about events the player can see are logged, with turn stamps.
+#[test]
+fn message_visibility() {
+    let _lock = common::lock_turn();
+    let (dungeon, _player) = room_with_player(Coord::new(3, 3));
+    let color = Color { r: 1, g: 2, b: 3 };
+    GAMEDATA
+        .write()
+        .unwrap()
+        .set_turn(GameRatio::from_integer(5));
+
+    dungeon.add_message(&[Coord::new(4, 4)], "the seen event.", color);
+    // Outside the dungeon, so never visible.
+    dungeon.add_message(&[Coord::new(-1, -1)], "the unseen event.", color);
+
+    let messages = GAMEDATA.read().unwrap().recent_messages(1);
+    assert_eq!(messages[0].text(), "The seen event.");
+    assert_eq!(messages[0].color(), color);
+    assert_eq!(messages[0].turn(), GameRatio::from_integer(5));
+}
+
+// Test that recent messages are drawn next to the game view, newest at the bottom.
+#[test]
+fn message_panel() {
+    let _lock = common::lock_turn();
+    let (dungeon, _player) = room_with_player(Coord::new(10, 10));
+    let (x, bottom) = {
+        let settings = GAMEDATA.read().unwrap().ui_settings;
+        (settings.game_width + 1, settings.game_height as i32 - 1)
+    };
+
+    {
+        let game_data = GAMEDATA.read().unwrap();
+        game_data.add_message("Older panel message.", constants::MESSAGE_COLOR);
+        game_data.add_message("Newest panel message.", constants::MESSAGE_BAD_COLOR);
+    }
+    ui::draw_all(&dungeon);
+
+    assert_eq!(
+        screen_row(bottom)[x..].trim_end(),
+        "[0] Newest panel message."
+    );
+    assert_eq!(
+        screen_row(bottom - 1)[x..].trim_end(),
+        "[0] Older panel message."
+    );
+    assert_eq!(screen_color(x as i32, bottom), constants::MESSAGE_BAD_COLOR);
+}
+
+// Test that the message history can be scrolled.
+#[test]
+fn message_history() {
+    let _lock = common::lock_turn();
+    let _ = room_with_player(Coord::new(10, 10));
+    let height = GAMEDATA.read().unwrap().console_settings.height();
+    let page = height - 2;
+
+    {
+        let game_data = GAMEDATA.read().unwrap();
+        for i in 0..100 {
+            game_data.add_message(&format!("History {}.", i), constants::MESSAGE_COLOR);
+        }
+    }
+
+    // Scroll back a page and a line. The history closes along with the window.
+    with_console(|console| {
+        console.push_code(KeyCode::PageUp);
+        console.push_code(KeyCode::Up);
+    });
+    ui::show_message_history();
+
+    assert_eq!(screen_row(0).trim_end(), "Message history");
+    assert_eq!(
+        screen_row(page).trim_end(),
+        format!("[0] History {}.", 99 - page - 1)
+    );
+}
+
+// Test picking up and dropping items with scripted keys.
+#[test]
+fn pick_up_and_drop() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, mut player) = room_with_player(Coord::new(10, 10));
+    let coord = Coord::new(11, 10);
+
+    dungeon.add_item(coord, ItemStack::new(Item::from_key("dagger").unwrap(), 2));
+    dungeon.add_item(coord, ItemStack::new(Item::from_key("club").unwrap(), 1));
+
+    // Step onto the items and pick up the daggers, the first item listed.
+    with_console(|console| {
+        console.push_code(KeyCode::Right);
+        console.push_char('g');
+        console.push_char('a');
+    });
+    player::player_act(&mut player, &mut dungeon);
+    assert_eq!(
+        GAMEDATA.read().unwrap().recent_messages(1)[0].text(),
+        "There are several items here."
+    );
+    player::player_act(&mut player, &mut dungeon);
+
+    assert_eq!(player.inventory().top().to_string(), "dagger (x2)");
+    assert_eq!(dungeon.stash_size(coord), 1);
+    assert_eq!(
+        GAMEDATA.read().unwrap().recent_messages(1)[0].text(),
+        "You pick up the dagger (x2)."
+    );
+
+    // A single stack is picked up without asking.
+    with_console(|console| console.push_char(','));
+    player::player_act(&mut player, &mut dungeon);
+    assert!(dungeon[coord].item_stash.is_none());
+    assert_eq!(player.inventory().len(), 2);
+
+    // Drop the daggers again.
+    with_console(|console| {
+        console.push_char('d');
+        console.push_char('a');
+    });
+    player::player_act(&mut player, &mut dungeon);
+    assert_eq!(player.inventory().len(), 1);
+    assert_eq!(
+        dungeon[coord]
+            .item_stash
+            .as_ref()
+            .unwrap()
+            .top()
+            .to_string(),
+        "dagger (x2)"
+    );
+
+    // There is nothing left to pick up once the stash is gone.
+    with_console(|console| {
+        console.push_char(',');
+        console.push_char(',');
+    });
+    player::player_act(&mut player, &mut dungeon);
+    assert_eq!(player.inventory().len(), 2);
+    player::player_act(&mut player, &mut dungeon);
+    assert_eq!(
+        GAMEDATA.read().unwrap().recent_messages(1)[0].text(),
+        "There is nothing here to pick up."
+    );
+}
+
+// Test that the inventory screen lists items with letters and the total weight.
+#[test]
+fn inventory_screen() {
+    let _lock = common::lock_turn();
+    let (_dungeon, player) = room_with_player(Coord::new(10, 10));
+    let height = GAMEDATA.read().unwrap().console_settings.height();
+
+    player
+        .inner
+        .lock()
+        .unwrap()
+        .inventory
+        .add(ItemStack::new(Item::from_key("potion_healing").unwrap(), 3));
+
+    with_console(|console| console.push_code(KeyCode::Escape));
+    ui::show_items("Inventory", &player.inventory());
+
+    assert_eq!(screen_row(0).trim_end(), "Inventory");
+    assert!(screen_row(2).starts_with("a) ! potion of healing (x3), "));
+    assert!(screen_row(height - 2).starts_with("Total weight: "));
+}
+
+// Test wielding and removing equipment with scripted keys.
+#[test]
+fn wield_and_remove() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, mut player) = room_with_player(Coord::new(10, 10));
+    let damage = player.damage();
+
+    player
+        .inner
+        .lock()
+        .unwrap()
+        .inventory
+        .add(ItemStack::new(Item::from_key("hammer").unwrap(), 1));
+
+    // The player starts out with a dagger, which is swapped for the hammer.
+    with_console(|console| {
+        console.push_char('w');
+        console.push_char('a');
+    });
+    player::player_act(&mut player, &mut dungeon);
+    assert_eq!(player.damage(), damage + 3);
+    assert_eq!(player.inventory().top().item().key(), "dagger");
+    assert_eq!(
+        GAMEDATA.read().unwrap().recent_messages(1)[0].text(),
+        "You wield the hammer."
+    );
+
+    // There is no armor left in the inventory to wear.
+    with_console(|console| console.push_char('W'));
+    player::player_act(&mut player, &mut dungeon);
+    assert_eq!(
+        GAMEDATA.read().unwrap().recent_messages(1)[0].text(),
+        "You have no armor to equip."
+    );
+
+    // Take off the leather armor, listed after the weapon.
+    with_console(|console| {
+        console.push_char('T');
+        console.push_char('b');
+    });
+    player::player_act(&mut player, &mut dungeon);
+    assert!(player.equipment().get(EquipSlot::Body).is_none());
+    assert_eq!(player.inventory().len(), 2);
+}
+
+// Test reading a scroll of identify and choosing what to identify with scripted keys.
+#[test]
+fn read_identify() {
+    let _lock = common::lock_turn();
+    let (mut dungeon, mut player) = room_with_player(Coord::new(10, 10));
+    GAMEDATA
+        .read()
+        .unwrap()
+        .set_item_knowledge(ItemKnowledge::generate().unwrap());
+
+    {
+        let mut inner = player.inner.lock().unwrap();
+        inner
+            .inventory
+            .add(ItemStack::new(Item::from_key("potion_water").unwrap(), 1));
+        inner.inventory.add(ItemStack::new(
+            Item::from_key("scroll_identify").unwrap(),
+            1,
+        ));
+    }
+    let water = Item::from_key("potion_water").unwrap();
+    let appearance = water.name();
+    assert!(!water.is_identified());
+
+    // Read the scroll, then identify the potion, the only unidentified item left.
+    with_console(|console| {
+        console.push_char('r');
+        console.push_char('a');
+        console.push_char('a');
+    });
+    player::player_act(&mut player, &mut dungeon);
+
+    assert!(water.is_identified());
+    assert_eq!(player.inventory().len(), 1);
+    let messages = GAMEDATA.read().unwrap().recent_messages(2);
+    assert_eq!(messages[0].text(), "That was a scroll of identify.");
+    assert_eq!(
+        messages[1].text(),
+        format!("The {} is a potion of water.", appearance)
+    );
+
+    GAMEDATA
+        .read()
+        .unwrap()
+        .set_item_knowledge(ItemKnowledge::default());
+}
+
+// Test searching for a hidden trap and confirming before stepping onto it.
+#[test]
+fn search_and_avoid_trap() {
+    let _lock = common::lock_turn();
+    let start = Coord::new(10, 10);
+    let trap = Coord::new(11, 10);
+    let (mut dungeon, mut player) = room_with_player(start);
+    let objects = DATABASE.read().unwrap().get_obj("objects").unwrap();
+    Object::insert_new(
+        &mut dungeon,
+        trap,
+        &objects.get_obj("dart_trap").unwrap(),
+        true,
+    )
+    .unwrap();
+
+    // Search until the trap is found.
+    let object = dungeon[trap].object.clone().unwrap();
+    for _ in 0..100 {
+        if !object.hidden() {
+            break;
+        }
+        with_console(|console| console.push_char('s'));
+        player::player_act(&mut player, &mut dungeon);
+    }
+    assert!(!object.hidden());
+    assert_eq!(
+        GAMEDATA.read().unwrap().recent_messages(1)[0].text(),
+        "You find a dart trap!"
+    );
+
+    // Decline to step onto it, then step on it anyway.
+    with_console(|console| {
+        console.push_code(KeyCode::Right);
+        console.push_char('n');
+        console.push_code(KeyCode::Right);
+        console.push_char('y');
+    });
+    player::player_act(&mut player, &mut dungeon);
+    assert_eq!(player.coord(), trap);
+    assert!(player.hp() < player.hp_max() as i32);
+}
+
+// Test opening and closing a door in a chosen direction.
+#[test]
+fn open_and_close_door() {
+    let _lock = common::lock_turn();
+    let start = Coord::new(10, 10);
+    let door = Coord::new(10, 9);
+    let (mut dungeon, mut player) = room_with_player(start);
+    let objects = DATABASE.read().unwrap().get_obj("objects").unwrap();
+    Object::insert_new(
+        &mut dungeon,
+        door,
+        &objects.get_obj("wooden_door").unwrap(),
+        false,
+    )
+    .unwrap();
+    let object = dungeon[door].object.clone().unwrap();
+
+    // There is no door to the east, so that doesn't take a turn and the next command is read.
+    with_console(|console| {
+        console.push_char('c');
+        console.push_code(KeyCode::Right);
+        console.push_char('c');
+        console.push_code(KeyCode::Up);
+    });
+    assert_eq!(
+        player::player_act(&mut player, &mut dungeon),
+        ActResult::None
+    );
+    assert!(!object.passable());
+
+    // Cancelling the direction prompt doesn't take a turn either.
+    with_console(|console| {
+        console.push_char('o');
+        console.push_code(KeyCode::Escape);
+        console.push_char('o');
+        console.push_code(KeyCode::Up);
+    });
+    player::player_act(&mut player, &mut dungeon);
+    assert!(object.passable());
+    assert_eq!(player.coord(), start);
+}
@@ -2,6 +2,8 @@
 
 use crate::actor::Actor;
 use crate::console::Color;
+use crate::defs::big_to_u32;
+use crate::dungeon::DepthDirection;
 use crate::error::GameError;
 use crate::game_data::GameData;
 use crate::item::ItemStash;
@@ -17,6 +19,8 @@ use std::sync::Arc;
 /// Struct containing the tile information for a class of tiles.
 #[derive(Debug)]
 pub struct TileInfo {
+    /// Key of this tile in the database.
+    pub key: String,
     pub material: Arc<MaterialInfo>,
 
     pub name: String,
@@ -28,10 +32,17 @@ pub struct TileInfo {
     pub transparent: bool,
 
     pub staircase: Staircase,
+
+    /// Must deep pools of this feature be swum through?
+    pub swim: bool,
+    /// Damage dealt to anyone stepping into this feature.
+    pub burn: u32,
+    /// Does this feature drop anyone stepping into it to the next depth?
+    pub fall: bool,
 }
 
 impl TileInfo {
-    pub fn new(game_data: &GameData, tile_data: &Obj) -> GameResult<TileInfo> {
+    pub fn new(game_data: &GameData, key: &str, tile_data: &Obj) -> GameResult<TileInfo> {
         let _material = tile_data.get_obj("material")?;
         let mname = _material.get_str("name")?;
         let id = _material.id();
@@ -45,8 +56,13 @@ impl TileInfo {
 
         let staircase = Staircase::from_str(&tile_data.get_str("staircase")?)?;
 
+        let swim = tile_data.get_bool("swim")?;
+        let burn = big_to_u32(tile_data.get_int("burn")?)?;
+        let fall = tile_data.get_bool("fall")?;
+
         // Create the `TileInfo`.
         let tile_info = TileInfo {
+            key: key.into(),
             material,
 
             name,
@@ -56,6 +72,10 @@ impl TileInfo {
             transparent,
 
             staircase,
+
+            swim,
+            burn,
+            fall,
         };
 
         Ok(tile_info)
@@ -90,6 +110,8 @@ pub struct Tile {
 
     /// Elevation of this tile in the heightmap. 0 is ground level, < 0 is below ground.
     pub height: i32,
+    /// Terrain feature on top of the tile, such as a pool of water.
+    pub feature: Option<Feature>,
 
     // Not serialized:
     pub actor: Option<Actor>,
@@ -103,16 +125,22 @@ impl Tile {
         let id = tile_data.id();
         let info = GAMEDATA.read().unwrap().tile_info(id);
 
-        Ok(Tile {
+        Ok(Tile::from_info(info))
+    }
+
+    /// Returns a new `Tile` object of the given type.
+    pub fn from_info(info: Arc<TileInfo>) -> Tile {
+        Tile {
             info,
             last_seen: Cell::new(None),
 
             height: 0,
+            feature: None,
 
             actor: None,
             object: None,
             item_stash: None,
-        })
+        }
     }
 
     pub fn set_tile_info(&mut self, tile_data: &Obj) -> GameResult<()> {
@@ -122,6 +150,18 @@ impl Tile {
 
         Ok(())
     }
+
+    /// Covers the tile with `amount` of the feature `feature_data`.
+    pub fn set_feature(&mut self, feature_data: &Obj, amount: u32) -> GameResult<()> {
+        let id = feature_data.id();
+
+        self.feature = Some(Feature {
+            info: GAMEDATA.read().unwrap().tile_info(id),
+            amount,
+        });
+
+        Ok(())
+    }
 }
 
 impl Tile {
@@ -129,6 +169,15 @@ impl Tile {
         self.info.passable
     }
 
+    /// Returns true if stepping onto the tile would hurt, such as lava, or drop an actor to the
+    /// next depth, such as a chasm. Actors go around hazards and nothing is generated on them.
+    pub fn hazard(&self) -> bool {
+        match self.feature {
+            Some(ref feature) => feature.info.burn > 0 || feature.info.fall,
+            None => false,
+        }
+    }
+
     pub fn transparent(&self) -> bool {
         self.info.transparent
     }
@@ -138,6 +187,22 @@ impl Tile {
     }
 }
 
+/// A terrain feature, such as water, lava or a chasm, lying on top of a tile.
+#[derive(Clone, Debug)]
+pub struct Feature {
+    /// A reference to the `TileInfo` of the feature.
+    pub info: Arc<TileInfo>,
+    /// How deep a liquid is. Liquids flow to tiles whose surface is lower than their own.
+    pub amount: u32,
+}
+
+impl Feature {
+    /// Returns true if the feature is a liquid that can flow.
+    pub fn liquid(&self) -> bool {
+        self.info.material.liquid
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Staircase {
     Up,
@@ -146,6 +211,18 @@ pub enum Staircase {
     None,
 }
 
+impl Staircase {
+    /// Returns true if this staircase can be taken in `direction`.
+    pub fn leads(self, direction: DepthDirection) -> bool {
+        matches!(
+            (self, direction),
+            (Staircase::UpDown, _)
+                | (Staircase::Up, DepthDirection::Up)
+                | (Staircase::Down, DepthDirection::Down)
+        )
+    }
+}
+
 impl FromStr for Staircase {
     type Err = GameError;
 
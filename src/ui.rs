@@ -1,12 +1,17 @@
 //! User interface module.
 
-use crate::console::Color;
+use crate::console::{Color, DrawConsole, KeyCode};
 use crate::constants;
 use crate::coord::Coord;
 use crate::defs::big_to_usize;
 use crate::dungeon::Dungeon;
+use crate::equipment::{EquipSlot, Equipment};
 use crate::game_data::GameData;
+use crate::item::{ItemStack, ItemStash};
+use crate::message::Message;
+use crate::util::direction::CompassDirection;
 use crate::util::rectangle::Rectangle;
+use crate::util::string::wrap;
 use crate::{GameResult, CONSOLE, GAMEDATA};
 use over::Obj;
 use std::cell::Cell;
@@ -64,10 +69,337 @@ pub fn draw_all(dungeon: &Dungeon) {
     CONSOLE.lock().unwrap().clear();
 
     draw_game(dungeon);
+    draw_status();
+    draw_messages();
 
     CONSOLE.lock().unwrap().flush();
 }
 
+/// Displays `question` and waits for the player to answer yes or no.
+/// Returns false if the window is closed.
+pub fn prompt_yes_no(question: &str) -> bool {
+    let mut console = CONSOLE.lock().unwrap();
+
+    console.clear();
+    console.put_str(0, 0, question, constants::STATUS_COLOR);
+    console.put_str(0, 1, "(y/n)", constants::STATUS_COLOR);
+    console.flush();
+
+    loop {
+        if console.window_closed() {
+            return false;
+        }
+
+        match console.wait_for_keypress(true).printable {
+            'y' | 'Y' => return true,
+            'n' | 'N' => return false,
+            _ => (),
+        }
+    }
+}
+
+/// Displays `question` over the top row of the screen and waits for the player to pick a direction
+/// with the arrow keys. Returns `None` if the player cancels or the window is closed.
+pub fn prompt_direction(question: &str) -> Option<CompassDirection> {
+    let mut console = CONSOLE.lock().unwrap();
+    let width = GAMEDATA.read().unwrap().console_settings.width() as usize;
+
+    let text = format!("{} (arrow keys, Esc: cancel)", question);
+    console.put_str(
+        0,
+        0,
+        &format!("{:1$}", text, width),
+        constants::STATUS_COLOR,
+    );
+    console.flush();
+
+    if console.window_closed() {
+        return None;
+    }
+
+    match console.wait_for_keypress(true).code {
+        KeyCode::Left => Some(CompassDirection::W),
+        KeyCode::Up => Some(CompassDirection::N),
+        KeyCode::Right => Some(CompassDirection::E),
+        KeyCode::Down => Some(CompassDirection::S),
+        _ => None,
+    }
+}
+
+/// Draws game status information to the right of the game view.
+pub fn draw_status() {
+    let mut console = CONSOLE.lock().unwrap();
+    let game_data = GAMEDATA.read().unwrap();
+
+    let x = game_data.ui_settings.game_width as i32 + 1;
+    console.put_str(
+        x,
+        0,
+        &format!("Seed: {}", game_data.seed()),
+        constants::STATUS_COLOR,
+    );
+
+    let player = game_data.player();
+    console.put_str(
+        x,
+        1,
+        &format!("HP: {}/{}", player.hp().max(0), player.hp_max()),
+        constants::STATUS_COLOR,
+    );
+    console.put_str(
+        x,
+        2,
+        &format!(
+            "Att: {}  Def: {}  Dmg: {}",
+            player.attack(),
+            player.defense(),
+            player.damage()
+        ),
+        constants::STATUS_COLOR,
+    );
+}
+
+/// Draws the most recent messages in a panel below the status, with the newest at the bottom.
+pub fn draw_messages() {
+    let mut console = CONSOLE.lock().unwrap();
+    let game_data = GAMEDATA.read().unwrap();
+
+    let x = game_data.ui_settings.game_width as i32 + 1;
+    let width = (game_data.console_settings.width() - x).max(1) as usize;
+    let top = constants::STATUS_HEIGHT;
+    let height = (game_data.ui_settings.game_height as i32 - top).max(0) as usize;
+
+    // Every message takes up at least one line, so this is all that can fit.
+    let lines = wrap_messages(&game_data.recent_messages(height), width);
+    let start = lines.len().saturating_sub(height);
+
+    for (y, (line, color)) in lines[start..].iter().enumerate() {
+        console.put_str(x, top + y as i32, line, *color);
+    }
+}
+
+/// Shows the full message history, scrolled to the newest messages, until escape is pressed.
+/// The history can be scrolled with the arrow keys, page up/down, and home/end.
+pub fn show_message_history() {
+    let (messages, width, height) = {
+        let game_data = GAMEDATA.read().unwrap();
+        let settings = &game_data.console_settings;
+        (game_data.messages(), settings.width(), settings.height())
+    };
+
+    let lines = wrap_messages(&messages, width.max(1) as usize);
+    // Leave room for the title and the help line.
+    let page = (height - 2).max(1) as usize;
+    let max_top = lines.len().saturating_sub(page);
+    let mut top = max_top;
+
+    let mut console = CONSOLE.lock().unwrap();
+
+    loop {
+        console.clear();
+        console.put_str(0, 0, "Message history", constants::STATUS_COLOR);
+        for (y, (line, color)) in lines.iter().skip(top).take(page).enumerate() {
+            console.put_str(0, y as i32 + 1, line, *color);
+        }
+        console.put_str(
+            0,
+            height - 1,
+            "Arrows, PgUp/PgDn, Home/End: scroll. Esc: return.",
+            constants::STATUS_COLOR,
+        );
+        console.flush();
+
+        if console.window_closed() {
+            return;
+        }
+
+        match console.wait_for_keypress(true).code {
+            KeyCode::Up => top = top.saturating_sub(1),
+            KeyCode::Down => top = (top + 1).min(max_top),
+            KeyCode::PageUp => top = top.saturating_sub(page),
+            KeyCode::PageDown => top = (top + page).min(max_top),
+            KeyCode::Home => top = 0,
+            KeyCode::End => top = max_top,
+            KeyCode::Escape => return,
+            _ => (),
+        }
+    }
+}
+
+/// An entry in a lettered list shown by `select_from_list`.
+#[derive(Clone, Debug)]
+pub struct ListEntry {
+    pub c: char,
+    pub color: Color,
+    pub text: String,
+}
+
+impl ListEntry {
+    /// Describes an item stack along with its equipment modifiers and weight.
+    pub fn from_stack(stack: &ItemStack) -> ListEntry {
+        let modifiers = stack.item().modifiers();
+        let text = if modifiers.is_empty() {
+            format!("{}, {}", stack, format_weight(stack.weight()))
+        } else {
+            format!(
+                "{} [{}], {}",
+                stack,
+                modifiers,
+                format_weight(stack.weight())
+            )
+        };
+
+        ListEntry {
+            c: stack.draw_c(),
+            color: stack.draw_color(),
+            text,
+        }
+    }
+}
+
+/// Shows `title` above a lettered list of `entries` and waits for the player to choose one.
+/// Returns the index of the chosen entry, or `None` if the player cancels.
+pub fn select_from_list(title: &str, entries: &[ListEntry]) -> Option<usize> {
+    let mut console = CONSOLE.lock().unwrap();
+    let height = GAMEDATA.read().unwrap().console_settings.height();
+
+    draw_list(&mut console, title, entries);
+    console.put_str(
+        0,
+        height - 1,
+        "Press a letter to choose. Esc: cancel.",
+        constants::STATUS_COLOR,
+    );
+    console.flush();
+
+    if console.window_closed() {
+        return None;
+    }
+
+    let key = console.wait_for_keypress(true);
+    match LIST_LETTERS.find(key.printable) {
+        Some(index) if key.code == KeyCode::Char && index < entries.len() => Some(index),
+        _ => None,
+    }
+}
+
+/// Shows `title` above a lettered list of the items in `stash` and waits for the player to choose
+/// one. Returns the index of the chosen stack, or `None` if the player cancels.
+pub fn select_item(title: &str, stash: &ItemStash) -> Option<usize> {
+    select_from_list(title, &stash_entries(stash))
+}
+
+/// Shows `title` above a list of the items in `stash` and their total weight, until a key is
+/// pressed.
+pub fn show_items(title: &str, stash: &ItemStash) {
+    let mut console = CONSOLE.lock().unwrap();
+    let height = GAMEDATA.read().unwrap().console_settings.height();
+
+    draw_list(&mut console, title, &stash_entries(stash));
+    console.put_str(
+        0,
+        height - 2,
+        &format!("Total weight: {}", format_weight(stash.weight())),
+        constants::STATUS_COLOR,
+    );
+    console.put_str(0, height - 1, "Press any key.", constants::STATUS_COLOR);
+    console.flush();
+
+    if !console.window_closed() {
+        console.wait_for_keypress(true);
+    }
+}
+
+/// Shows every equipment slot along with what is equipped in it, until a key is pressed.
+pub fn show_equipment(equipment: &Equipment) {
+    let mut console = CONSOLE.lock().unwrap();
+    let height = GAMEDATA.read().unwrap().console_settings.height();
+
+    console.clear();
+    console.put_str(0, 0, "Equipment", constants::STATUS_COLOR);
+
+    for (slot, y) in EquipSlot::all().iter().zip(2..) {
+        console.put_str(0, y, &format!("{}:", slot), constants::STATUS_COLOR);
+        match equipment.get(*slot) {
+            Some(item) => {
+                let entry = ListEntry::from_stack(&ItemStack::new(item.clone(), 1));
+                console.draw_char(8, y, entry.c, entry.color);
+                console.put_str(10, y, &entry.text, constants::STATUS_COLOR);
+            }
+            None => console.put_str(8, y, "-", constants::STATUS_COLOR),
+        }
+    }
+
+    console.put_str(
+        0,
+        height - 2,
+        &format!("Total: {}", equipment.modifiers()),
+        constants::STATUS_COLOR,
+    );
+    console.put_str(0, height - 1, "Press any key.", constants::STATUS_COLOR);
+    console.flush();
+
+    if !console.window_closed() {
+        console.wait_for_keypress(true);
+    }
+}
+
+// Letters used to choose from lists, in order.
+const LIST_LETTERS: &str = "abcdefghijklmnopqrstuvwxyz";
+
+// Returns list entries for the items in `stash`.
+fn stash_entries(stash: &ItemStash) -> Vec<ListEntry> {
+    stash.iter().map(ListEntry::from_stack).collect()
+}
+
+// Clears the screen and draws `title` above a lettered list of `entries`.
+fn draw_list(console: &mut DrawConsole, title: &str, entries: &[ListEntry]) {
+    console.clear();
+    console.put_str(0, 0, title, constants::STATUS_COLOR);
+
+    for ((letter, entry), y) in LIST_LETTERS.chars().zip(entries).zip(2..) {
+        console.draw_char(0, y, letter, constants::STATUS_COLOR);
+        console.put_str(1, y, ")", constants::STATUS_COLOR);
+        console.draw_char(3, y, entry.c, entry.color);
+        console.put_str(5, y, &entry.text, constants::STATUS_COLOR);
+    }
+}
+
+// Formats a weight given in grams.
+fn format_weight(grams: f32) -> String {
+    format!("{:.1} kg", grams / 1000.0)
+}
+
+// Wraps messages with their turn stamps into lines of at most `width` characters.
+fn wrap_messages(messages: &[Message], width: usize) -> Vec<(String, Color)> {
+    messages
+        .iter()
+        .flat_map(|message| {
+            let color = message.color();
+            wrap(&message.to_string(), width)
+                .into_iter()
+                .map(move |line| (line, color))
+        })
+        .collect()
+}
+
+/// Shows the dungeon as the player last saw it and waits for a keypress before the game ends.
+pub fn draw_game_over(dungeon: &Dungeon) {
+    draw_all(dungeon);
+
+    let mut console = CONSOLE.lock().unwrap();
+    // Draw below the game view, out of the way of the messages.
+    let y = GAMEDATA.read().unwrap().ui_settings.game_height as i32 + 1;
+
+    console.put_str(0, y, "You have died.", constants::MESSAGE_BAD_COLOR);
+    console.put_str(0, y + 1, "Press any key.", constants::STATUS_COLOR);
+    console.flush();
+
+    if !console.window_closed() {
+        console.wait_for_keypress(true);
+    }
+}
+
 pub fn draw_game(dungeon: &Dungeon) {
     let mut console = CONSOLE.lock().unwrap();
 
@@ -111,8 +443,9 @@ pub fn draw_game(dungeon: &Dungeon) {
                 // Tile currently visible.
 
                 // Common case is that we draw a tile, so initialize with tile's values.
-                draw_c = tile.info.draw_c();
-                draw_color = tile.info.draw_color();
+                let (c, color) = shade_tile(dungeon, coord);
+                draw_c = c;
+                draw_color = color;
                 let mut foreground_drawn = false;
 
                 // Draw actor.
@@ -149,3 +482,35 @@ pub fn draw_game(dungeon: &Dungeon) {
         }
     }
 }
+
+// Returns the glyph and color of the tile at `coord`. Higher ground is drawn brighter and lower
+// ground darker. Features are drawn over the tile, with deeper liquids darker and chasms dark.
+fn shade_tile(dungeon: &Dungeon, coord: Coord) -> (char, Color) {
+    let tile = &dungeon[coord];
+
+    let (c, color, factor) = match tile.feature {
+        Some(ref feature) if feature.info.fall => {
+            (feature.info.draw_c(), feature.info.draw_color(), 0.5)
+        }
+        Some(ref feature) => (
+            feature.info.draw_c(),
+            feature.info.draw_color(),
+            (1.2 - 0.1 * feature.amount as f32).clamp(0.5, 1.0),
+        ),
+        None => (
+            tile.info.draw_c(),
+            tile.info.draw_color(),
+            (1.0 + 0.1 * tile.height as f32).clamp(0.4, 1.4),
+        ),
+    };
+
+    let scale = |value: u8| (f32::from(value) * factor).min(255.0) as u8;
+    (
+        c,
+        Color {
+            r: scale(color.r),
+            g: scale(color.g),
+            b: scale(color.b),
+        },
+    )
+}
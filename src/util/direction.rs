@@ -19,6 +19,15 @@ pub enum CompassDirection {
     SW,
 }
 
+impl CompassDirection {
+    /// Returns all eight compass directions.
+    pub fn all() -> [CompassDirection; 8] {
+        use self::CompassDirection::*;
+
+        [W, N, E, S, NW, NE, SE, SW]
+    }
+}
+
 impl Direction for CompassDirection {
     fn unit_vec(&self) -> (i32, i32) {
         use self::CompassDirection::*;
@@ -66,6 +75,15 @@ pub enum CardinalDirection {
     S,
 }
 
+impl CardinalDirection {
+    /// Returns all four orthogonal directions.
+    pub fn all() -> [CardinalDirection; 4] {
+        use self::CardinalDirection::*;
+
+        [W, N, E, S]
+    }
+}
+
 impl Direction for CardinalDirection {
     fn unit_vec(&self) -> (i32, i32) {
         use self::CardinalDirection::*;
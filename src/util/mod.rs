@@ -9,6 +9,7 @@ pub mod math;
 pub mod convert;
 pub mod direction;
 pub mod file;
+pub mod noise;
 pub mod rand;
 pub mod rectangle;
 pub mod string;
@@ -0,0 +1,82 @@
+//! Value noise for heightmaps.
+
+use crate::util::rand::rand_int;
+
+/// Random values on a coarse lattice, smoothly interpolated in between. Each octave adds a finer
+/// lattice at half the weight of the previous one.
+pub struct Noise {
+    octaves: Vec<Lattice>,
+}
+
+impl Noise {
+    /// Returns noise covering a `width` by `height` grid, with lattice points `scale` tiles apart
+    /// in the coarsest octave.
+    pub fn new(width: usize, height: usize, scale: usize, num_octaves: usize) -> Noise {
+        let mut octaves = Vec::with_capacity(num_octaves);
+        let mut scale = scale.max(1);
+
+        for _ in 0..num_octaves.max(1) {
+            octaves.push(Lattice::new(width, height, scale));
+            scale = (scale / 2).max(1);
+        }
+
+        Noise { octaves }
+    }
+
+    /// Returns the noise at (`x`, `y`), in the range `[0, 1]`.
+    pub fn get(&self, x: usize, y: usize) -> f64 {
+        let mut total = 0.0;
+        let mut weight = 1.0;
+        let mut total_weight = 0.0;
+
+        for lattice in &self.octaves {
+            total += lattice.get(x, y) * weight;
+            total_weight += weight;
+            weight /= 2.0;
+        }
+
+        total / total_weight
+    }
+}
+
+struct Lattice {
+    values: Vec<f64>,
+    cols: usize,
+    scale: usize,
+}
+
+impl Lattice {
+    fn new(width: usize, height: usize, scale: usize) -> Lattice {
+        let cols = width / scale + 2;
+        let rows = height / scale + 2;
+        let values = (0..cols * rows)
+            .map(|_| f64::from(rand_int(0, 1000)) / 1000.0)
+            .collect();
+
+        Lattice {
+            values,
+            cols,
+            scale,
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> f64 {
+        let (col, row) = (x / self.scale, y / self.scale);
+        let tx = smooth((x % self.scale) as f64 / self.scale as f64);
+        let ty = smooth((y % self.scale) as f64 / self.scale as f64);
+
+        let value = |col: usize, row: usize| self.values[row * self.cols + col];
+        let top = lerp(value(col, row), value(col + 1, row), tx);
+        let bottom = lerp(value(col, row + 1), value(col + 1, row + 1), tx);
+
+        lerp(top, bottom, ty)
+    }
+}
+
+fn smooth(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
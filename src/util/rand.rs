@@ -1,12 +1,112 @@
 //! Random number utility functions.
+//!
+//! All randomness in the game is drawn from a single seeded generator owned by `GameData`, so that
+//! a given seed always reproduces the same world.
 
+use crate::GAMEDATA;
 use num::rational::Ratio;
 use num::{Bounded, Integer};
-use rand;
 use rand::distributions::uniform::SampleUniform;
 use rand::seq::SliceRandom;
-use rand::Rng;
-use std::fmt::Display;
+use rand::{self, Rng, RngCore, SeedableRng};
+use rand_pcg::Pcg32;
+use std::fmt::{self, Display};
+
+/// Seedable random number generator used for all game logic.
+///
+/// The underlying algorithm is fixed so that seeds are reproducible across platforms and builds.
+/// Every number is drawn through `next_u32`, which counts its steps so that the state of the
+/// generator can be saved as its seed and step count.
+#[derive(Clone)]
+pub struct GameRng {
+    seed: u64,
+    steps: u64,
+    rng: Pcg32,
+}
+
+impl GameRng {
+    /// Creates a new generator from `seed`.
+    pub fn new(seed: u64) -> GameRng {
+        GameRng {
+            seed,
+            steps: 0,
+            rng: Pcg32::seed_from_u64(seed),
+        }
+    }
+
+    /// Recreates the generator from `seed` in the state it was in after `steps` steps.
+    pub fn with_steps(seed: u64, steps: u64) -> GameRng {
+        let mut rng = Self::new(seed);
+        for _ in 0..steps {
+            rng.next_u32();
+        }
+
+        rng
+    }
+
+    /// Creates a new generator from a random seed.
+    pub fn from_entropy() -> GameRng {
+        Self::new(rand::thread_rng().gen())
+    }
+
+    /// Returns the seed this generator was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the number of steps taken since the generator was seeded.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.steps += 1;
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = u64::from(self.next_u32());
+        let high = u64::from(self.next_u32());
+        (high << 32) | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for GameRng {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "GameRng {{ seed: {}, steps: {} }}",
+            self.seed, self.steps
+        )
+    }
+}
+
+/// Runs `f` with exclusive access to the game-wide random number generator.
+///
+/// # Panics
+/// If called while the current thread holds a write lock on `GAMEDATA`.
+pub fn with_rng<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut GameRng) -> R,
+{
+    let game_data = GAMEDATA.read().unwrap();
+    let mut rng = game_data.rng().lock().unwrap();
+    f(&mut rng)
+}
 
 /// Trait allowing access to random elements and/or indices in implementing containers.
 pub trait Choose<T> {
@@ -22,7 +122,7 @@ pub trait Choose<T> {
 
 impl<T> Choose<T> for Vec<T> {
     fn choose(&self) -> Option<&T> {
-        self.as_slice().choose(&mut rand::thread_rng())
+        self.choose_index().map(|i| &self[i])
     }
 
     fn choose_index(&self) -> Option<usize> {
@@ -42,12 +142,17 @@ impl<T> Choose<T> for Vec<T> {
     }
 }
 
+/// Shuffles `slice` into a random order.
+pub fn shuffle<T>(slice: &mut [T]) {
+    with_rng(|rng| slice.shuffle(rng))
+}
+
 /// Returns a random Integer in the range `[x, y]` inclusive.
 pub fn rand_int<T>(x: T, y: T) -> T
 where
     T: Integer + SampleUniform,
 {
-    rand::thread_rng().gen_range(x, y + T::one())
+    with_rng(|rng| rng.gen_range(x, y + T::one()))
 }
 
 /// Returns a random Ratio in the inclusive range `[x, y]` with the given denominator.
@@ -66,7 +171,7 @@ pub fn dice<T>(x: T, y: T) -> bool
 where
     T: Copy + Display + Integer + SampleUniform,
 {
-    debug_assert!(x <= y, format!("Assert failed: dice({}, {})", x, y));
+    debug_assert!(x <= y, "Assert failed: dice({}, {})", x, y);
     rand_int(T::one(), y) <= x
 }
 
@@ -98,4 +203,19 @@ mod tests {
             assert!(!dice(0, rand_int(1, 100)));
         }
     }
+
+    #[test]
+    fn test_game_rng_seed() {
+        let mut rng1 = GameRng::new(1234);
+        let mut rng2 = GameRng::new(1234);
+        let mut rng3 = GameRng::new(4321);
+
+        let seq1: Vec<u32> = (0..100).map(|_| rng1.gen_range(0, 1000)).collect();
+        let seq2: Vec<u32> = (0..100).map(|_| rng2.gen_range(0, 1000)).collect();
+        let seq3: Vec<u32> = (0..100).map(|_| rng3.gen_range(0, 1000)).collect();
+
+        assert_eq!(rng1.seed(), 1234);
+        assert_eq!(seq1, seq2);
+        assert_ne!(seq1, seq3);
+    }
 }
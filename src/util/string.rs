@@ -17,6 +17,47 @@ pub fn capitalize(s: &str) -> String {
     }
 }
 
+/// Splits `s` into lines no longer than `width` characters, breaking at spaces where possible.
+/// Words longer than `width` are split across lines.
+pub fn wrap(s: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_len = 0;
+
+    for word in s.split_whitespace() {
+        let mut chars: Vec<char> = word.chars().collect();
+
+        if line_len > 0 && line_len + 1 + chars.len() > width {
+            lines.push(line);
+            line = String::new();
+            line_len = 0;
+        }
+
+        // Break up words that don't fit on a line of their own.
+        while chars.len() > width {
+            if line_len > 0 {
+                lines.push(line);
+                line = String::new();
+                line_len = 0;
+            }
+            lines.push(chars.drain(..width).collect());
+        }
+
+        if line_len > 0 {
+            line.push(' ');
+            line_len += 1;
+        }
+        line_len += chars.len();
+        line.extend(chars);
+    }
+
+    if line_len > 0 || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -29,4 +70,13 @@ mod tests {
         assert_eq!("Yabba dabba", capitalize("yabba dabba"));
         assert_eq!("Doo", capitalize("Doo"));
     }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(vec![""], wrap("", 5));
+        assert_eq!(vec!["one", "two"], wrap("one two", 5));
+        assert_eq!(vec!["one two"], wrap("one two", 7));
+        assert_eq!(vec!["a", "abcde", "fg b"], wrap("a abcdefg b", 5));
+        assert_eq!(vec!["a b c"], wrap("  a  b c ", 5));
+    }
 }